@@ -1,6 +1,5 @@
 use forceatlas2::*;
 use plotters::prelude::*;
-use std::io::BufRead;
 
 const SIZE: (u32, u32) = (1024, 1024);
 
@@ -10,48 +9,22 @@ const ANIM_MODE: bool = false;
 fn main() {
 	let file = std::fs::File::open(
 		std::env::args()
-			.skip(1)
-			.next()
+			.nth(1)
 			.expect("Usage: csv_import <csv_file>"),
 	)
 	.expect("Cannot open file");
 
-	let mut nodes = 0usize;
-	let mut edges = Vec::<(usize, usize)>::new();
-	for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
-		let line = line.expect("Error reading CSV");
-		let mut columns = line.split(&[' ', '\t', ',', ';'][..]);
-		if let (Some(n1), Some(n2)) = (columns.next(), columns.skip_while(|&c| c.is_empty()).next())
-		{
-			if let (Ok(n1), Ok(n2)) = (n1.parse(), n2.parse()) {
-				if n1 > nodes {
-					nodes = n1;
-				}
-				if n2 > nodes {
-					nodes = n2;
-				}
-				if n1 != n2 {
-					edges.push(if n1 < n2 { (n1, n2) } else { (n2, n1) });
-				}
-			} else {
-				eprintln!("Ignored line {} has bad number format", i);
-			}
-		} else {
-			eprintln!("Ignored line {} has <2 columns", i);
-		}
-	}
-	nodes += 1;
-
-	println!("Nodes: {}", nodes);
-
-	let mut layout = Layout::<f32>::from_graph(
-		edges,
-		Nodes::Degree(nodes),
-		None,
+	let (mut layout, labels) = io::edgelist::read_layout::<f32, _>(
+		std::io::BufReader::new(file),
+		&io::edgelist::Options {
+			header: true,
+			..Default::default()
+		},
 		Settings {
 			#[cfg(feature = "barnes_hut")]
 			barnes_hut: None,
 			chunk_size: None,
+			clusters: None,
 			dimensions: 2,
 			dissuade_hubs: false,
 			ka: 0.01,
@@ -62,7 +35,10 @@ fn main() {
 			prevent_overlapping: None,
 			strong_gravity: false,
 		},
-	);
+	)
+	.expect("Cannot read CSV");
+
+	println!("Nodes: {}", labels.len());
 
 	eprintln!("Computing layout...");
 	for i in 0..ITERATIONS {
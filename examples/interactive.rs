@@ -1,5 +1,4 @@
 use forceatlas2::*;
-use itertools::izip;
 use plotters::prelude::*;
 use sdl2;
 use sdl2::{
@@ -8,9 +7,7 @@ use sdl2::{
 	pixels::PixelFormatEnum,
 };
 use std::{
-	io::BufRead,
-	ops::AddAssign,
-	sync::{Arc, RwLock},
+	sync::{Arc, Mutex, RwLock},
 	thread,
 	time::Duration,
 };
@@ -20,6 +17,37 @@ const SIZE: (u32, u32) = (800, 800);
 const FRAMEDUR: u64 = 30;
 type T = f32;
 
+/// Squared swinging and traction of each node, computed by the worker for the node colors
+type Motion = Arc<Mutex<Vec<(T, T)>>>;
+
+/// Records the motion after each iteration, as frames only contain the positions
+struct MotionObserver(Motion);
+
+impl Observer<T> for MotionObserver {
+	fn after(&mut self, phase: Phase, layout: &Layout<T>) -> Control<T> {
+		if phase == Phase::ApplyForces {
+			let mut motion = self.0.lock().unwrap();
+			motion.clear();
+			motion.extend(layout.speeds.iter().zip(layout.old_speeds.iter()).map(
+				|(speed, old_speed)| {
+					(
+						(speed[0] - old_speed[0]).powi(2) + (speed[1] - old_speed[1]).powi(2),
+						(speed[0] + old_speed[0]).powi(2) + (speed[1] + old_speed[1]).powi(2),
+					)
+				},
+			));
+		}
+		Control::Continue
+	}
+}
+
+fn new_layout(edges: &[Edge], nodes: usize, settings: &Settings<T>, motion: &Motion) -> Layout<T> {
+	let mut layout =
+		Layout::from_graph(edges.to_vec(), Nodes::Degree(nodes), None, settings.clone());
+	layout.add_observer(Box::new(MotionObserver(motion.clone())));
+	layout
+}
+
 pub fn run(image: Arc<RwLock<(u32, u32, Vec<u8>)>>, size: Arc<RwLock<(u32, u32)>>) {
 	let sdl_context = sdl2::init().unwrap();
 	let video_subsystem = sdl_context.video().unwrap();
@@ -85,32 +113,16 @@ fn main() {
 	)
 	.expect("Cannot open file");
 
-	let iters = Arc::new(RwLock::new(0u32));
-	let mut nodes = 0usize;
-	let mut edges = Vec::<(usize, usize)>::new();
-	for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
-		let line = line.expect("Error reading CSV");
-		let mut columns = line.split(&[' ', '\t', ',', ';'][..]);
-		if let (Some(n1), Some(n2)) = (columns.next(), columns.skip_while(|&c| c.is_empty()).next())
-		{
-			if let (Ok(n1), Ok(n2)) = (n1.parse(), n2.parse()) {
-				if n1 > nodes {
-					nodes = n1;
-				}
-				if n2 > nodes {
-					nodes = n2;
-				}
-				if n1 != n2 {
-					edges.push(if n1 < n2 { (n1, n2) } else { (n2, n1) });
-				}
-			} else {
-				eprintln!("Ignored line {} has bad number format", i);
-			}
-		} else {
-			eprintln!("Ignored line {} has <2 columns", i);
-		}
-	}
-	nodes += 1;
+	let graph = io::edgelist::read::<T, _>(
+		std::io::BufReader::new(file),
+		&io::edgelist::Options {
+			header: true,
+			..Default::default()
+		},
+	)
+	.expect("Cannot read CSV");
+	let edges = graph.edges;
+	let nodes = graph.nb_nodes;
 
 	println!("Nodes: {}", nodes);
 
@@ -118,6 +130,7 @@ fn main() {
 		#[cfg(feature = "barnes_hut")]
 		barnes_hut: None,
 		chunk_size: None,
+		clusters: None,
 		dimensions: 2,
 		dissuade_hubs: false,
 		ka: 0.01,
@@ -129,69 +142,39 @@ fn main() {
 		strong_gravity: false,
 	};
 
-	let layout = Arc::new(RwLock::new(Layout::<T>::from_graph(
-		edges.clone(),
-		Nodes::Degree(nodes),
-		None,
-		settings.clone(),
-	)));
+	let motion = Motion::default();
+	let runner = LayoutRunner::spawn(new_layout(&edges, nodes, &settings, &motion));
+	let frames = runner.frame_reader();
 	let size = Arc::new(RwLock::new(SIZE));
 	let image = Arc::new(RwLock::new((
 		SIZE.0,
 		SIZE.1,
 		vec![0u8; SIZE.0 as usize * SIZE.1 as usize * 3],
 	)));
-	let computing = Arc::new(RwLock::new(false));
-	let sleep = Arc::new(RwLock::new(50u64));
 	let draw_links = Arc::new(RwLock::new(DRAW_LINKS));
-	draw_graph(
-		layout.clone(),
-		image.clone(),
-		size.clone(),
-		draw_links.clone(),
-	);
+	draw_graph(&frames.get(), &motion, &image, &size, &draw_links);
 
+	// Draws the frames published by the runner, at most one per `FRAMEDUR`
 	thread::spawn({
 		let image = image.clone();
-		let computing = computing.clone();
-		let layout = layout.clone();
-		let sleep = sleep.clone();
+		let motion = motion.clone();
 		let size = size.clone();
 		let draw_links = draw_links.clone();
 
 		let interval = Duration::from_millis(FRAMEDUR);
-		move || loop {
-			if *computing.read().unwrap() {
-				draw_graph(
-					layout.clone(),
-					image.clone(),
-					size.clone(),
-					draw_links.clone(),
-				);
+		move || {
+			let mut epoch = 0;
+			while let Some(frame) = frames.wait(epoch) {
+				epoch = frame.epoch;
+				draw_graph(&frame, &motion, &image, &size, &draw_links);
 				thread::sleep(interval);
-			} else {
-				thread::sleep(std::time::Duration::from_millis(*sleep.read().unwrap()));
-			}
-		}
-	});
-
-	thread::spawn({
-		let computing = computing.clone();
-		let layout = layout.clone();
-		let iters = iters.clone();
-		move || loop {
-			if *computing.read().unwrap() {
-				layout.write().unwrap().iteration();
-				iters.write().unwrap().add_assign(1);
-			} else {
-				thread::sleep(std::time::Duration::from_millis(*sleep.read().unwrap()));
 			}
 		}
 	});
 
 	thread::spawn(move || run(image, size));
 
-	thread::spawn(move || loop {
+	loop {
 		let cmd = scanrs::scanln();
 		let mut args = cmd.split(' ');
 		match args.next() {
@@ -209,33 +192,30 @@ fn main() {
 				println!("kr <f64>   repulsion");
 				println!("dl         toggle draw links")
 			}
-			Some("s") => {
-				let mut computing = computing.write().unwrap();
-				*computing = !*computing;
-			}
+			Some("s") => runner.send(if runner.frame().running {
+				Command::Pause
+			} else {
+				Command::Start
+			}),
 			Some("cs") => {
 				settings.chunk_size = args.next().map(|chunk_size| chunk_size.parse().unwrap());
-				layout.write().unwrap().set_settings(settings.clone());
+				runner.send(Command::SetSettings(settings.clone()));
 			}
 			Some("ka") => {
 				settings.ka = args.next().unwrap().parse().unwrap();
-				layout.write().unwrap().set_settings(settings.clone());
+				runner.send(Command::SetSettings(settings.clone()));
 			}
 			Some("kg") => {
 				settings.kg = args.next().unwrap().parse().unwrap();
-				layout.write().unwrap().set_settings(settings.clone());
+				runner.send(Command::SetSettings(settings.clone()));
 			}
 			Some("kr") => {
 				settings.kr = args.next().unwrap().parse().unwrap();
-				layout.write().unwrap().set_settings(settings.clone());
-			}
-			Some("r") => {
-				let mut layout = layout.write().unwrap();
-				let mut iters = iters.write().unwrap();
-				*iters = 0;
-				*layout =
-					Layout::from_graph(edges.clone(), Nodes::Degree(nodes), None, settings.clone());
+				runner.send(Command::SetSettings(settings.clone()));
 			}
+			Some("r") => runner.send(Command::Replace(Box::new(new_layout(
+				&edges, nodes, &settings, &motion,
+			)))),
 			Some("p") => println!(
 				"ka={}  kg={}  kr={}  cs={:?}",
 				settings.ka, settings.kg, settings.kr, settings.chunk_size
@@ -244,19 +224,19 @@ fn main() {
 				let mut draw_links = draw_links.write().unwrap();
 				*draw_links = !*draw_links;
 			}
-			Some("i") => println!("{}", iters.read().unwrap()),
+			Some("i") => println!("{}", runner.frame().iteration),
 			_ => println!("Unknown command"),
 		}
-	})
-	.join()
-	.unwrap();
+	}
+	runner.stop();
 }
 
 fn draw_graph(
-	layout: Arc<RwLock<Layout<T>>>,
-	image: Arc<RwLock<(u32, u32, Vec<u8>)>>,
-	size: Arc<RwLock<(u32, u32)>>,
-	draw_links: Arc<RwLock<bool>>,
+	frame: &Frame<T>,
+	motion: &Motion,
+	image: &RwLock<(u32, u32, Vec<u8>)>,
+	size: &RwLock<(u32, u32)>,
+	draw_links: &RwLock<bool>,
 ) {
 	let mut image = image.write().unwrap();
 	{
@@ -271,13 +251,11 @@ fn draw_graph(
 	let root = BitMapBackend::with_buffer(&mut image.2, size).into_drawing_area();
 	root.fill(&WHITE).unwrap();
 
-	let layout = layout.read().unwrap();
-
-	let mut min_v = layout.points.get_clone(0);
+	let mut min_v = frame.points.get_clone(0);
 	let mut max_v = min_v.clone();
 	let min = min_v.as_mut_slice();
 	let max = max_v.as_mut_slice();
-	for pos in layout.points.iter() {
+	for pos in frame.points.iter() {
 		if pos[0] < min[0] {
 			min[0] = pos[0];
 		}
@@ -307,11 +285,11 @@ fn draw_graph(
 		if *draw_links {
 			let link_color = RGBColor(5, 5, 5).mix(0.05);
 
-			for (h1, h2) in layout.edges.iter() {
+			for (h1, h2) in frame.edges.iter() {
 				root.draw(&PathElement::new(
 					vec![
 						{
-							let pos = layout.points.get(*h1);
+							let pos = frame.points.get(*h1);
 							unsafe {
 								(
 									((pos[0] - min[0]) * factor).to_int_unchecked::<i32>(),
@@ -320,7 +298,7 @@ fn draw_graph(
 							}
 						},
 						{
-							let pos = layout.points.get(*h2);
+							let pos = frame.points.get(*h2);
 							unsafe {
 								(
 									((pos[0] - min[0]) * factor).to_int_unchecked::<i32>(),
@@ -336,30 +314,14 @@ fn draw_graph(
 		}
 	}
 
-	let mut max_swinging = 0.0;
-	let mut max_traction = 0.0;
-	layout
-		.speeds
-		.iter()
-		.zip(layout.old_speeds.iter())
-		.for_each(|(speed, old_speed)| {
-			let swinging = (speed[0] - old_speed[0]).powi(2) + (speed[1] - old_speed[1]).powi(2);
-			let traction = (speed[0] + old_speed[0]).powi(2) + (speed[1] + old_speed[1]).powi(2);
-			if swinging > max_swinging {
-				max_swinging = swinging;
-			}
-			if traction > max_traction {
-				max_traction = traction;
-			}
-		});
+	let motion = motion.lock().unwrap();
+	let (max_swinging, max_traction) = motion.iter().fold((0.0, 0.0), |(max_s, max_t), &(s, t)| {
+		(T::max(max_s, s), T::max(max_t, t))
+	});
 
-	for (pos, speed, old_speed) in izip!(
-		layout.points.iter(),
-		layout.speeds.iter(),
-		layout.old_speeds.iter()
-	) {
-		let swinging = (speed[0] - old_speed[0]).powi(2) + (speed[1] - old_speed[1]).powi(2);
-		let traction = (speed[0] + old_speed[0]).powi(2) + (speed[1] + old_speed[1]).powi(2);
+	// Before the first iteration, there is no motion yet
+	for (node, pos) in frame.points.iter().enumerate() {
+		let (swinging, traction) = motion.get(node).copied().unwrap_or_default();
 
 		root.draw(&Circle::new(
 			unsafe {
@@ -379,7 +341,7 @@ fn draw_graph(
 		.unwrap();
 	}
 
-	std::mem::drop(layout);
+	std::mem::drop(motion);
 
 	println!("size:  {:?}", graph_size);
 	println!("scale: {}", factor);
@@ -0,0 +1,61 @@
+//! Reads the positions while another thread computes the layout, without locking it
+//!
+//! Run with `cargo run --release --example publish --features publish`
+
+use forceatlas2::*;
+use rand::Rng;
+use std::{thread, time::Duration};
+
+const EDGES: usize = 20_000;
+const NODES: usize = 5_000;
+const ITERATIONS: u64 = 500;
+type T = f32;
+
+fn main() {
+	let mut rng = rand::thread_rng();
+	let edges = (0..EDGES)
+		.map(|_| {
+			let n1 = rng.gen_range(0..NODES - 1);
+			(n1, rng.gen_range(n1 + 1..NODES))
+		})
+		.collect();
+	let mut layout = Layout::<T>::from_graph(
+		edges,
+		Nodes::Degree(NODES),
+		None,
+		Settings {
+			kr: 0.1,
+			..Default::default()
+		},
+	);
+
+	let reader = layout.publish();
+	let worker = thread::spawn(move || {
+		for _ in 0..ITERATIONS {
+			layout.iteration();
+		}
+	});
+
+	// The layout is owned by the worker: frames are the only way to see it
+	while !worker.is_finished() {
+		print_frame(&reader.load());
+		thread::sleep(Duration::from_millis(200));
+	}
+	print_frame(&reader.load());
+}
+
+fn print_frame(frame: &PointsFrame<T>) {
+	let (mut min, mut max) = ([T::MAX; 2], [T::MIN; 2]);
+	for pos in frame.points.iter() {
+		for i in 0..2 {
+			min[i] = min[i].min(pos[i]);
+			max[i] = max[i].max(pos[i]);
+		}
+	}
+	println!(
+		"iteration {:>4}: {:.1} × {:.1}",
+		frame.epoch,
+		max[0] - min[0],
+		max[1] - min[1]
+	);
+}
@@ -32,6 +32,7 @@ fn main() {
 			#[cfg(feature = "barnes_hut")]
 			barnes_hut: None,
 			chunk_size: None, //Some(256),
+			clusters: None,
 			dimensions: 2,
 			dissuade_hubs: false,
 			ka: 0.5,
@@ -1,4 +1,4 @@
-use crate::{drawer::DrawSettings, T};
+use crate::{drawer::DrawSettings, Graph, T};
 
 use forceatlas2::*;
 use gio::prelude::*;
@@ -7,11 +7,15 @@ use gtk::{
 	traits::{EntryExt, SettingsExt},
 };
 use parking_lot::RwLock;
-use rand::Rng;
 use static_rc::StaticRc;
-use std::{rc::Rc, sync::Arc, thread, time::Duration};
+use std::{
+	rc::Rc,
+	sync::{mpsc, Arc},
+	thread,
+	time::Duration,
+};
 
-const STANDBY_SLEEP: Duration = Duration::from_millis(50);
+/// Minimum time between two frames
 const DRAW_SLEEP: Duration = Duration::from_millis(30);
 
 enum MsgToGtk {
@@ -19,9 +23,11 @@ enum MsgToGtk {
 	Update,
 }
 
-struct MsgFromGtk {
-	redraw: bool,
-	resize: bool,
+enum MsgToDrawer {
+	/// The runner published a new frame
+	Frame,
+	Redraw,
+	Resize,
 }
 
 struct Pixbuf(gdk_pixbuf::Pixbuf);
@@ -32,15 +38,14 @@ unsafe impl Sync for Pixbuf {}
 fn build_ui(
 	app: &gtk::Application,
 	rx: Arc<RwLock<Option<glib::Receiver<MsgToGtk>>>>,
-	tx: Arc<RwLock<MsgFromGtk>>,
-	compute: Arc<RwLock<bool>>,
-	layout: Arc<RwLock<Layout<T>>>,
+	tx: mpsc::Sender<MsgToDrawer>,
+	commands: mpsc::Sender<Command<T>>,
+	frames: FrameReader<T>,
+	graph: Arc<Graph>,
 	settings: Arc<RwLock<Settings<T>>>,
 	pixbuf: Arc<RwLock<Option<Pixbuf>>>,
 	draw_settings: Arc<RwLock<DrawSettings>>,
 	zoom: Arc<RwLock<T>>,
-	d3: Arc<RwLock<bool>>,
-	nb_iters: Arc<RwLock<usize>>,
 ) {
 	let builder = gtk::Builder::new();
 	builder.add_from_string(include_str!("gui.glade")).unwrap();
@@ -133,11 +138,10 @@ fn build_ui(
 	zoom_input.set_text(&zoom.read().to_string());
 
 	{
-		let layout = layout.read();
 		let nb_nodes_disp: gtk::Label = builder.object("nb_nodes").unwrap();
 		let nb_edges_disp: gtk::Label = builder.object("nb_edges").unwrap();
-		nb_nodes_disp.set_text(&layout.masses.len().to_string());
-		nb_edges_disp.set_text(&layout.edges.len().to_string());
+		nb_nodes_disp.set_text(&graph.nb_nodes.to_string());
+		nb_edges_disp.set_text(&graph.edges.len().to_string());
 	}
 
 	let graph_area = StaticRc::<gtk::Image, 1, 1>::new(graph_area);
@@ -200,23 +204,23 @@ fn build_ui(
 					"KP_0" | "0" => zoom_input.set_text("1"),
 					"KP_2" | "2" => {
 						draw_settings.write().camera_angle.0 -= 0.1;
-						tx.write().redraw = true;
+						tx.send(MsgToDrawer::Redraw).ok();
 					}
 					"KP_4" | "4" => {
 						draw_settings.write().camera_angle.1 -= 0.1;
-						tx.write().redraw = true;
+						tx.send(MsgToDrawer::Redraw).ok();
 					}
 					"KP_5" | "5" => {
 						draw_settings.write().camera_angle = (0.0, 0.0);
-						tx.write().redraw = true;
+						tx.send(MsgToDrawer::Redraw).ok();
 					}
 					"KP_6" | "6" => {
 						draw_settings.write().camera_angle.1 += 0.1;
-						tx.write().redraw = true;
+						tx.send(MsgToDrawer::Redraw).ok();
 					}
 					"KP_8" | "8" => {
 						draw_settings.write().camera_angle.0 += 0.1;
-						tx.write().redraw = true;
+						tx.send(MsgToDrawer::Redraw).ok();
 					}
 					"Right" => {
 						graph_adj.0.set_value(graph_adj.0.value() + 16.0);
@@ -246,27 +250,25 @@ fn build_ui(
 	let graph_viewport = StaticRc::<gtk::Viewport, 1, 1>::new(graph_viewport);
 
 	compute_button.connect_toggled({
-		let tx = tx.clone();
+		let commands = commands.clone();
 		move |bt| {
-			let mut compute = compute.write();
-			if *compute {
-				tx.write().redraw = true;
-			}
-			*compute = bt.is_active();
+			commands
+				.send(if bt.is_active() {
+					Command::Start
+				} else {
+					Command::Pause
+				})
+				.ok();
 		}
 	});
 
 	reset_button.connect_clicked({
-		let layout = layout.clone();
-		let tx = tx.clone();
-		let nb_iters = nb_iters.clone();
+		let commands = commands.clone();
+		let graph = graph.clone();
+		let settings = settings.clone();
 		move |_| {
-			let mut rng = rand::thread_rng();
-			let mut layout = layout.write();
-			layout.old_speeds.points.fill(0.0);
-			layout.points.points.fill_with(|| rng.gen_range(-1.0..1.0));
-			tx.write().redraw = true;
-			*nb_iters.write() = 0;
+			let layout = graph.layout(settings.read().clone());
+			commands.send(Command::Replace(Box::new(layout))).ok();
 		}
 	});
 
@@ -328,7 +330,7 @@ fn build_ui(
 	});
 
 	chunk_size_input.connect_changed({
-		let layout = layout.clone();
+		let commands = commands.clone();
 		let settings = settings.clone();
 		move |entry| {
 			if let Ok(chunk_size) = entry.text().parse() {
@@ -339,8 +341,7 @@ fn build_ui(
 				} else {
 					Some(chunk_size)
 				};
-				let mut layout = layout.write();
-				layout.set_settings(settings.clone());
+				commands.send(Command::SetSettings(settings.clone())).ok();
 			} else {
 				entry.set_secondary_icon_name(Some("emblem-unreadable"));
 			}
@@ -348,15 +349,14 @@ fn build_ui(
 	});
 
 	ka_input.connect_changed({
-		let layout = layout.clone();
+		let commands = commands.clone();
 		let settings = settings.clone();
 		move |entry| {
 			if let Ok(ka) = entry.text().parse() {
 				entry.set_secondary_icon_name(None);
 				let mut settings = settings.write();
 				settings.ka = ka;
-				let mut layout = layout.write();
-				layout.set_settings(settings.clone());
+				commands.send(Command::SetSettings(settings.clone())).ok();
 			} else {
 				entry.set_secondary_icon_name(Some("emblem-unreadable"));
 			}
@@ -364,15 +364,14 @@ fn build_ui(
 	});
 
 	kg_input.connect_changed({
-		let layout = layout.clone();
+		let commands = commands.clone();
 		let settings = settings.clone();
 		move |entry| {
 			if let Ok(kg) = entry.text().parse() {
 				entry.set_secondary_icon_name(None);
 				let mut settings = settings.write();
 				settings.kg = kg;
-				let mut layout = layout.write();
-				layout.set_settings(settings.clone());
+				commands.send(Command::SetSettings(settings.clone())).ok();
 			} else {
 				entry.set_secondary_icon_name(Some("emblem-unreadable"));
 			}
@@ -380,15 +379,14 @@ fn build_ui(
 	});
 
 	kr_input.connect_changed({
-		let layout = layout.clone();
+		let commands = commands.clone();
 		let settings = settings.clone();
 		move |entry| {
 			if let Ok(kr) = entry.text().parse() {
 				entry.set_secondary_icon_name(None);
 				let mut settings = settings.write();
 				settings.kr = kr;
-				let mut layout = layout.write();
-				layout.set_settings(settings.clone());
+				commands.send(Command::SetSettings(settings.clone())).ok();
 			} else {
 				entry.set_secondary_icon_name(Some("emblem-unreadable"));
 			}
@@ -396,15 +394,14 @@ fn build_ui(
 	});
 
 	speed_input.connect_changed({
-		let layout = layout.clone();
+		let commands = commands.clone();
 		let settings = settings.clone();
 		move |entry| {
 			if let Ok(speed) = entry.text().parse() {
 				entry.set_secondary_icon_name(None);
 				let mut settings = settings.write();
 				settings.speed = speed;
-				let mut layout = layout.write();
-				layout.set_settings(settings.clone());
+				commands.send(Command::SetSettings(settings.clone())).ok();
 			} else {
 				entry.set_secondary_icon_name(Some("emblem-unreadable"));
 			}
@@ -412,7 +409,7 @@ fn build_ui(
 	});
 
 	barneshut_input.connect_toggled({
-		let layout = layout.clone();
+		let commands = commands.clone();
 		let settings = settings.clone();
 		let barneshut_theta_input = barneshut_theta_input.clone();
 		move |entry| {
@@ -421,22 +418,20 @@ fn build_ui(
 					barneshut_theta_input.set_secondary_icon_name(None);
 					let mut settings = settings.write();
 					settings.barnes_hut = Some(theta);
-					let mut layout = layout.write();
-					layout.set_settings(settings.clone());
+					commands.send(Command::SetSettings(settings.clone())).ok();
 				} else {
 					barneshut_theta_input.set_secondary_icon_name(Some("emblem-unreadable"));
 				}
 			} else {
 				let mut settings = settings.write();
 				settings.barnes_hut = None;
-				let mut layout = layout.write();
-				layout.set_settings(settings.clone());
+				commands.send(Command::SetSettings(settings.clone())).ok();
 			}
 		}
 	});
 
 	barneshut_theta_input.connect_changed({
-		let layout = layout.clone();
+		let commands = commands.clone();
 		let settings = settings.clone();
 		move |entry| {
 			if let Ok(theta) = entry.text().parse() {
@@ -444,8 +439,7 @@ fn build_ui(
 				if barneshut_input.is_active() {
 					let mut settings = settings.write();
 					settings.barnes_hut = Some(theta);
-					let mut layout = layout.write();
-					layout.set_settings(settings.clone());
+					commands.send(Command::SetSettings(settings.clone())).ok();
 				}
 			} else {
 				entry.set_secondary_icon_name(Some("emblem-unreadable"));
@@ -458,7 +452,7 @@ fn build_ui(
 		let draw_settings = draw_settings.clone();
 		move |draw_edges_input| {
 			draw_settings.write().draw_edges = draw_edges_input.is_active();
-			tx.write().redraw = true;
+			tx.send(MsgToDrawer::Redraw).ok();
 		}
 	});
 
@@ -473,7 +467,7 @@ fn build_ui(
 				(c.blue() * 255.) as u8,
 				(c.alpha() * 255.) as u8,
 			);
-			tx.write().redraw = true;
+			tx.send(MsgToDrawer::Redraw).ok();
 		}
 	});
 
@@ -482,7 +476,7 @@ fn build_ui(
 		let draw_settings = draw_settings.clone();
 		move |draw_nodes_input| {
 			draw_settings.write().draw_nodes = draw_nodes_input.is_active();
-			tx.write().redraw = true;
+			tx.send(MsgToDrawer::Redraw).ok();
 		}
 	});
 
@@ -496,7 +490,7 @@ fn build_ui(
 				(c.green() * 255.) as u8,
 				(c.blue() * 255.) as u8,
 			);
-			tx.write().redraw = true;
+			tx.send(MsgToDrawer::Redraw).ok();
 		}
 	});
 
@@ -508,7 +502,7 @@ fn build_ui(
 				entry.set_secondary_icon_name(None);
 				let mut draw_settings = draw_settings.write();
 				if draw_settings.node_radius != v {
-					tx.write().redraw = true;
+					tx.send(MsgToDrawer::Redraw).ok();
 				}
 				draw_settings.node_radius = v;
 			} else {
@@ -526,7 +520,7 @@ fn build_ui(
 				(c.green() * 255.) as u8,
 				(c.blue() * 255.) as u8,
 			);
-			tx.write().redraw = true;
+			tx.send(MsgToDrawer::Redraw).ok();
 		}
 	});
 
@@ -550,7 +544,7 @@ fn build_ui(
 						(graph_viewport.allocated_height() as T * *zoom) as i32,
 					)
 					.map(Pixbuf);
-					tx.write().redraw = true;
+					tx.send(MsgToDrawer::Redraw).ok();
 					return;
 				}
 			}
@@ -558,25 +552,11 @@ fn build_ui(
 		}
 	});
 
-	d3_input.connect_toggled({
-		let tx = tx.clone();
-		let nb_iters = nb_iters.clone();
-		move |d3_input| {
-			let mut d3 = d3.write();
-			let mut layout = layout.write();
-			let mut settings = settings.write();
-			*d3 = d3_input.is_active();
-			settings.dimensions = if *d3 { 3 } else { 2 };
-			*layout = Layout::from_graph(
-				layout.edges.clone(),
-				Nodes::Degree(layout.masses.len()),
-				layout.weights.clone(),
-				settings.clone(),
-			);
-			*nb_iters.write() = 0;
-			tx.write().redraw = true;
-			drop(layout);
-		}
+	d3_input.connect_toggled(move |d3_input| {
+		let mut settings = settings.write();
+		settings.dimensions = if d3_input.is_active() { 3 } else { 2 };
+		let layout = graph.layout(settings.clone());
+		commands.send(Command::Replace(Box::new(layout))).ok();
 	});
 
 	let resize_handler = {
@@ -584,7 +564,6 @@ fn build_ui(
 		let tx = tx.clone();
 		move || {
 			let mut pixbuf = pixbuf.write();
-			tx.write().redraw = true;
 			let zoom = zoom.read();
 			*pixbuf = gdk_pixbuf::Pixbuf::new(
 				gdk_pixbuf::Colorspace::Rgb,
@@ -594,12 +573,13 @@ fn build_ui(
 				(graph_viewport.allocated_height() as T * *zoom) as i32,
 			)
 			.map(Pixbuf);
+			tx.send(MsgToDrawer::Redraw).ok();
 		}
 	};
 
 	window.connect_configure_event({
 		move |_, _| {
-			tx.write().resize = true;
+			tx.send(MsgToDrawer::Resize).ok();
 			true
 		}
 	});
@@ -611,7 +591,7 @@ fn build_ui(
 					if let Some(pixbuf) = pixbuf.read().as_ref() {
 						graph_area.set_from_pixbuf(Some(&pixbuf.0));
 					}
-					nb_iters_disp.set_text(&nb_iters.read().to_string());
+					nb_iters_disp.set_text(&frames.get().iteration.to_string());
 				}
 				MsgToGtk::Resize => resize_handler(),
 			}
@@ -623,10 +603,10 @@ fn build_ui(
 }
 
 pub fn run(
-	compute: Arc<RwLock<bool>>,
-	layout: Arc<RwLock<Layout<T>>>,
+	commands: mpsc::Sender<Command<T>>,
+	frames: FrameReader<T>,
+	graph: Arc<Graph>,
 	settings: Arc<RwLock<Settings<T>>>,
-	nb_iters: Arc<RwLock<usize>>,
 ) {
 	let application = gtk::Application::new(
 		Some("org.framagit.ZettaScript.forceatlas2.examples.viz"),
@@ -635,10 +615,7 @@ pub fn run(
 
 	let (tx, rx) = glib::MainContext::sync_channel(glib::PRIORITY_DEFAULT, 4);
 	let rx = Arc::new(RwLock::new(Some(rx)));
-	let msg_from_gtk = Arc::new(RwLock::new(MsgFromGtk {
-		redraw: true,
-		resize: false,
-	}));
+	let (drawer_tx, drawer_rx) = mpsc::channel();
 	let pixbuf = Arc::new(RwLock::new(None));
 	let draw_settings = Arc::new(RwLock::new(DrawSettings {
 		draw_edges: true,
@@ -650,94 +627,63 @@ pub fn run(
 		camera_angle: (0.0, 0.0),
 	}));
 	let zoom = Arc::new(RwLock::new(1.0));
-	let d3 = Arc::new(RwLock::new(false));
 
 	application.connect_activate({
-		let compute = compute.clone();
-		let layout = layout.clone();
+		let drawer_tx = drawer_tx.clone();
+		let frames = frames.clone();
 		let pixbuf = pixbuf.clone();
 		let draw_settings = draw_settings.clone();
-		let msg_from_gtk = msg_from_gtk.clone();
-		let d3 = d3.clone();
 		move |app| {
 			build_ui(
 				app,
 				rx.clone(),
-				msg_from_gtk.clone(),
-				compute.clone(),
-				layout.clone(),
+				drawer_tx.clone(),
+				commands.clone(),
+				frames.clone(),
+				graph.clone(),
 				settings.clone(),
 				pixbuf.clone(),
 				draw_settings.clone(),
 				zoom.clone(),
-				d3.clone(),
-				nb_iters.clone(),
 			)
 		}
 	});
 
-	thread::spawn(move || loop {
-		thread::sleep(if *compute.read() {
-			if let Some(pixbuf) = pixbuf.write().as_ref() {
-				let layout = layout.read();
-				if *d3.read() {
-					crate::drawer::draw_graph_3d(
-						layout,
-						(pixbuf.0.width(), pixbuf.0.height()),
-						unsafe { pixbuf.0.pixels() },
-						pixbuf.0.rowstride(),
-						draw_settings.read().clone(),
-					);
-				} else {
-					crate::drawer::draw_graph(
-						layout,
-						(pixbuf.0.width(), pixbuf.0.height()),
-						unsafe { pixbuf.0.pixels() },
-						pixbuf.0.rowstride(),
-						draw_settings.read().clone(),
-					);
+	// Forwards the frames published by the runner, at most one per `DRAW_SLEEP`
+	thread::spawn({
+		let frames = frames.clone();
+		move || {
+			let mut epoch = 0;
+			while let Some(frame) = frames.wait(epoch) {
+				epoch = frame.epoch;
+				if drawer_tx.send(MsgToDrawer::Frame).is_err() {
+					break;
 				}
-				tx.send(MsgToGtk::Update).unwrap();
+				thread::sleep(DRAW_SLEEP);
 			}
-			let mut msg_from_gtk = msg_from_gtk.write();
-			if msg_from_gtk.resize {
-				msg_from_gtk.resize = false;
-				msg_from_gtk.redraw = false;
-				tx.send(MsgToGtk::Resize).unwrap();
+		}
+	});
+
+	thread::spawn(move || {
+		while let Ok(msg) = drawer_rx.recv() {
+			let mut resize = matches!(msg, MsgToDrawer::Resize);
+			for msg in drawer_rx.try_iter() {
+				resize |= matches!(msg, MsgToDrawer::Resize);
 			}
-			DRAW_SLEEP
-		} else {
-			let mut msg_from_gtk = msg_from_gtk.write();
-			if msg_from_gtk.resize {
-				msg_from_gtk.resize = false;
-				msg_from_gtk.redraw = false;
+			if resize {
+				// GTK recreates the pixbuf, then requests a redraw
 				tx.send(MsgToGtk::Resize).unwrap();
-			} else if msg_from_gtk.redraw {
-				msg_from_gtk.redraw = false;
-				if let Some(pixbuf) = pixbuf.write().as_ref() {
-					let layout = layout.read();
-					if *d3.read() {
-						crate::drawer::draw_graph_3d(
-							layout,
-							(pixbuf.0.width(), pixbuf.0.height()),
-							unsafe { pixbuf.0.pixels() },
-							pixbuf.0.rowstride(),
-							draw_settings.read().clone(),
-						);
-					} else {
-						crate::drawer::draw_graph(
-							layout,
-							(pixbuf.0.width(), pixbuf.0.height()),
-							unsafe { pixbuf.0.pixels() },
-							pixbuf.0.rowstride(),
-							draw_settings.read().clone(),
-						);
-					}
-					tx.send(MsgToGtk::Update).unwrap();
-				}
+			} else if let Some(pixbuf) = pixbuf.write().as_ref() {
+				crate::drawer::draw_graph(
+					&frames.get(),
+					(pixbuf.0.width(), pixbuf.0.height()),
+					unsafe { pixbuf.0.pixels() },
+					pixbuf.0.rowstride(),
+					draw_settings.read().clone(),
+				);
+				tx.send(MsgToGtk::Update).unwrap();
 			}
-			STANDBY_SLEEP
-		});
+		}
 	});
 
 	application.run_with_args::<&str>(&[]);
@@ -3,55 +3,53 @@ mod gui;
 
 use forceatlas2::*;
 use parking_lot::RwLock;
-use std::{io::BufRead, sync::Arc, thread, time::Duration};
+use std::sync::Arc;
 
-const STANDBY_SLEEP: Duration = Duration::from_millis(50);
-const COMPUTE_SLEEP: Duration = Duration::from_millis(1);
 type T = f32;
 
-fn main() {
-	let file = std::fs::File::open(std::env::args().nth(1).expect("Usage: viz <csv_file>"))
-		.expect("Cannot open file");
+/// Graph read from the file, to restart the layout
+pub struct Graph {
+	pub edges: Vec<Edge>,
+	pub nb_nodes: usize,
+	pub weights: Option<Vec<T>>,
+}
 
-	let mut nodes = 0usize;
-	let mut edges = Vec::<(usize, usize)>::new();
-	let mut weights = Vec::new();
-	for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
-		let line = line.expect("Error reading CSV");
-		let mut columns = line.split(&[' ', '\t', ',', ';'][..]);
-		if let (Some(n1), Some(n2)) = (columns.next(), columns.next()) {
-			if let (Ok(n1), Ok(n2)) = (n1.parse(), n2.parse()) {
-				if n1 > nodes {
-					nodes = n1;
-				}
-				if n2 > nodes {
-					nodes = n2;
-				}
-				if n1 != n2 {
-					edges.push(if n1 < n2 { (n1, n2) } else { (n2, n1) });
-					weights.push(columns.next().map_or(1.0, |w| {
-						w.parse().unwrap_or_else(|_| {
-							eprintln!("Ignored weight line {} has bad number format", i);
-							1.0
-						})
-					}));
-				} else {
-					eprintln!("Ignored line {} has loop", i);
-				}
-			} else {
-				eprintln!("Ignored line {} has bad number format", i);
-			}
-		} else {
-			eprintln!("Ignored line {} has <2 columns", i);
-		}
+impl Graph {
+	/// Randomly positioned layout
+	pub fn layout(&self, settings: Settings<T>) -> Layout<T> {
+		Layout::from_graph(
+			self.edges.clone(),
+			Nodes::Degree(self.nb_nodes),
+			self.weights.clone(),
+			settings,
+		)
 	}
-	nodes += 1;
+}
+
+fn main() {
+	let file = std::fs::File::open(
+		std::env::args()
+			.nth(1)
+			.expect("Usage: viz <csv_file> [weighted]"),
+	)
+	.expect("Cannot open file");
+
+	let graph = io::edgelist::read::<T, _>(
+		std::io::BufReader::new(file),
+		&io::edgelist::Options {
+			header: true,
+			weight_column: std::env::args().nth(2).map(|_| 2),
+			..Default::default()
+		},
+	)
+	.expect("Cannot read CSV");
 
-	println!("Nodes: {}", nodes);
+	println!("Nodes: {}", graph.nb_nodes);
 
 	let settings = Settings {
 		barnes_hut: None,
 		chunk_size: Some(256),
+		clusters: None,
 		dimensions: 2,
 		dissuade_hubs: false,
 		ka: 1.0,
@@ -63,32 +61,19 @@ fn main() {
 		strong_gravity: false,
 	};
 
-	let layout = Arc::new(RwLock::new(Layout::<T>::from_graph(
-		edges,
-		Nodes::Degree(nodes),
-		Some(weights),
-		settings.clone(),
-	)));
-
-	let compute = Arc::new(RwLock::new(false));
-	let settings = Arc::new(RwLock::new(settings));
-	let nb_iters = Arc::new(RwLock::new(0usize));
-
-	thread::spawn({
-		let compute = compute.clone();
-		let layout = layout.clone();
-		let nb_iters = nb_iters.clone();
-		move || loop {
-			thread::sleep(if *compute.read() {
-				let mut nb_iters = nb_iters.write();
-				layout.write().iteration();
-				*nb_iters += 1;
-				COMPUTE_SLEEP
-			} else {
-				STANDBY_SLEEP
-			});
-		}
+	let graph = Arc::new(Graph {
+		edges: graph.edges,
+		nb_nodes: graph.nb_nodes,
+		weights: graph.weights,
 	});
+	// Computes the layout in the background, controlled by the GUI
+	let runner = LayoutRunner::spawn(graph.layout(settings.clone()));
 
-	gui::run(compute, layout, settings, nb_iters);
+	gui::run(
+		runner.sender(),
+		runner.frame_reader(),
+		graph,
+		Arc::new(RwLock::new(settings)),
+	);
+	runner.stop();
 }
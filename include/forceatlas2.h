@@ -0,0 +1,188 @@
+#ifndef FORCEATLAS2_H
+#define FORCEATLAS2_H
+
+/* Generated by cbindgen from src/capi.rs, do not edit */
+
+#include <stdbool.h>
+#include <stddef.h>
+
+typedef enum Fa2Status {
+  FA2_STATUS_OK = 0,
+  /**
+   * A required pointer is null
+   */
+  FA2_STATUS_NULL_POINTER = 1,
+  /**
+   * An argument is out of range (e.g. a node index)
+   */
+  FA2_STATUS_INVALID_ARGUMENT = 2,
+  /**
+   * The feature is not enabled in this build
+   */
+  FA2_STATUS_UNSUPPORTED = 3,
+  /**
+   * Internal error
+   */
+  FA2_STATUS_PANIC = 4,
+} Fa2Status;
+
+/**
+ * Opaque layout handle
+ */
+typedef struct Fa2Layout Fa2Layout;
+
+/**
+ * Creates a layout
+ *
+ * `edges` contains `nb_edges` pairs of node indices. Loops are ignored.
+ * `weights` (`nb_edges` values) and `positions` (`nb_nodes * dimensions` values) may be null:
+ * the layout is then unweighted, or randomly positioned.
+ *
+ * # Safety
+ * Pointers must be valid for the given lengths. `*layout` must be freed with [`fa2_layout_free`].
+ */
+Fa2Status fa2_layout_new(const size_t *edges,
+                         size_t nb_edges,
+                         const double *weights,
+                         size_t nb_nodes,
+                         size_t dimensions,
+                         const double *positions,
+                         Fa2Layout **layout);
+
+/**
+ * Frees a layout (null is ignored)
+ *
+ * # Safety
+ * `layout` must come from [`fa2_layout_new`] and must not be used afterwards.
+ */
+void fa2_layout_free(Fa2Layout *layout);
+
+/**
+ * Computes an iteration
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_iteration(Fa2Layout *layout);
+
+/**
+ * Gives the number of nodes and of dimensions
+ *
+ * # Safety
+ * `layout` must be a valid handle. Output pointers may be null.
+ */
+Fa2Status fa2_layout_size(Fa2Layout *layout, size_t *nb_nodes, size_t *dimensions);
+
+/**
+ * Gives the position buffer, without copy: `nb_nodes * dimensions` values, e.g. `[x1, y1, x2, y2, ...]`
+ *
+ * The buffer may be modified, e.g. to move nodes. It stays valid until the layout is freed.
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_positions(Fa2Layout *layout, double **positions, size_t *len);
+
+/**
+ * Pins or unpins a node: a pinned node is not moved by iterations
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_set_pinned(Fa2Layout *layout, size_t node, bool pinned);
+
+/**
+ * Whether a node is pinned
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_is_pinned(Fa2Layout *layout, size_t node, bool *pinned);
+
+/**
+ * Attraction coefficient
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_set_ka(Fa2Layout *layout, double ka);
+
+/**
+ * Gravity coefficient
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_set_kg(Fa2Layout *layout, double kg);
+
+/**
+ * Repulsion coefficient
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_set_kr(Fa2Layout *layout, double kr);
+
+/**
+ * Speed factor
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_set_speed(Fa2Layout *layout, double speed);
+
+/**
+ * Logarithmic attraction
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_set_lin_log(Fa2Layout *layout, bool lin_log);
+
+/**
+ * Gravity does not decrease with distance
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_set_strong_gravity(Fa2Layout *layout, bool strong_gravity);
+
+/**
+ * Move hubs (high degree nodes) to the center
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_set_dissuade_hubs(Fa2Layout *layout, bool dissuade_hubs);
+
+/**
+ * Prevent node overlapping: `node_size` is the radius around a node where the repulsion coefficient is `kr_prime`
+ *
+ * Disabled if `node_size` is not positive.
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_set_prevent_overlapping(Fa2Layout *layout, double node_size, double kr_prime);
+
+/**
+ * Barnes-Hut repulsion with parameter `theta`, disabled if `theta` is not positive
+ *
+ * Returns `FA2_STATUS_UNSUPPORTED` without the `barnes_hut` feature.
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_set_barnes_hut(Fa2Layout *layout, double theta);
+
+/**
+ * Number of nodes computed by each thread in repulsion, 0 to disable parallelization
+ *
+ * Returns `FA2_STATUS_UNSUPPORTED` without the `parallel` feature.
+ *
+ * # Safety
+ * `layout` must be a valid handle.
+ */
+Fa2Status fa2_layout_set_chunk_size(Fa2Layout *layout, size_t chunk_size);
+
+#endif /* FORCEATLAS2_H */
@@ -0,0 +1,605 @@
+//! Command-line layout tool
+//!
+//! Requires the `cli` feature:
+//!
+//! ```sh
+//! cargo run --release --features cli --bin fa2 -- --help
+//! ```
+
+use clap::{Parser, ValueEnum};
+use forceatlas2::{io, render::raster, Layout, Settings};
+use std::{
+	fs::File,
+	io::{BufRead, BufReader, BufWriter, Read, Write},
+	path::{Path, PathBuf},
+	process::ExitCode,
+	time::Instant,
+};
+
+/// Invalid arguments or configuration (also used by clap)
+const EXIT_USAGE: u8 = 2;
+/// Cannot read the input or the configuration file
+const EXIT_INPUT: u8 = 3;
+/// Cannot write an output
+const EXIT_OUTPUT: u8 = 4;
+/// `--tolerance` was not reached within `--iterations`
+const EXIT_NOT_CONVERGED: u8 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum InputFormat {
+	/// Edge list (CSV-like): source, target and optional weight columns
+	Edgelist,
+	Gexf,
+	Graphml,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum OutputFormat {
+	/// `id,x,y[,z...]`
+	Csv,
+	/// Graphology JSON
+	Json,
+	/// GEXF (other attributes are kept if the input is GEXF)
+	Gexf,
+}
+
+/// ForceAtlas2 graph layout
+///
+/// Exit codes: 0 success, 2 invalid arguments, 3 cannot read input, 4 cannot write output,
+/// 5 not converged (with --tolerance, outputs are still written).
+#[derive(Parser)]
+#[command(version)]
+struct Args {
+	/// Input graph
+	input: PathBuf,
+	/// Input format (guessed from the extension by default)
+	#[arg(long, short = 'f')]
+	format: Option<InputFormat>,
+
+	/// Edge list: column delimiter (whitespace, `,` or `;` by default)
+	#[arg(long)]
+	delimiter: Option<char>,
+	/// Edge list: skip the first line
+	#[arg(long)]
+	header: bool,
+	/// Edge list: do not merge reciprocal and duplicate edges
+	#[arg(long)]
+	directed: bool,
+	/// Edge list: index of the weight column (unweighted by default)
+	#[arg(long)]
+	weight_column: Option<usize>,
+
+	/// JSON settings file (missing fields have their default value, flags override it)
+	#[arg(long, short = 'c')]
+	config: Option<PathBuf>,
+	#[arg(long)]
+	dimensions: Option<usize>,
+	/// Attraction coefficient
+	#[arg(long)]
+	ka: Option<f64>,
+	/// Gravity coefficient
+	#[arg(long)]
+	kg: Option<f64>,
+	/// Repulsion coefficient
+	#[arg(long)]
+	kr: Option<f64>,
+	#[arg(long)]
+	speed: Option<f64>,
+	/// Logarithmic attraction
+	#[arg(long, num_args = 0..=1, default_missing_value = "true")]
+	lin_log: Option<bool>,
+	/// Gravity does not decrease with distance
+	#[arg(long, num_args = 0..=1, default_missing_value = "true")]
+	strong_gravity: Option<bool>,
+	/// Move hubs to the center
+	#[arg(long, num_args = 0..=1, default_missing_value = "true")]
+	dissuade_hubs: Option<bool>,
+	/// Prevent overlapping: radius of the nodes (0 to disable)
+	#[arg(long)]
+	node_size: Option<f64>,
+	/// Prevent overlapping: repulsion coefficient between overlapping nodes
+	#[arg(long, default_value_t = 100.0)]
+	kr_prime: f64,
+	/// Barnes-Hut repulsion with this theta (0 to disable, requires the `barnes_hut` feature)
+	#[arg(long)]
+	barnes_hut: Option<f64>,
+	/// Nodes per thread in repulsion (0 to disable parallelization, requires the `parallel` feature)
+	#[arg(long)]
+	chunk_size: Option<usize>,
+
+	/// Maximum number of iterations
+	#[arg(long, short = 'n', default_value_t = 1000)]
+	iterations: u64,
+	/// Stop when the mean displacement of the nodes during an iteration is below this value
+	#[arg(long, short = 't')]
+	tolerance: Option<f64>,
+
+	/// Positions output (standard output if not given)
+	#[arg(long, short = 'o')]
+	output: Option<PathBuf>,
+	/// Output format (guessed from the extension by default, CSV for standard output)
+	#[arg(long)]
+	output_format: Option<OutputFormat>,
+	/// PNG image output
+	#[arg(long)]
+	image: Option<PathBuf>,
+	/// Image size in pixels
+	#[arg(long, default_value = "1024x1024", value_parser = parse_size)]
+	image_size: (usize, usize),
+	/// Do not print progress nor timing
+	#[arg(long, short = 'q')]
+	quiet: bool,
+}
+
+fn parse_size(s: &str) -> Result<(usize, usize), String> {
+	let (width, height) = s
+		.split_once('x')
+		.ok_or_else(|| "expected WIDTHxHEIGHT".to_string())?;
+	Ok((
+		width.parse().map_err(|e| format!("invalid width: {}", e))?,
+		height
+			.parse()
+			.map_err(|e| format!("invalid height: {}", e))?,
+	))
+}
+
+/// Error message and exit code
+#[derive(Debug)]
+struct Failure(String, u8);
+
+fn extension(path: &Path) -> String {
+	path.extension()
+		.and_then(|ext| ext.to_str())
+		.unwrap_or_default()
+		.to_ascii_lowercase()
+}
+
+fn input_format(args: &Args) -> InputFormat {
+	args.format
+		.unwrap_or_else(|| match extension(&args.input).as_str() {
+			"gexf" => InputFormat::Gexf,
+			"graphml" => InputFormat::Graphml,
+			_ => InputFormat::Edgelist,
+		})
+}
+
+/// Settings from the configuration file (if any), overridden by the flags
+fn settings(args: &Args) -> Result<Settings<f64>, Failure> {
+	let mut settings = Settings::<f64>::default();
+	if let Some(path) = &args.config {
+		let file = File::open(path)
+			.map_err(|e| Failure(format!("{}: {}", path.display(), e), EXIT_INPUT))?;
+		settings = read_config(BufReader::new(file))
+			.map_err(|e| Failure(format!("{}: {}", path.display(), e), EXIT_USAGE))?;
+	}
+	apply_flags(args, &mut settings)?;
+	Ok(settings)
+}
+
+/// Parses a JSON settings object (missing fields keep their default value)
+fn read_config<R: Read>(reader: R) -> Result<Settings<f64>, String> {
+	let config: serde_json::Value = serde_json::from_reader(reader).map_err(|e| e.to_string())?;
+	let serde_json::Value::Object(config) = config else {
+		return Err("expected an object".into());
+	};
+	let mut merged = serde_json::to_value(Settings::<f64>::default()).unwrap();
+	for (key, value) in config {
+		if merged.get(&key).is_none() {
+			return Err(format!("unknown setting `{}`", key));
+		}
+		merged[key] = value;
+	}
+	serde_json::from_value(merged).map_err(|e| e.to_string())
+}
+
+/// Overrides the settings with the flags given
+fn apply_flags(args: &Args, settings: &mut Settings<f64>) -> Result<(), Failure> {
+	if let Some(dimensions) = args.dimensions {
+		settings.dimensions = dimensions;
+	}
+	if settings.dimensions == 0 {
+		return Err(Failure("dimensions must be positive".into(), EXIT_USAGE));
+	}
+	if let Some(ka) = args.ka {
+		settings.ka = ka;
+	}
+	if let Some(kg) = args.kg {
+		settings.kg = kg;
+	}
+	if let Some(kr) = args.kr {
+		settings.kr = kr;
+	}
+	if let Some(speed) = args.speed {
+		settings.speed = speed;
+	}
+	if let Some(lin_log) = args.lin_log {
+		settings.lin_log = lin_log;
+	}
+	if let Some(strong_gravity) = args.strong_gravity {
+		settings.strong_gravity = strong_gravity;
+	}
+	if let Some(dissuade_hubs) = args.dissuade_hubs {
+		settings.dissuade_hubs = dissuade_hubs;
+	}
+	if let Some(node_size) = args.node_size {
+		settings.prevent_overlapping = (node_size > 0.0).then_some((node_size, args.kr_prime));
+	}
+	if let Some(theta) = args.barnes_hut {
+		#[cfg(feature = "barnes_hut")]
+		{
+			settings.barnes_hut = (theta > 0.0).then_some(theta);
+		}
+		#[cfg(not(feature = "barnes_hut"))]
+		if theta > 0.0 {
+			return Err(Failure(
+				"--barnes-hut requires the `barnes_hut` feature".into(),
+				EXIT_USAGE,
+			));
+		}
+	}
+	if let Some(chunk_size) = args.chunk_size {
+		#[cfg(feature = "parallel")]
+		{
+			settings.chunk_size = (chunk_size != 0).then_some(chunk_size);
+		}
+		#[cfg(not(feature = "parallel"))]
+		if chunk_size != 0 {
+			return Err(Failure(
+				"--chunk-size requires the `parallel` feature".into(),
+				EXIT_USAGE,
+			));
+		}
+	}
+	Ok(())
+}
+
+fn read(args: &Args, settings: Settings<f64>) -> Result<(Layout<f64>, Vec<String>), Failure> {
+	let input_error =
+		|e: &dyn std::fmt::Display| Failure(format!("{}: {}", args.input.display(), e), EXIT_INPUT);
+	let reader = BufReader::new(File::open(&args.input).map_err(|e| input_error(&e))?);
+	read_graph(args, reader, settings).map_err(|e| input_error(&e))
+}
+
+/// Reads the graph in the input format given by the arguments
+fn read_graph<R: BufRead>(
+	args: &Args,
+	reader: R,
+	settings: Settings<f64>,
+) -> Result<(Layout<f64>, Vec<String>), io::Error> {
+	Ok(match input_format(args) {
+		InputFormat::Edgelist => io::edgelist::read_layout(
+			reader,
+			&io::edgelist::Options {
+				delimiter: args.delimiter,
+				directed: args.directed,
+				header: args.header,
+				weight_column: args.weight_column,
+				..Default::default()
+			},
+			settings,
+		)?,
+		InputFormat::Gexf => io::gexf::read(reader)?.into_layout(settings),
+		InputFormat::Graphml => {
+			io::graphml::read(reader, &Default::default())?.into_layout(settings)
+		}
+	})
+}
+
+fn write(args: &Args, layout: &Layout<f64>, ids: &[String]) -> Result<(), Failure> {
+	let format = args.output_format.unwrap_or_else(|| {
+		match args.output.as_deref().map(extension).as_deref() {
+			Some("json") => OutputFormat::Json,
+			Some("gexf") => OutputFormat::Gexf,
+			_ => OutputFormat::Csv,
+		}
+	});
+	let output_name = args
+		.output
+		.as_ref()
+		.map_or_else(|| "<stdout>".into(), |path| path.display().to_string());
+	let output_error =
+		|e: &dyn std::fmt::Display| Failure(format!("{}: {}", output_name, e), EXIT_OUTPUT);
+
+	let mut writer: Box<dyn Write> = match &args.output {
+		Some(path) => Box::new(BufWriter::new(
+			File::create(path).map_err(|e| output_error(&e))?,
+		)),
+		None => Box::new(BufWriter::new(std::io::stdout().lock())),
+	};
+	match format {
+		OutputFormat::Csv => write_csv(layout, ids, &mut writer).map_err(|e| output_error(&e))?,
+		OutputFormat::Json => {
+			io::json::write(layout, Some(ids), io::json::Format::Graphology, &mut writer)
+				.map_err(|e| output_error(&e))?
+		}
+		OutputFormat::Gexf if input_format(args) == InputFormat::Gexf => {
+			// Keep the input's attributes
+			let reader =
+				BufReader::new(File::open(&args.input).map_err(|e| {
+					Failure(format!("{}: {}", args.input.display(), e), EXIT_INPUT)
+				})?);
+			io::gexf::write_positions(reader, &mut writer, layout, ids)
+				.map_err(|e| output_error(&e))?
+		}
+		OutputFormat::Gexf => {
+			io::gexf::write(layout, Some(ids), &mut writer).map_err(|e| output_error(&e))?
+		}
+	}
+	writer.flush().map_err(|e| output_error(&e))?;
+
+	if let Some(path) = &args.image {
+		let image_error =
+			|e: &dyn std::fmt::Display| Failure(format!("{}: {}", path.display(), e), EXIT_OUTPUT);
+		raster::render(layout, &Default::default(), args.image_size)
+			.write_png(BufWriter::new(
+				File::create(path).map_err(|e| image_error(&e))?,
+			))
+			.map_err(|e| image_error(&e))?;
+	}
+	Ok(())
+}
+
+fn write_csv<W: Write>(layout: &Layout<f64>, ids: &[String], mut writer: W) -> std::io::Result<()> {
+	write!(writer, "id")?;
+	for axis in 0..layout.points.dimensions {
+		match ["x", "y", "z"].get(axis) {
+			Some(name) => write!(writer, ",{}", name)?,
+			None => write!(writer, ",d{}", axis)?,
+		}
+	}
+	writeln!(writer)?;
+	for (id, pos) in ids.iter().zip(layout.points.iter()) {
+		if id.contains([',', '"', '\n', '\r']) {
+			write!(writer, "\"{}\"", id.replace('"', "\"\""))?;
+		} else {
+			write!(writer, "{}", id)?;
+		}
+		for coord in pos {
+			write!(writer, ",{}", coord)?;
+		}
+		writeln!(writer)?;
+	}
+	Ok(())
+}
+
+fn run(args: &Args) -> Result<bool, Failure> {
+	let settings = settings(args)?;
+
+	let start = Instant::now();
+	let (mut layout, ids) = read(args, settings)?;
+	if !args.quiet {
+		eprintln!(
+			"Read {} nodes and {} edges in {:.3}s",
+			layout.masses.len(),
+			layout.edges.len(),
+			start.elapsed().as_secs_f64()
+		);
+	}
+
+	let start = Instant::now();
+	let (iterations, converged) = iterate(args, &mut layout);
+	if !args.quiet {
+		let elapsed = start.elapsed().as_secs_f64();
+		eprintln!(
+			"\r{} {} iterations in {:.3}s ({:.1} it/s)",
+			match (args.tolerance, converged) {
+				(None, _) => "Ran",
+				(Some(_), true) => "Converged after",
+				(Some(_), false) => "Did not converge after",
+			},
+			iterations,
+			elapsed,
+			iterations as f64 / elapsed
+		);
+	}
+
+	let start = Instant::now();
+	write(args, &layout, &ids)?;
+	if !args.quiet {
+		eprintln!("Wrote outputs in {:.3}s", start.elapsed().as_secs_f64());
+	}
+	Ok(converged)
+}
+
+/// Runs iterations until `--iterations` or `--tolerance` is reached
+///
+/// Returns the number of iterations and whether the layout converged (always if there is no tolerance).
+fn iterate(args: &Args, layout: &mut Layout<f64>) -> (u64, bool) {
+	let nb_nodes = layout.masses.len().max(1) as f64;
+	let mut previous = layout.points.points.clone();
+	let mut converged = args.tolerance.is_none();
+	let mut iterations = 0;
+	while iterations < args.iterations {
+		layout.iteration();
+		iterations += 1;
+		if let Some(tolerance) = args.tolerance {
+			let displacement = layout
+				.points
+				.iter()
+				.zip(previous.chunks_exact(layout.points.dimensions))
+				.map(|(pos, old)| {
+					pos.iter()
+						.zip(old)
+						.map(|(x, old)| (x - old).powi(2))
+						.sum::<f64>()
+						.sqrt()
+				})
+				.sum::<f64>()
+				/ nb_nodes;
+			if displacement < tolerance {
+				converged = true;
+				break;
+			}
+			previous.copy_from_slice(&layout.points.points);
+		}
+		if !args.quiet && iterations % 10 == 0 {
+			eprint!("\rIteration {}/{}", iterations, args.iterations);
+		}
+	}
+	(iterations, converged)
+}
+
+fn exit_code(result: &Result<bool, Failure>) -> u8 {
+	match result {
+		Ok(true) => 0,
+		Ok(false) => EXIT_NOT_CONVERGED,
+		Err(Failure(_, code)) => *code,
+	}
+}
+
+fn main() -> ExitCode {
+	let args = Args::parse();
+	let result = run(&args);
+	if let Err(Failure(message, _)) = &result {
+		eprintln!("fa2: {}", message);
+	}
+	ExitCode::from(exit_code(&result))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn args(args: &[&str]) -> Args {
+		Args::try_parse_from(["fa2"].iter().chain(args)).unwrap()
+	}
+
+	fn temp_file(name: &str, contents: &str) -> PathBuf {
+		let path = std::env::temp_dir().join(format!("fa2-{}-{}", std::process::id(), name));
+		std::fs::write(&path, contents).unwrap();
+		path
+	}
+
+	const GRAPH: &str = "a b\nb c\nc a\nc d\n";
+
+	#[test]
+	fn test_settings() {
+		let config = read_config(&br#"{"kg": 2.0, "lin_log": true}"#[..]).unwrap();
+		assert_eq!(config.kg, 2.0);
+		assert!(config.lin_log);
+		assert_eq!(config.kr, Settings::<f64>::default().kr);
+		assert!(read_config(&br#"{"kgg": 2.0}"#[..]).is_err());
+		assert!(read_config(&b"[]"[..]).is_err());
+
+		// Flags override the configuration file
+		let config = temp_file(
+			"settings.json",
+			r#"{"dimensions": 3, "kr": 3.0, "lin_log": true}"#,
+		);
+		let settings = settings(&args(&[
+			"graph.csv",
+			"-c",
+			config.to_str().unwrap(),
+			"--kr",
+			"5",
+			"--lin-log=false",
+			"--strong-gravity",
+			"--node-size",
+			"2",
+		]))
+		.unwrap();
+		std::fs::remove_file(config).unwrap();
+		assert_eq!(settings.dimensions, 3);
+		assert_eq!(settings.kr, 5.0);
+		assert!(!settings.lin_log);
+		assert!(settings.strong_gravity);
+		assert_eq!(settings.prevent_overlapping, Some((2.0, 100.0)));
+		assert_eq!(settings.ka, Settings::<f64>::default().ka);
+	}
+
+	#[test]
+	fn test_read_graph() {
+		let (layout, ids) = read_graph(
+			&args(&["graph.txt", "--dimensions", "3"]),
+			GRAPH.as_bytes(),
+			Settings {
+				dimensions: 3,
+				..Default::default()
+			},
+		)
+		.unwrap();
+		assert_eq!(ids, vec!["a", "b", "c", "d"]);
+		assert_eq!(layout.edges.len(), 4);
+		assert_eq!(layout.points.dimensions, 3);
+		// Non-numeric weight
+		assert!(read_graph(
+			&args(&["graph.txt", "--weight-column", "2"]),
+			"a b x\n".as_bytes(),
+			Settings::default()
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn test_iterate() {
+		let graph = |args: &Args| {
+			read_graph(args, GRAPH.as_bytes(), Settings::default())
+				.unwrap()
+				.0
+		};
+
+		let args = args(&["graph.txt", "-n", "20"]);
+		assert_eq!(iterate(&args, &mut graph(&args)), (20, true));
+		// Any displacement is below this tolerance
+		let args = Args {
+			tolerance: Some(f64::INFINITY),
+			..args
+		};
+		assert_eq!(iterate(&args, &mut graph(&args)), (1, true));
+		let args = Args {
+			tolerance: Some(0.0),
+			..args
+		};
+		assert_eq!(iterate(&args, &mut graph(&args)), (20, false));
+	}
+
+	#[test]
+	fn test_exit_codes() {
+		let input = temp_file("graph.txt", GRAPH);
+		let output = temp_file("positions.csv", "");
+		let bad_config = temp_file("bad.json", r#"{"unknown": 1}"#);
+		let missing = std::env::temp_dir().join(format!("fa2-{}-missing", std::process::id()));
+		let input = input.to_str().unwrap();
+		let output = output.to_str().unwrap();
+		let run_args = |extra: &[&str]| {
+			let mut list = vec![input, "-q", "-n", "5"];
+			list.extend_from_slice(extra);
+			if !extra.contains(&"-o") {
+				list.extend_from_slice(&["-o", output]);
+			}
+			exit_code(&run(&args(&list)))
+		};
+
+		assert_eq!(run_args(&[]), 0);
+		assert!(std::fs::read_to_string(output)
+			.unwrap()
+			.starts_with("id,x,y\n"));
+		assert_eq!(
+			Args::try_parse_from(["fa2", input, "--unknown"])
+				.err()
+				.unwrap()
+				.exit_code(),
+			EXIT_USAGE as i32
+		);
+		assert_eq!(run_args(&["--dimensions", "0"]), EXIT_USAGE);
+		assert_eq!(run_args(&["-c", bad_config.to_str().unwrap()]), EXIT_USAGE);
+		assert_eq!(run_args(&["-c", missing.to_str().unwrap()]), EXIT_INPUT);
+		assert_eq!(
+			exit_code(&run(&args(&[missing.to_str().unwrap(), "-q"]))),
+			EXIT_INPUT
+		);
+		assert_eq!(
+			run_args(&["-o", missing.join("positions.csv").to_str().unwrap()]),
+			EXIT_OUTPUT
+		);
+		assert_eq!(run_args(&["-t", "0"]), EXIT_NOT_CONVERGED);
+		// Outputs are still written
+		assert!(std::fs::read_to_string(output)
+			.unwrap()
+			.starts_with("id,x,y\n"));
+
+		for path in [input, output, bad_config.to_str().unwrap()] {
+			std::fs::remove_file(path).unwrap();
+		}
+	}
+}
@@ -0,0 +1,493 @@
+//! C API
+//!
+//! Requires the `capi` feature. The header is `include/forceatlas2.h`; build the library with:
+//!
+//! ```sh
+//! cargo rustc --release --lib --features capi --crate-type cdylib
+//! ```
+//!
+//! Every function returns a [`Fa2Status`]: panics never cross the FFI boundary.
+//! Layouts use `double` coordinates and `Nodes::Degree` masses.
+
+use crate::{io::EdgeCollector, Layout, Nodes, Settings};
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Opaque layout handle
+pub struct Fa2Layout(Layout<f64>);
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fa2Status {
+	Ok = 0,
+	/// A required pointer is null
+	NullPointer = 1,
+	/// An argument is out of range (e.g. a node index)
+	InvalidArgument = 2,
+	/// The feature is not enabled in this build
+	Unsupported = 3,
+	/// Internal error
+	Panic = 4,
+}
+
+/// Runs `f`, catching panics
+fn guard<F: FnOnce() -> Result<(), Fa2Status>>(f: F) -> Fa2Status {
+	match catch_unwind(AssertUnwindSafe(f)) {
+		Ok(Ok(())) => Fa2Status::Ok,
+		Ok(Err(status)) => status,
+		Err(_) => Fa2Status::Panic,
+	}
+}
+
+unsafe fn get<'a, P>(ptr: *mut P) -> Result<&'a mut P, Fa2Status> {
+	ptr.as_mut().ok_or(Fa2Status::NullPointer)
+}
+
+fn update_settings<F: FnOnce(&mut Settings<f64>) -> Result<(), Fa2Status>>(
+	layout: *mut Fa2Layout,
+	f: F,
+) -> Fa2Status {
+	guard(|| {
+		let layout = unsafe { get(layout)? };
+		let mut settings = layout.0.get_settings().clone();
+		f(&mut settings)?;
+		layout.0.set_settings(settings);
+		Ok(())
+	})
+}
+
+/// Creates a layout
+///
+/// `edges` contains `nb_edges` pairs of node indices. Loops are ignored.
+/// `weights` (`nb_edges` values) and `positions` (`nb_nodes * dimensions` values) may be null:
+/// the layout is then unweighted, or randomly positioned.
+///
+/// # Safety
+/// Pointers must be valid for the given lengths. `*layout` must be freed with [`fa2_layout_free`].
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_new(
+	edges: *const usize,
+	nb_edges: usize,
+	weights: *const f64,
+	nb_nodes: usize,
+	dimensions: usize,
+	positions: *const f64,
+	layout: *mut *mut Fa2Layout,
+) -> Fa2Status {
+	guard(|| {
+		let out = get(layout)?;
+		*out = std::ptr::null_mut();
+		if edges.is_null() && nb_edges != 0 {
+			return Err(Fa2Status::NullPointer);
+		}
+		if dimensions == 0 {
+			return Err(Fa2Status::InvalidArgument);
+		}
+		let nb_ends = nb_edges.checked_mul(2).ok_or(Fa2Status::InvalidArgument)?;
+		let nb_coords = nb_nodes
+			.checked_mul(dimensions)
+			.ok_or(Fa2Status::InvalidArgument)?;
+		let edges: &[usize] = if nb_edges == 0 {
+			&[]
+		} else {
+			std::slice::from_raw_parts(edges, nb_ends)
+		};
+		let weights = (!weights.is_null()).then(|| std::slice::from_raw_parts(weights, nb_edges));
+
+		let mut collector = EdgeCollector::new(weights.is_some(), true);
+		for (edge, nodes) in edges.chunks_exact(2).enumerate() {
+			if nodes[0] >= nb_nodes || nodes[1] >= nb_nodes {
+				return Err(Fa2Status::InvalidArgument);
+			}
+			collector.push(nodes[0], nodes[1], weights.map(|weights| weights[edge]));
+		}
+
+		let settings = Settings {
+			dimensions,
+			..Default::default()
+		};
+		*out = Box::into_raw(Box::new(Fa2Layout(if positions.is_null() {
+			Layout::from_graph(
+				collector.edges,
+				Nodes::Degree(nb_nodes),
+				collector.weights,
+				settings,
+			)
+		} else {
+			Layout::from_position_graph(
+				collector.edges,
+				Nodes::Degree(nb_nodes),
+				std::slice::from_raw_parts(positions, nb_coords).to_vec(),
+				collector.weights,
+				settings,
+			)
+		})));
+		Ok(())
+	})
+}
+
+/// Frees a layout (null is ignored)
+///
+/// # Safety
+/// `layout` must come from [`fa2_layout_new`] and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_free(layout: *mut Fa2Layout) {
+	if !layout.is_null() {
+		let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(layout))));
+	}
+}
+
+/// Computes an iteration
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_iteration(layout: *mut Fa2Layout) -> Fa2Status {
+	guard(|| {
+		get(layout)?.0.iteration();
+		Ok(())
+	})
+}
+
+/// Gives the number of nodes and of dimensions
+///
+/// # Safety
+/// `layout` must be a valid handle. Output pointers may be null.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_size(
+	layout: *mut Fa2Layout,
+	nb_nodes: *mut usize,
+	dimensions: *mut usize,
+) -> Fa2Status {
+	guard(|| {
+		let layout = get(layout)?;
+		if let Some(nb_nodes) = nb_nodes.as_mut() {
+			*nb_nodes = layout.0.masses.len();
+		}
+		if let Some(dimensions) = dimensions.as_mut() {
+			*dimensions = layout.0.points.dimensions;
+		}
+		Ok(())
+	})
+}
+
+/// Gives the position buffer, without copy: `nb_nodes * dimensions` values, e.g. `[x1, y1, x2, y2, ...]`
+///
+/// The buffer may be modified, e.g. to move nodes. It stays valid until the layout is freed.
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_positions(
+	layout: *mut Fa2Layout,
+	positions: *mut *mut f64,
+	len: *mut usize,
+) -> Fa2Status {
+	guard(|| {
+		let layout = get(layout)?;
+		let positions = get(positions)?;
+		let len = get(len)?;
+		*positions = layout.0.points.points.as_mut_ptr();
+		*len = layout.0.points.points.len();
+		Ok(())
+	})
+}
+
+/// Pins or unpins a node: a pinned node is not moved by iterations
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_set_pinned(
+	layout: *mut Fa2Layout,
+	node: usize,
+	pinned: bool,
+) -> Fa2Status {
+	guard(|| {
+		let layout = get(layout)?;
+		if node >= layout.0.masses.len() {
+			return Err(Fa2Status::InvalidArgument);
+		}
+		layout.0.set_pinned(node, pinned);
+		Ok(())
+	})
+}
+
+/// Whether a node is pinned
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_is_pinned(
+	layout: *mut Fa2Layout,
+	node: usize,
+	pinned: *mut bool,
+) -> Fa2Status {
+	guard(|| {
+		let layout = get(layout)?;
+		let pinned = get(pinned)?;
+		if node >= layout.0.masses.len() {
+			return Err(Fa2Status::InvalidArgument);
+		}
+		*pinned = layout.0.is_pinned(node);
+		Ok(())
+	})
+}
+
+/// Attraction coefficient
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_set_ka(layout: *mut Fa2Layout, ka: f64) -> Fa2Status {
+	update_settings(layout, |settings| {
+		settings.ka = ka;
+		Ok(())
+	})
+}
+
+/// Gravity coefficient
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_set_kg(layout: *mut Fa2Layout, kg: f64) -> Fa2Status {
+	update_settings(layout, |settings| {
+		settings.kg = kg;
+		Ok(())
+	})
+}
+
+/// Repulsion coefficient
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_set_kr(layout: *mut Fa2Layout, kr: f64) -> Fa2Status {
+	update_settings(layout, |settings| {
+		settings.kr = kr;
+		Ok(())
+	})
+}
+
+/// Speed factor
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_set_speed(layout: *mut Fa2Layout, speed: f64) -> Fa2Status {
+	update_settings(layout, |settings| {
+		settings.speed = speed;
+		Ok(())
+	})
+}
+
+/// Logarithmic attraction
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_set_lin_log(
+	layout: *mut Fa2Layout,
+	lin_log: bool,
+) -> Fa2Status {
+	update_settings(layout, |settings| {
+		settings.lin_log = lin_log;
+		Ok(())
+	})
+}
+
+/// Gravity does not decrease with distance
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_set_strong_gravity(
+	layout: *mut Fa2Layout,
+	strong_gravity: bool,
+) -> Fa2Status {
+	update_settings(layout, |settings| {
+		settings.strong_gravity = strong_gravity;
+		Ok(())
+	})
+}
+
+/// Move hubs (high degree nodes) to the center
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_set_dissuade_hubs(
+	layout: *mut Fa2Layout,
+	dissuade_hubs: bool,
+) -> Fa2Status {
+	update_settings(layout, |settings| {
+		settings.dissuade_hubs = dissuade_hubs;
+		Ok(())
+	})
+}
+
+/// Prevent node overlapping: `node_size` is the radius around a node where the repulsion coefficient is `kr_prime`
+///
+/// Disabled if `node_size` is not positive.
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_set_prevent_overlapping(
+	layout: *mut Fa2Layout,
+	node_size: f64,
+	kr_prime: f64,
+) -> Fa2Status {
+	update_settings(layout, |settings| {
+		settings.prevent_overlapping = (node_size > 0.0).then_some((node_size, kr_prime));
+		Ok(())
+	})
+}
+
+/// Barnes-Hut repulsion with parameter `theta`, disabled if `theta` is not positive
+///
+/// Returns `FA2_STATUS_UNSUPPORTED` without the `barnes_hut` feature.
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_set_barnes_hut(
+	layout: *mut Fa2Layout,
+	theta: f64,
+) -> Fa2Status {
+	update_settings(layout, |settings| {
+		#[cfg(feature = "barnes_hut")]
+		{
+			settings.barnes_hut = (theta > 0.0).then_some(theta);
+			Ok(())
+		}
+		#[cfg(not(feature = "barnes_hut"))]
+		{
+			let _ = (settings, theta);
+			Err(Fa2Status::Unsupported)
+		}
+	})
+}
+
+/// Number of nodes computed by each thread in repulsion, 0 to disable parallelization
+///
+/// Returns `FA2_STATUS_UNSUPPORTED` without the `parallel` feature.
+///
+/// # Safety
+/// `layout` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn fa2_layout_set_chunk_size(
+	layout: *mut Fa2Layout,
+	chunk_size: usize,
+) -> Fa2Status {
+	update_settings(layout, |settings| {
+		#[cfg(feature = "parallel")]
+		{
+			settings.chunk_size = (chunk_size != 0).then_some(chunk_size);
+			Ok(())
+		}
+		#[cfg(not(feature = "parallel"))]
+		{
+			let _ = (settings, chunk_size);
+			Err(Fa2Status::Unsupported)
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_capi() {
+		unsafe {
+			let mut layout = std::ptr::null_mut();
+			// Out of range node
+			assert_eq!(
+				fa2_layout_new(
+					[0, 3].as_ptr(),
+					1,
+					std::ptr::null(),
+					3,
+					2,
+					std::ptr::null(),
+					&mut layout
+				),
+				Fa2Status::InvalidArgument
+			);
+			assert!(layout.is_null());
+			// Overflowing lengths
+			assert_eq!(
+				fa2_layout_new(
+					[0, 1].as_ptr(),
+					usize::MAX,
+					std::ptr::null(),
+					3,
+					2,
+					std::ptr::null(),
+					&mut layout
+				),
+				Fa2Status::InvalidArgument
+			);
+			assert_eq!(
+				fa2_layout_new(
+					std::ptr::null(),
+					0,
+					std::ptr::null(),
+					usize::MAX,
+					2,
+					[0.0].as_ptr(),
+					&mut layout
+				),
+				Fa2Status::InvalidArgument
+			);
+
+			let positions = [0.0, 0.0, 1.0, 1.0, -1.0, 1.0];
+			assert_eq!(
+				fa2_layout_new(
+					[1, 0, 0, 2].as_ptr(),
+					2,
+					[1.0, 2.0].as_ptr(),
+					3,
+					2,
+					positions.as_ptr(),
+					&mut layout
+				),
+				Fa2Status::Ok
+			);
+			assert_eq!((*layout).0.edges, vec![(0, 1), (0, 2)]);
+
+			let (mut nb_nodes, mut dimensions) = (0, 0);
+			assert_eq!(
+				fa2_layout_size(layout, &mut nb_nodes, &mut dimensions),
+				Fa2Status::Ok
+			);
+			assert_eq!((nb_nodes, dimensions), (3, 2));
+
+			assert_eq!(fa2_layout_set_kr(layout, 0.5), Fa2Status::Ok);
+			assert_eq!((*layout).0.get_settings().kr, 0.5);
+			assert_eq!(fa2_layout_set_pinned(layout, 0, true), Fa2Status::Ok);
+			assert_eq!(
+				fa2_layout_set_pinned(layout, 3, true),
+				Fa2Status::InvalidArgument
+			);
+			assert_eq!(fa2_layout_iteration(layout), Fa2Status::Ok);
+			assert_eq!(
+				fa2_layout_iteration(std::ptr::null_mut()),
+				Fa2Status::NullPointer
+			);
+
+			let (mut buffer, mut len) = (std::ptr::null_mut(), 0);
+			assert_eq!(
+				fa2_layout_positions(layout, &mut buffer, &mut len),
+				Fa2Status::Ok
+			);
+			let buffer = std::slice::from_raw_parts(buffer, len);
+			assert_eq!(&buffer[..2], &[0.0, 0.0]);
+			assert_ne!(&buffer[2..4], &[1.0, 1.0]);
+
+			fa2_layout_free(layout);
+		}
+	}
+}
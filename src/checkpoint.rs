@@ -0,0 +1,645 @@
+//! Native binary checkpoint format
+//!
+//! All the integers and floats are little-endian.
+//!
+//! | Field | Type |
+//! |-------|------|
+//! | magic | `b"FA2CKPT\0"` |
+//! | version | `u32` |
+//! | coordinate size in bytes | `u8` (4 for `f32`, 8 for `f64`) |
+//! | dimensions, nodes, edges | `u64` ×3 |
+//! | weighted | `u8` |
+//! | mass mode | `u8` |
+//! | settings | see [`write_settings`] |
+//! | edges | `[u64; 2]` × edges |
+//! | weights (if weighted) | coord × edges |
+//! | masses | coord × nodes |
+//! | points, speeds, old speeds | coord × nodes × dimensions, ×3 |
+//! | number of nodes having a cluster | `u64` |
+//! | clusters | `u64` × nodes having a cluster |
+//! | number of pinned flags | `u64` |
+//! | pinned flags | `u8` × pinned flags |
+//! | CRC32 of all the previous bytes | `u32` |
+
+use crate::{
+	forces::{self, Attraction, Repulsion},
+	layout::{ClusterSettings, Layout, Settings},
+	util::*,
+};
+
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+const MAGIC: &[u8; 8] = b"FA2CKPT\0";
+const VERSION: u32 = 1;
+/// Number of edges encoded or decoded at once
+const EDGE_BUFFER: usize = 4096;
+
+mod private {
+	pub trait Sealed {}
+
+	impl Sealed for f32 {}
+	impl Sealed for f64 {}
+}
+
+/// Coordinate type that can be stored in a checkpoint
+///
+/// Sealed: coordinates are written as raw bytes, which is only sound for the primitive floats.
+pub trait CheckpointCoord: Coord + Copy + private::Sealed {
+	/// Size in bytes
+	const SIZE: usize;
+
+	fn write_le(self, buf: &mut [u8]);
+	fn read_le(buf: &[u8]) -> Self;
+}
+
+impl CheckpointCoord for f32 {
+	const SIZE: usize = 4;
+
+	fn write_le(self, buf: &mut [u8]) {
+		buf.copy_from_slice(&f32::to_le_bytes(self));
+	}
+
+	fn read_le(buf: &[u8]) -> Self {
+		f32::from_le_bytes(buf.try_into().unwrap())
+	}
+}
+
+impl CheckpointCoord for f64 {
+	const SIZE: usize = 8;
+
+	fn write_le(self, buf: &mut [u8]) {
+		buf.copy_from_slice(&f64::to_le_bytes(self));
+	}
+
+	fn read_le(buf: &[u8]) -> Self {
+		f64::from_le_bytes(buf.try_into().unwrap())
+	}
+}
+
+struct CrcWriter<W> {
+	hasher: crc32fast::Hasher,
+	inner: W,
+}
+
+impl<W: Write> Write for CrcWriter<W> {
+	fn write(&mut self, buf: &[u8]) -> Result<usize> {
+		let n = self.inner.write(buf)?;
+		self.hasher.update(&buf[..n]);
+		Ok(n)
+	}
+
+	fn flush(&mut self) -> Result<()> {
+		self.inner.flush()
+	}
+}
+
+struct CrcReader<R> {
+	hasher: crc32fast::Hasher,
+	inner: R,
+}
+
+impl<R: Read> Read for CrcReader<R> {
+	fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+		let n = self.inner.read(buf)?;
+		self.hasher.update(&buf[..n]);
+		Ok(n)
+	}
+}
+
+fn invalid_data(msg: &str) -> Error {
+	Error::new(ErrorKind::InvalidData, msg)
+}
+
+fn write_u8(writer: &mut impl Write, val: u8) -> Result<()> {
+	writer.write_all(&[val])
+}
+
+fn write_u64(writer: &mut impl Write, val: u64) -> Result<()> {
+	writer.write_all(&val.to_le_bytes())
+}
+
+fn write_coord<T: CheckpointCoord>(writer: &mut impl Write, val: T) -> Result<()> {
+	let mut buf = [0u8; 8];
+	val.write_le(&mut buf[..T::SIZE]);
+	writer.write_all(&buf[..T::SIZE])
+}
+
+fn write_option_coord<T: CheckpointCoord>(writer: &mut impl Write, val: Option<T>) -> Result<()> {
+	match val {
+		Some(val) => {
+			write_u8(writer, 1)?;
+			write_coord(writer, val)
+		}
+		None => write_u8(writer, 0),
+	}
+}
+
+/// Writes a coordinate array as raw little-endian bytes
+fn write_coords<T: CheckpointCoord>(writer: &mut impl Write, vals: &[T]) -> Result<()> {
+	#[cfg(target_endian = "little")]
+	{
+		writer.write_all(unsafe {
+			std::slice::from_raw_parts(vals.as_ptr() as *const u8, std::mem::size_of_val(vals))
+		})
+	}
+	#[cfg(not(target_endian = "little"))]
+	{
+		let mut buf = vec![0u8; T::SIZE * EDGE_BUFFER];
+		for chunk in vals.chunks(EDGE_BUFFER) {
+			for (val, buf) in chunk.iter().zip(buf.chunks_exact_mut(T::SIZE)) {
+				val.write_le(buf);
+			}
+			writer.write_all(&buf[..chunk.len() * T::SIZE])?;
+		}
+		Ok(())
+	}
+}
+
+fn read_u8(reader: &mut impl Read) -> Result<u8> {
+	let mut buf = [0u8; 1];
+	reader.read_exact(&mut buf)?;
+	Ok(buf[0])
+}
+
+fn read_bool(reader: &mut impl Read) -> Result<bool> {
+	match read_u8(reader)? {
+		0 => Ok(false),
+		1 => Ok(true),
+		_ => Err(invalid_data("Invalid boolean")),
+	}
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+	let mut buf = [0u8; 4];
+	reader.read_exact(&mut buf)?;
+	Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64> {
+	let mut buf = [0u8; 8];
+	reader.read_exact(&mut buf)?;
+	Ok(u64::from_le_bytes(buf))
+}
+
+fn read_usize(reader: &mut impl Read) -> Result<usize> {
+	read_u64(reader)?
+		.try_into()
+		.map_err(|_| invalid_data("Integer too large for this platform"))
+}
+
+fn read_coord<T: CheckpointCoord>(reader: &mut impl Read) -> Result<T> {
+	let mut buf = [0u8; 8];
+	reader.read_exact(&mut buf[..T::SIZE])?;
+	Ok(T::read_le(&buf[..T::SIZE]))
+}
+
+fn read_option_coord<T: CheckpointCoord>(reader: &mut impl Read) -> Result<Option<T>> {
+	Ok(if read_bool(reader)? {
+		Some(read_coord(reader)?)
+	} else {
+		None
+	})
+}
+
+/// Reads a coordinate array, growing it chunk by chunk
+///
+/// `len` comes from the unverified header: a corrupted length fails at the end of the input
+/// instead of allocating it all.
+fn read_coords<T: CheckpointCoord>(reader: &mut impl Read, len: usize) -> Result<Vec<T>> {
+	let mut vals = Vec::with_capacity(len.min(EDGE_BUFFER));
+	let mut buf = vec![0u8; T::SIZE * EDGE_BUFFER];
+	while vals.len() < len {
+		let n = (len - vals.len()).min(EDGE_BUFFER);
+		let buf = &mut buf[..n * T::SIZE];
+		reader.read_exact(buf)?;
+		vals.extend(buf.chunks_exact(T::SIZE).map(T::read_le));
+	}
+	Ok(vals)
+}
+
+/// Settings are encoded field by field, in declaration order.
+///
+/// Optional fields are prefixed by a `u8` (1 if present).
+/// Fields behind a disabled feature are still encoded, as `None`.
+/// When reading, a chunk size is ignored without the `parallel` feature,
+/// but a Barnes-Hut setting is rejected without the `barnes_hut` feature, as it changes the layout.
+/// `clusters` is encoded last, as `intra_attraction`, `inter_attraction`, `gravity`.
+fn write_settings<T: CheckpointCoord>(
+	writer: &mut impl Write,
+	settings: &Settings<T>,
+) -> Result<()> {
+	#[cfg(feature = "barnes_hut")]
+	write_option_coord(writer, settings.barnes_hut)?;
+	#[cfg(not(feature = "barnes_hut"))]
+	write_option_coord::<T>(writer, None)?;
+	#[cfg(feature = "parallel")]
+	let chunk_size = settings.chunk_size;
+	#[cfg(not(feature = "parallel"))]
+	let chunk_size: Option<usize> = None;
+	match chunk_size {
+		Some(chunk_size) => {
+			write_u8(writer, 1)?;
+			write_u64(writer, chunk_size as u64)?;
+		}
+		None => write_u8(writer, 0)?,
+	}
+	write_u8(writer, settings.dissuade_hubs as u8)?;
+	write_coord(writer, settings.ka)?;
+	write_coord(writer, settings.kg)?;
+	write_coord(writer, settings.kr)?;
+	write_u8(writer, settings.lin_log as u8)?;
+	match settings.prevent_overlapping {
+		Some((node_size, kr_prime)) => {
+			write_u8(writer, 1)?;
+			write_coord(writer, node_size)?;
+			write_coord(writer, kr_prime)?;
+		}
+		None => write_u8(writer, 0)?,
+	}
+	write_coord(writer, settings.speed)?;
+	write_u8(writer, settings.strong_gravity as u8)?;
+	match &settings.clusters {
+		Some(clusters) => {
+			write_u8(writer, 1)?;
+			write_coord(writer, clusters.intra_attraction)?;
+			write_coord(writer, clusters.inter_attraction)?;
+			write_coord(writer, clusters.gravity)
+		}
+		None => write_u8(writer, 0),
+	}
+}
+
+fn read_settings<T: CheckpointCoord>(
+	reader: &mut impl Read,
+	dimensions: usize,
+) -> Result<Settings<T>> {
+	let barnes_hut = read_option_coord::<T>(reader)?;
+	#[cfg(not(feature = "barnes_hut"))]
+	if barnes_hut.is_some() {
+		return Err(invalid_data(
+			"Checkpoint uses Barnes-Hut, which is not enabled",
+		));
+	}
+	#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+	let chunk_size = if read_bool(reader)? {
+		Some(read_usize(reader)?)
+	} else {
+		None
+	};
+	let mut settings = Settings {
+		#[cfg(feature = "barnes_hut")]
+		barnes_hut,
+		#[cfg(feature = "parallel")]
+		chunk_size,
+		clusters: None,
+		dimensions,
+		dissuade_hubs: read_bool(reader)?,
+		ka: read_coord(reader)?,
+		kg: read_coord(reader)?,
+		kr: read_coord(reader)?,
+		lin_log: read_bool(reader)?,
+		prevent_overlapping: if read_bool(reader)? {
+			Some((read_coord(reader)?, read_coord(reader)?))
+		} else {
+			None
+		},
+		speed: read_coord(reader)?,
+		strong_gravity: read_bool(reader)?,
+	};
+	if read_bool(reader)? {
+		settings.clusters = Some(ClusterSettings {
+			intra_attraction: read_coord(reader)?,
+			inter_attraction: read_coord(reader)?,
+			gravity: read_coord(reader)?,
+		});
+	}
+	Ok(settings)
+}
+
+impl<T: CheckpointCoord + std::fmt::Debug> Layout<T>
+where
+	Layout<T>: Repulsion<T> + Attraction<T>,
+{
+	/// Writes the whole state of the layout, so that it can be resumed exactly
+	///
+	/// A derived mass mode cannot be stored: it is replaced by `MassMode::Fixed`,
+	/// and can be restored with [`Layout::set_mass_mode`].
+	///
+	/// `writer` should be buffered.
+	pub fn write_checkpoint(&self, writer: &mut impl Write) -> Result<()> {
+		let mut writer = CrcWriter {
+			hasher: crc32fast::Hasher::new(),
+			inner: writer,
+		};
+
+		writer.write_all(MAGIC)?;
+		writer.write_all(&VERSION.to_le_bytes())?;
+		write_u8(&mut writer, T::SIZE as u8)?;
+		write_u64(&mut writer, self.settings.dimensions as u64)?;
+		write_u64(&mut writer, self.masses.len() as u64)?;
+		write_u64(&mut writer, self.edges.len() as u64)?;
+		write_u8(&mut writer, self.weights.is_some() as u8)?;
+		write_u8(
+			&mut writer,
+			match self.mass_mode {
+				MassMode::Fixed | MassMode::Derived(_) => 0,
+				MassMode::Degree => 1,
+				MassMode::WeightedDegree => 2,
+				MassMode::LogDegree => 3,
+			},
+		)?;
+		write_settings(&mut writer, &self.settings)?;
+
+		let mut buf = Vec::with_capacity(EDGE_BUFFER * 16);
+		for chunk in self.edges.chunks(EDGE_BUFFER) {
+			buf.clear();
+			for (n1, n2) in chunk {
+				buf.extend_from_slice(&(*n1 as u64).to_le_bytes());
+				buf.extend_from_slice(&(*n2 as u64).to_le_bytes());
+			}
+			writer.write_all(&buf)?;
+		}
+		if let Some(weights) = &self.weights {
+			write_coords(&mut writer, weights)?;
+		}
+		write_coords(&mut writer, &self.masses)?;
+		write_coords(&mut writer, &self.points.points)?;
+		write_coords(&mut writer, &self.speeds.points)?;
+		write_coords(&mut writer, &self.old_speeds.points)?;
+		write_u64(&mut writer, self.clusters.len() as u64)?;
+		for chunk in self.clusters.chunks(EDGE_BUFFER) {
+			buf.clear();
+			for cluster in chunk {
+				buf.extend_from_slice(&(*cluster as u64).to_le_bytes());
+			}
+			writer.write_all(&buf)?;
+		}
+		write_u64(&mut writer, self.pinned.len() as u64)?;
+		for chunk in self.pinned.chunks(EDGE_BUFFER) {
+			buf.clear();
+			buf.extend(chunk.iter().map(|pinned| *pinned as u8));
+			writer.write_all(&buf)?;
+		}
+
+		let crc = writer.hasher.clone().finalize();
+		writer.inner.write_all(&crc.to_le_bytes())
+	}
+
+	/// Reads a layout written by [`Layout::write_checkpoint`]
+	///
+	/// Returns an error of kind `InvalidData` if the checkpoint is corrupted,
+	/// has an unsupported version, or was written with another coordinate type.
+	///
+	/// `reader` should be buffered.
+	pub fn read_checkpoint(reader: impl Read) -> Result<Self> {
+		let mut reader = CrcReader {
+			hasher: crc32fast::Hasher::new(),
+			inner: reader,
+		};
+
+		let mut magic = [0u8; 8];
+		reader.read_exact(&mut magic)?;
+		if &magic != MAGIC {
+			return Err(invalid_data("Not a checkpoint"));
+		}
+		if read_u32(&mut reader)? != VERSION {
+			return Err(invalid_data("Unsupported checkpoint version"));
+		}
+		if read_u8(&mut reader)? as usize != T::SIZE {
+			return Err(invalid_data("Checkpoint has another coordinate type"));
+		}
+		let dimensions = read_usize(&mut reader)?;
+		if dimensions == 0 {
+			return Err(invalid_data("Checkpoint has zero dimensions"));
+		}
+		let nb_nodes = read_usize(&mut reader)?;
+		let nb_edges = read_usize(&mut reader)?;
+		let nb = nb_nodes
+			.checked_mul(dimensions)
+			.ok_or_else(|| invalid_data("Too many coordinates"))?;
+		let weighted = read_bool(&mut reader)?;
+		let mass_mode = match read_u8(&mut reader)? {
+			0 => MassMode::Fixed,
+			1 => MassMode::Degree,
+			2 => MassMode::WeightedDegree,
+			3 => MassMode::LogDegree,
+			_ => return Err(invalid_data("Invalid mass mode")),
+		};
+		let settings = read_settings(&mut reader, dimensions)?;
+
+		// The header is not verified yet: allocate as the input is read
+		let mut edges = Vec::with_capacity(nb_edges.min(EDGE_BUFFER));
+		let mut buf = vec![0u8; EDGE_BUFFER * 16];
+		while edges.len() < nb_edges {
+			let n = (nb_edges - edges.len()).min(EDGE_BUFFER);
+			let buf = &mut buf[..n * 16];
+			reader.read_exact(buf)?;
+			for edge in buf.chunks_exact(16) {
+				let n1 = u64::from_le_bytes(edge[..8].try_into().unwrap()) as usize;
+				let n2 = u64::from_le_bytes(edge[8..].try_into().unwrap()) as usize;
+				if n1 >= nb_nodes || n2 >= nb_nodes {
+					return Err(invalid_data("Edge refers to a nonexistent node"));
+				}
+				if n1 >= n2 {
+					return Err(invalid_data("Edge does not respect n1 < n2"));
+				}
+				edges.push((n1, n2));
+			}
+		}
+		let weights = if weighted {
+			Some(read_coords(&mut reader, nb_edges)?)
+		} else {
+			None
+		};
+		let masses = read_coords(&mut reader, nb_nodes)?;
+		let points = read_coords(&mut reader, nb)?;
+		let speeds = read_coords(&mut reader, nb)?;
+		let old_speeds = read_coords(&mut reader, nb)?;
+		let nb_clusters = read_usize(&mut reader)?;
+		if nb_clusters > nb_nodes {
+			return Err(invalid_data("More clusters than nodes"));
+		}
+		let clusters = (0..nb_clusters)
+			.map(|_| read_usize(&mut reader))
+			.collect::<Result<Vec<usize>>>()?;
+		if clusters.iter().any(|cluster| *cluster >= nb_clusters) {
+			return Err(invalid_data("Cluster out of range"));
+		}
+		let nb_pinned = read_usize(&mut reader)?;
+		if nb_pinned > nb_nodes {
+			return Err(invalid_data("More pinned flags than nodes"));
+		}
+		let pinned = (0..nb_pinned)
+			.map(|_| read_bool(&mut reader))
+			.collect::<Result<Vec<bool>>>()?;
+
+		let crc = reader.hasher.clone().finalize();
+		if read_u32(&mut reader.inner)? != crc {
+			return Err(invalid_data("Checkpoint CRC mismatch"));
+		}
+
+		Ok(Self {
+			cluster_buffers: Default::default(),
+			clusters,
+			constraints: Vec::new(),
+			degrees: Degrees::from_edges(nb_nodes, &edges, weights.as_deref()),
+			edges,
+			masses,
+			mass_mode,
+			old_speeds: PointList {
+				dimensions,
+				points: old_speeds,
+			},
+			points: PointList { dimensions, points },
+			speeds: PointList {
+				dimensions,
+				points: speeds,
+			},
+			forces: Vec::new(),
+			observers: Default::default(),
+			pinned,
+			#[cfg(feature = "publish")]
+			publisher: None,
+			weights,
+			fn_attraction: Self::choose_attraction(&settings),
+			fn_gravity: forces::choose_gravity(&settings),
+			fn_repulsion: Self::choose_repulsion(&settings),
+			settings,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_checkpoint_resume() {
+		let mut layout = Layout::<f32>::from_position_graph(
+			vec![(0, 1), (0, 2), (1, 3)],
+			Nodes::WeightedDegree(4),
+			vec![-1.0, -1.0, 1.0, 1.0, 0.5, -2.0, 1.5, 0.0],
+			Some(vec![1.0, 2.0, 0.5]),
+			Settings::default(),
+		);
+		for _ in 0..5 {
+			layout.iteration();
+		}
+
+		let mut buf = Vec::new();
+		layout.write_checkpoint(&mut buf).unwrap();
+		let mut resumed = Layout::<f32>::read_checkpoint(buf.as_slice()).unwrap();
+		assert_eq!(resumed.edges, layout.edges);
+		assert_eq!(resumed.weights, layout.weights);
+		assert_eq!(resumed.masses, layout.masses);
+		assert!(resumed.mass_mode == MassMode::WeightedDegree);
+		assert_eq!(resumed.old_speeds.points, layout.old_speeds.points);
+
+		for _ in 0..5 {
+			layout.iteration();
+			resumed.iteration();
+		}
+		assert_eq!(layout.points.points, resumed.points.points);
+
+		assert!(Layout::<f64>::read_checkpoint(buf.as_slice()).is_err());
+		let last = buf.len() - 5;
+		buf[last] ^= 1;
+		assert_eq!(
+			Layout::<f32>::read_checkpoint(buf.as_slice())
+				.err()
+				.unwrap()
+				.kind(),
+			ErrorKind::InvalidData
+		);
+	}
+
+	#[test]
+	fn test_checkpoint_clusters() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (1, 2)],
+			Nodes::Degree(3),
+			vec![-1.0, -1.0, 1.0, 1.0, 0.5, -2.0],
+			None,
+			Settings {
+				clusters: Some(ClusterSettings::default()),
+				..Default::default()
+			},
+		);
+		layout.set_clusters(vec![0, 1]);
+		let mut buf = Vec::new();
+		layout.write_checkpoint(&mut buf).unwrap();
+		let resumed = Layout::<f64>::read_checkpoint(buf.as_slice()).unwrap();
+		assert_eq!(resumed.clusters, vec![0, 1]);
+		assert_eq!(
+			resumed.settings.clusters.unwrap().inter_attraction,
+			ClusterSettings::<f64>::default().inter_attraction
+		);
+	}
+
+	#[test]
+	fn test_checkpoint_pinned() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (1, 2)],
+			Nodes::Degree(3),
+			vec![-1.0, -1.0, 1.0, 1.0, 0.5, -2.0],
+			None,
+			Settings::default(),
+		);
+		layout.set_pinned(1, true);
+		let mut buf = Vec::new();
+		layout.write_checkpoint(&mut buf).unwrap();
+		let mut resumed = Layout::<f64>::read_checkpoint(buf.as_slice()).unwrap();
+		assert!(resumed.is_pinned(1));
+		resumed.iteration();
+		assert_eq!(resumed.points.get(1), &[1.0, 1.0]);
+	}
+
+	#[test]
+	fn test_checkpoint_header() {
+		let layout =
+			Layout::<f32>::from_graph(vec![(0, 1)], Nodes::Degree(2), None, Settings::default());
+		let mut buf = Vec::new();
+		layout.write_checkpoint(&mut buf).unwrap();
+
+		// Huge node or edge counts are not trusted before reading the data
+		for offset in [21, 29] {
+			let mut huge = buf.clone();
+			huge[offset..offset + 8].copy_from_slice(&(u64::MAX / 64).to_le_bytes());
+			assert_eq!(
+				Layout::<f32>::read_checkpoint(huge.as_slice())
+					.err()
+					.unwrap()
+					.kind(),
+				ErrorKind::UnexpectedEof
+			);
+		}
+
+		// Edges must respect `n1 < n2`
+		let mut settings = Vec::new();
+		write_settings(&mut settings, &layout.settings).unwrap();
+		// Magic, version, coordinate size, dimensions, nodes, edges, weighted, mass mode
+		let edges = 39 + settings.len();
+		for edge in [(1u64, 0u64), (1, 1)] {
+			let mut invalid = buf.clone();
+			invalid[edges..edges + 8].copy_from_slice(&edge.0.to_le_bytes());
+			invalid[edges + 8..edges + 16].copy_from_slice(&edge.1.to_le_bytes());
+			assert_eq!(
+				Layout::<f32>::read_checkpoint(invalid.as_slice())
+					.err()
+					.unwrap()
+					.kind(),
+				ErrorKind::InvalidData
+			);
+		}
+
+		let mut zero = buf;
+		zero[13..21].copy_from_slice(&0u64.to_le_bytes());
+		assert_eq!(
+			Layout::<f32>::read_checkpoint(zero.as_slice())
+				.err()
+				.unwrap()
+				.kind(),
+			ErrorKind::InvalidData
+		);
+	}
+}
@@ -0,0 +1,261 @@
+//! Geometric constraints, applied to the positions after each iteration
+//!
+//! Constraints are projections: after the forces moved the nodes, each constraint moves every node
+//! (except pinned ones) to the nearest allowed position. They are applied in order of registration,
+//! so a node is only guaranteed to respect the last constraint when they conflict.
+
+use crate::{layout::Layout, util::*};
+
+/// Projection of a node's position, registered with [`Layout::add_constraint`]
+pub trait Constraint<T: Coord> {
+	fn project(&self, node: usize, pos: &mut Position<T>);
+
+	/// Panics if the constraint cannot project positions of `dimensions` coordinates
+	///
+	/// Called by [`Layout::add_constraint`], so that an invalid constraint fails when registered rather than
+	/// during an iteration.
+	fn check(&self, _dimensions: usize) {}
+}
+
+/// Keeps the nodes in an axis-aligned box
+///
+/// `min` and `max` may have fewer coordinates than the layout: the other axes are not constrained.
+pub struct BoundingBox<T> {
+	pub min: Vec<T>,
+	pub max: Vec<T>,
+}
+
+impl<T: Coord> Constraint<T> for BoundingBox<T> {
+	fn project(&self, _node: usize, pos: &mut Position<T>) {
+		for ((pos, min), max) in pos.iter_mut().zip(self.min.iter()).zip(self.max.iter()) {
+			if *pos < *min {
+				*pos = min.clone();
+			} else if *pos > *max {
+				*pos = max.clone();
+			}
+		}
+	}
+}
+
+/// Keeps the nodes in a ball (a disc in 2D)
+///
+/// `center` must have as many coordinates as the layout.
+pub struct BoundingBall<T> {
+	pub center: Vec<T>,
+	pub radius: T,
+}
+
+impl<T: Coord> Constraint<T> for BoundingBall<T> {
+	fn project(&self, _node: usize, pos: &mut Position<T>) {
+		project_radius(pos, &self.center, &T::zero(), &self.radius);
+	}
+
+	fn check(&self, dimensions: usize) {
+		assert_eq!(self.center.len(), dimensions, "Wrong center dimensions");
+	}
+}
+
+/// Sets a coordinate of the nodes to given values (e.g. a timeline on x)
+///
+/// Nodes after the end of `values` are not constrained. `axis` must be less than the layout's dimensions.
+pub struct FixedAxis<T> {
+	pub axis: usize,
+	pub values: Vec<T>,
+}
+
+impl<T: Coord> Constraint<T> for FixedAxis<T> {
+	fn project(&self, node: usize, pos: &mut Position<T>) {
+		if let Some(value) = self.values.get(node) {
+			pos[self.axis] = value.clone();
+		}
+	}
+
+	fn check(&self, dimensions: usize) {
+		assert!(self.axis < dimensions, "Axis out of range");
+	}
+}
+
+/// Keeps the distance of each node to `center` in the band `(min, max)` of its category
+///
+/// Nodes after the end of `categories` are not constrained. `center` must have as many coordinates as the layout,
+/// and every category must have a band.
+pub struct RadialBand<T> {
+	pub center: Vec<T>,
+	/// `(min, max)` distance to the center, by category
+	pub bands: Vec<(T, T)>,
+	/// Category of each node
+	pub categories: Vec<usize>,
+}
+
+impl<T: Coord> Constraint<T> for RadialBand<T> {
+	fn project(&self, node: usize, pos: &mut Position<T>) {
+		if let Some(category) = self.categories.get(node) {
+			let (min, max) = &self.bands[*category];
+			project_radius(pos, &self.center, min, max);
+		}
+	}
+
+	fn check(&self, dimensions: usize) {
+		assert_eq!(self.center.len(), dimensions, "Wrong center dimensions");
+		assert!(
+			self.categories
+				.iter()
+				.all(|category| *category < self.bands.len()),
+			"Category without band"
+		);
+	}
+}
+
+/// Moves `pos` radially so that its distance to `center` is in `[min, max]`
+///
+/// `center` must have the same length as `pos`.
+fn project_radius<T: Coord>(pos: &mut Position<T>, center: &[T], min: &T, max: &T) {
+	let d = pos
+		.iter()
+		.zip(center.iter())
+		.map(|(pos, center)| (pos.clone() - center.clone()).pow_n(2u32))
+		.sum::<T>()
+		.sqrt();
+	let target = if d < *min {
+		min.clone()
+	} else if d > *max {
+		max.clone()
+	} else {
+		return;
+	};
+	if d.is_zero() {
+		// No direction: move along the first axis
+		pos[0] = center[0].clone() + target;
+		return;
+	}
+	let f = target / d;
+	for (pos, center) in pos.iter_mut().zip(center.iter()) {
+		*pos = center.clone() + (pos.clone() - center.clone()) * f.clone();
+	}
+}
+
+impl<T: Coord> Layout<T> {
+	/// Registers a constraint, applied after each iteration
+	///
+	/// # Panics
+	/// Panics if the constraint does not fit the layout's dimensions (see [`Constraint::check`]).
+	pub fn add_constraint(&mut self, constraint: Box<dyn Constraint<T> + Send + Sync>) {
+		constraint.check(self.settings.dimensions);
+		self.constraints.push(constraint);
+	}
+
+	/// Removes all the constraints
+	pub fn clear_constraints(&mut self) {
+		self.constraints.clear();
+	}
+
+	pub(crate) fn apply_constraints(&mut self) {
+		if self.constraints.is_empty() {
+			return;
+		}
+		for (node, pos) in self.points.iter_mut().enumerate() {
+			if self.pinned.get(node) == Some(&true) {
+				continue;
+			}
+			for constraint in self.constraints.iter() {
+				constraint.project(node, pos);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Settings;
+
+	#[test]
+	fn test_project() {
+		let mut pos = [3.0, -4.0, 1.0];
+		BoundingBox {
+			min: vec![-1.0, -1.0],
+			max: vec![1.0, 1.0],
+		}
+		.project(0, &mut pos);
+		assert_eq!(pos, [1.0, -1.0, 1.0]);
+
+		let mut pos = [3.0, 4.0];
+		BoundingBall {
+			center: vec![0.0, 0.0],
+			radius: 2.5,
+		}
+		.project(0, &mut pos);
+		assert_eq!(pos, [1.5, 2.0]);
+
+		let band = RadialBand {
+			center: vec![1.0, 1.0],
+			bands: vec![(2.0, 3.0), (0.0, 1.0)],
+			categories: vec![0, 1],
+		};
+		let mut pos = [1.0, 1.0];
+		band.project(0, &mut pos);
+		assert_eq!(pos, [3.0, 1.0]);
+		let mut pos = [1.0, 2.0];
+		band.project(0, &mut pos);
+		assert_eq!(pos, [1.0, 3.0]);
+		band.project(1, &mut pos);
+		assert_eq!(pos, [1.0, 2.0]);
+		// No category
+		band.project(2, &mut pos);
+		assert_eq!(pos, [1.0, 2.0]);
+	}
+
+	#[test]
+	fn test_constraints() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (1, 2), (2, 3)],
+			Nodes::Degree(4),
+			vec![0.0, 0.0, 1.0, 0.5, 2.0, -0.5, 3.0, 0.0],
+			None,
+			Settings {
+				kr: 100.0,
+				..Default::default()
+			},
+		);
+		layout.set_pinned(3, true);
+		layout.add_constraint(Box::new(FixedAxis {
+			axis: 0,
+			values: vec![0.0, 1.0, 2.0],
+		}));
+		layout.add_constraint(Box::new(BoundingBox {
+			min: vec![-10.0, -0.25],
+			max: vec![10.0, 0.25],
+		}));
+		for _ in 0..20 {
+			layout.iteration();
+			for (node, pos) in layout.points.iter().take(3).enumerate() {
+				assert_eq!(pos[0], node as f64);
+				assert!(pos[1].abs() <= 0.25);
+			}
+			assert_eq!(layout.points.get(3), &[3.0, 0.0]);
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "Axis out of range")]
+	fn test_check_axis() {
+		let mut layout =
+			Layout::<f64>::from_graph(vec![(0, 1)], Nodes::Degree(2), None, Settings::default());
+		layout.add_constraint(Box::new(FixedAxis {
+			axis: 2,
+			values: vec![0.0],
+		}));
+	}
+
+	#[test]
+	#[should_panic(expected = "Category without band")]
+	fn test_check_band() {
+		let mut layout =
+			Layout::<f64>::from_graph(vec![(0, 1)], Nodes::Degree(2), None, Settings::default());
+		layout.add_constraint(Box::new(RadialBand {
+			center: vec![0.0, 0.0],
+			bands: vec![(0.0, 1.0)],
+			categories: vec![0, 1],
+		}));
+	}
+}
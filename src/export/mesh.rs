@@ -0,0 +1,630 @@
+//! Triangle meshes of 3D layouts, e.g. for 3D printing
+//!
+//! Nodes are ico-spheres with a radius proportional to the square root of their mass,
+//! and edges are cylinders with a radius proportional to the square root of their weight.
+//! Layouts with less than 3 dimensions are flat (missing coordinates are zero), extra dimensions are ignored.
+//!
+//! ```no_run
+//! use forceatlas2::{export::mesh, Layout};
+//!
+//! # fn f(layout: &Layout<f32>) {
+//! let meshes = mesh::build(layout, &mesh::Options::default());
+//! let file = std::fs::File::create("layout.stl").unwrap();
+//! mesh::write_stl(&meshes, std::io::BufWriter::new(file)).unwrap();
+//! # }
+//! ```
+
+use crate::{layout::Layout, util::*};
+
+use std::{collections::HashMap, io::Write};
+
+pub struct Options {
+	/// Number of subdivisions of the icosahedron (each one multiplies the number of triangles by 4)
+	pub sphere_subdivisions: usize,
+	/// Number of sides of the cylinders
+	pub cylinder_segments: usize,
+	/// Radius of a node of mass 1
+	pub node_radius: f64,
+	/// Radius of an edge of weight 1 (or of every edge if the layout is not weighted)
+	///
+	/// If zero, edges are not exported.
+	pub edge_radius: f64,
+	/// Factor applied to the positions (not to the radii)
+	pub scale: f64,
+	/// Merge every node and edge into a single mesh
+	///
+	/// Otherwise there is one mesh per node, then one per edge.
+	pub merge: bool,
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			sphere_subdivisions: 2,
+			cylinder_segments: 8,
+			node_radius: 1.0,
+			edge_radius: 0.25,
+			scale: 1.0,
+			merge: true,
+		}
+	}
+}
+
+/// Indexed triangle mesh, with one normal per vertex
+///
+/// Triangles are counter-clockwise when seen from the outside.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+	pub name: String,
+	pub positions: Vec<[f32; 3]>,
+	pub normals: Vec<[f32; 3]>,
+	/// Vertex indices, 3 per triangle
+	pub indices: Vec<u32>,
+}
+
+impl Mesh {
+	pub fn nb_triangles(&self) -> usize {
+		self.indices.len() / 3
+	}
+
+	/// Appends `other`, scaled by `scale` then translated by `offset`
+	pub fn append(&mut self, other: &Mesh, scale: f32, offset: [f32; 3]) {
+		let first = self.positions.len() as u32;
+		self.positions.extend(other.positions.iter().map(|pos| {
+			[
+				pos[0] * scale + offset[0],
+				pos[1] * scale + offset[1],
+				pos[2] * scale + offset[2],
+			]
+		}));
+		self.normals.extend_from_slice(&other.normals);
+		self.indices
+			.extend(other.indices.iter().map(|index| index + first));
+	}
+
+	/// Bounding box `(min, max)`
+	pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+		self.positions.iter().fold(
+			([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+			|(min, max), pos| {
+				(
+					[min[0].min(pos[0]), min[1].min(pos[1]), min[2].min(pos[2])],
+					[max[0].max(pos[0]), max[1].max(pos[1]), max[2].max(pos[2])],
+				)
+			},
+		)
+	}
+}
+
+/// Sphere centered on the origin, made of `20 * 4^subdivisions` triangles
+pub fn ico_sphere(radius: f32, subdivisions: usize) -> Mesh {
+	let t = (1.0 + 5f32.sqrt()) / 2.0;
+	let mut vertices: Vec<[f32; 3]> = [
+		[-1.0, t, 0.0],
+		[1.0, t, 0.0],
+		[-1.0, -t, 0.0],
+		[1.0, -t, 0.0],
+		[0.0, -1.0, t],
+		[0.0, 1.0, t],
+		[0.0, -1.0, -t],
+		[0.0, 1.0, -t],
+		[t, 0.0, -1.0],
+		[t, 0.0, 1.0],
+		[-t, 0.0, -1.0],
+		[-t, 0.0, 1.0],
+	]
+	.into_iter()
+	.map(normalize)
+	.collect();
+	let mut triangles: Vec<[u32; 3]> = vec![
+		[0, 11, 5],
+		[0, 5, 1],
+		[0, 1, 7],
+		[0, 7, 10],
+		[0, 10, 11],
+		[1, 5, 9],
+		[5, 11, 4],
+		[11, 10, 2],
+		[10, 7, 6],
+		[7, 1, 8],
+		[3, 9, 4],
+		[3, 4, 2],
+		[3, 2, 6],
+		[3, 6, 8],
+		[3, 8, 9],
+		[4, 9, 5],
+		[2, 4, 11],
+		[6, 2, 10],
+		[8, 6, 7],
+		[9, 8, 1],
+	];
+
+	for _ in 0..subdivisions {
+		// Midpoints are shared by the two triangles of an edge
+		let mut midpoints = HashMap::<(u32, u32), u32>::new();
+		let mut midpoint = |a: u32, b: u32| {
+			*midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+				let (va, vb) = (vertices[a as usize], vertices[b as usize]);
+				vertices.push(normalize([va[0] + vb[0], va[1] + vb[1], va[2] + vb[2]]));
+				vertices.len() as u32 - 1
+			})
+		};
+		triangles = triangles
+			.into_iter()
+			.flat_map(|[a, b, c]| {
+				let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+				[[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+			})
+			.collect();
+	}
+
+	Mesh {
+		name: String::new(),
+		positions: vertices
+			.iter()
+			.map(|v| [v[0] * radius, v[1] * radius, v[2] * radius])
+			.collect(),
+		normals: vertices,
+		indices: triangles.into_iter().flatten().collect(),
+	}
+}
+
+/// Closed cylinder from `p1` to `p2`
+///
+/// Empty if `p1 == p2` or if there are less than 3 segments.
+pub fn cylinder(p1: [f32; 3], p2: [f32; 3], radius: f32, segments: usize) -> Mesh {
+	let mut mesh = Mesh::default();
+	let axis = [p2[0] - p1[0], p2[1] - p1[1], p2[2] - p1[2]];
+	if axis == [0.0; 3] || segments < 3 {
+		return mesh;
+	}
+	let d = normalize(axis);
+	// Orthonormal basis (u, v, d), using the axis least aligned with d
+	let e = if d[0].abs() <= d[1].abs() && d[0].abs() <= d[2].abs() {
+		[1.0, 0.0, 0.0]
+	} else if d[1].abs() <= d[2].abs() {
+		[0.0, 1.0, 0.0]
+	} else {
+		[0.0, 0.0, 1.0]
+	};
+	let u = normalize(cross(d, e));
+	let v = cross(d, u);
+	let directions: Vec<[f32; 3]> = (0..segments)
+		.map(|i| {
+			let (sin, cos) = (i as f32 * std::f32::consts::TAU / segments as f32).sin_cos();
+			[
+				u[0] * cos + v[0] * sin,
+				u[1] * cos + v[1] * sin,
+				u[2] * cos + v[2] * sin,
+			]
+		})
+		.collect();
+	let ring = |center: [f32; 3], dir: [f32; 3]| {
+		[
+			center[0] + dir[0] * radius,
+			center[1] + dir[1] * radius,
+			center[2] + dir[2] * radius,
+		]
+	};
+	let n = segments as u32;
+
+	// Side: bottom ring, then top ring
+	for center in [p1, p2] {
+		for dir in &directions {
+			mesh.positions.push(ring(center, *dir));
+			mesh.normals.push(*dir);
+		}
+	}
+	for i in 0..n {
+		let j = (i + 1) % n;
+		mesh.indices
+			.extend_from_slice(&[i, j, n + j, i, n + j, n + i]);
+	}
+
+	// Caps: center, then ring
+	for (center, normal, flip) in [(p1, [-d[0], -d[1], -d[2]], true), (p2, d, false)] {
+		let first = mesh.positions.len() as u32;
+		mesh.positions.push(center);
+		mesh.normals.push(normal);
+		for dir in &directions {
+			mesh.positions.push(ring(center, *dir));
+			mesh.normals.push(normal);
+		}
+		for i in 0..n {
+			let (a, b) = (first + 1 + i, first + 1 + (i + 1) % n);
+			if flip {
+				mesh.indices.extend_from_slice(&[first, b, a]);
+			} else {
+				mesh.indices.extend_from_slice(&[first, a, b]);
+			}
+		}
+	}
+
+	mesh
+}
+
+/// Builds the meshes of the layout
+///
+/// Returns a single mesh named `graph` if `options.merge`, otherwise meshes named `node {i}` then `edge {i}`.
+pub fn build<T: Coord>(layout: &Layout<T>, options: &Options) -> Vec<Mesh> {
+	let sphere = ico_sphere(1.0, options.sphere_subdivisions);
+	let position = |node: usize| {
+		let pos = layout.points.get(node);
+		let mut coords = [0.0; 3];
+		for (dst, src) in coords.iter_mut().zip(pos) {
+			*dst = (src.to_f64().unwrap() * options.scale) as f32;
+		}
+		coords
+	};
+
+	let mut meshes = Vec::new();
+	let mut merged = Mesh {
+		name: "graph".into(),
+		..Default::default()
+	};
+
+	for (node, mass) in layout.masses.iter().enumerate() {
+		let radius = (options.node_radius * crate::io::node_size(mass)) as f32;
+		if options.merge {
+			merged.append(&sphere, radius, position(node));
+		} else {
+			let mut mesh = Mesh {
+				name: format!("node {}", node),
+				..Default::default()
+			};
+			mesh.append(&sphere, radius, position(node));
+			meshes.push(mesh);
+		}
+	}
+
+	if options.edge_radius > 0.0 {
+		for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
+			let radius = layout
+				.weights
+				.as_ref()
+				.map_or(options.edge_radius, |weights| {
+					options.edge_radius * weights[edge].to_f64().unwrap().max(0.0).sqrt()
+				}) as f32;
+			if radius <= 0.0 {
+				continue;
+			}
+			let mut mesh = cylinder(
+				position(*n1),
+				position(*n2),
+				radius,
+				options.cylinder_segments,
+			);
+			if options.merge {
+				merged.append(&mesh, 1.0, [0.0; 3]);
+			} else {
+				mesh.name = format!("edge {}", edge);
+				meshes.push(mesh);
+			}
+		}
+	}
+
+	if options.merge {
+		meshes.push(merged);
+	}
+	meshes
+}
+
+/// Writes a binary STL file, with every mesh in the same solid
+pub fn write_stl<W: Write>(meshes: &[Mesh], mut writer: W) -> std::io::Result<()> {
+	writer.write_all(&[0; 80])?;
+	let nb_triangles: usize = meshes.iter().map(Mesh::nb_triangles).sum();
+	writer.write_all(&(nb_triangles as u32).to_le_bytes())?;
+	for mesh in meshes {
+		for triangle in mesh.indices.chunks_exact(3) {
+			let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[triangle[i] as usize]);
+			let normal = normalize(cross(
+				[b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+				[c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+			));
+			for vector in [normal, a, b, c] {
+				for x in vector {
+					writer.write_all(&x.to_le_bytes())?;
+				}
+			}
+			// Attribute byte count
+			writer.write_all(&[0, 0])?;
+		}
+	}
+	writer.flush()
+}
+
+/// Writes a Wavefront OBJ file, with one object per mesh
+pub fn write_obj<W: Write>(meshes: &[Mesh], mut writer: W) -> std::io::Result<()> {
+	// OBJ indices are global and start at 1
+	let mut first = 1;
+	for mesh in meshes {
+		if !mesh.name.is_empty() {
+			writeln!(writer, "o {}", mesh.name.replace(char::is_whitespace, "_"))?;
+		}
+		for pos in &mesh.positions {
+			writeln!(writer, "v {} {} {}", pos[0], pos[1], pos[2])?;
+		}
+		for normal in &mesh.normals {
+			writeln!(writer, "vn {} {} {}", normal[0], normal[1], normal[2])?;
+		}
+		for triangle in mesh.indices.chunks_exact(3) {
+			let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize + first);
+			writeln!(writer, "f {a}//{a} {b}//{b} {c}//{c}")?;
+		}
+		first += mesh.positions.len();
+	}
+	writer.flush()
+}
+
+/// Writes a binary glTF 2.0 file (`.glb`), with one scene node per mesh
+///
+/// Meshes without triangles are skipped, as glTF forbids empty accessors.
+pub fn write_glb<W: Write>(meshes: &[Mesh], mut writer: W) -> std::io::Result<()> {
+	let meshes: Vec<&Mesh> = meshes
+		.iter()
+		.filter(|mesh| !mesh.indices.is_empty())
+		.collect();
+
+	// Binary buffer: positions, normals and indices of each mesh, all 4-byte aligned
+	let mut buffer = Vec::<u8>::new();
+	let mut buffer_views = Vec::<u8>::new();
+	let mut accessors = Vec::<u8>::new();
+	let mut gltf_meshes = Vec::<u8>::new();
+	let mut nodes = Vec::<u8>::new();
+	let mut nb_views = 0;
+
+	let mut add_view = |buffer: &mut Vec<u8>, data: &[u8], target: u32| -> std::io::Result<usize> {
+		if nb_views != 0 {
+			buffer_views.push(b',');
+		}
+		write!(
+			buffer_views,
+			r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+			buffer.len(),
+			data.len(),
+			target
+		)?;
+		buffer.extend_from_slice(data);
+		nb_views += 1;
+		Ok(nb_views - 1)
+	};
+
+	for (i, mesh) in meshes.iter().enumerate() {
+		let sep = if i == 0 { "" } else { "," };
+		let nb_vertices = mesh.positions.len();
+
+		let positions: Vec<u8> = mesh
+			.positions
+			.iter()
+			.flatten()
+			.flat_map(|x| x.to_le_bytes())
+			.collect();
+		let normals: Vec<u8> = mesh
+			.normals
+			.iter()
+			.flatten()
+			.flat_map(|x| x.to_le_bytes())
+			.collect();
+		let indices: Vec<u8> = mesh.indices.iter().flat_map(|x| x.to_le_bytes()).collect();
+		let positions = add_view(&mut buffer, &positions, 34962)?;
+		let normals = add_view(&mut buffer, &normals, 34962)?;
+		let indices = add_view(&mut buffer, &indices, 34963)?;
+
+		// One accessor per view, with the same index. POSITION requires min and max.
+		let (min, max) = mesh.bounds();
+		write!(
+			accessors,
+			r#"{sep}{{"bufferView":{positions},"componentType":5126,"count":{nb_vertices},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}},{{"bufferView":{normals},"componentType":5126,"count":{nb_vertices},"type":"VEC3"}},{{"bufferView":{indices},"componentType":5125,"count":{},"type":"SCALAR"}}"#,
+			min[0],
+			min[1],
+			min[2],
+			max[0],
+			max[1],
+			max[2],
+			mesh.indices.len()
+		)?;
+		write!(
+			gltf_meshes,
+			r#"{sep}{{"primitives":[{{"attributes":{{"POSITION":{positions},"NORMAL":{normals}}},"indices":{indices}}}]}}"#
+		)?;
+		write!(nodes, r#"{sep}{{"mesh":{i},"name":"#)?;
+		crate::io::json::write_str(&mut nodes, &mesh.name)?;
+		nodes.push(b'}');
+	}
+
+	let mut json = Vec::<u8>::new();
+	json.extend_from_slice(br#"{"asset":{"version":"2.0","generator":"forceatlas2"}"#);
+	// Top-level arrays must not be empty: a file without meshes only has the asset
+	if !meshes.is_empty() {
+		write!(
+			json,
+			r#","scene":0,"scenes":[{{"nodes":[{}]}}],"nodes":["#,
+			(0..meshes.len())
+				.map(|i| i.to_string())
+				.collect::<Vec<_>>()
+				.join(",")
+		)?;
+		json.extend_from_slice(&nodes);
+		json.extend_from_slice(br#"],"meshes":["#);
+		json.extend_from_slice(&gltf_meshes);
+		json.extend_from_slice(br#"],"accessors":["#);
+		json.extend_from_slice(&accessors);
+		json.extend_from_slice(br#"],"bufferViews":["#);
+		json.extend_from_slice(&buffer_views);
+		write!(json, r#"],"buffers":[{{"byteLength":{}}}]"#, buffer.len())?;
+	}
+	json.push(b'}');
+	// Chunks are 4-byte aligned, JSON with spaces
+	while !json.len().is_multiple_of(4) {
+		json.push(b' ');
+	}
+
+	// The binary chunk is optional
+	let bin_len = if buffer.is_empty() {
+		0
+	} else {
+		8 + buffer.len()
+	};
+	let total = 12 + 8 + json.len() + bin_len;
+	writer.write_all(b"glTF")?;
+	writer.write_all(&2u32.to_le_bytes())?;
+	writer.write_all(&(total as u32).to_le_bytes())?;
+	writer.write_all(&(json.len() as u32).to_le_bytes())?;
+	writer.write_all(b"JSON")?;
+	writer.write_all(&json)?;
+	if !buffer.is_empty() {
+		writer.write_all(&(buffer.len() as u32).to_le_bytes())?;
+		writer.write_all(b"BIN\0")?;
+		writer.write_all(&buffer)?;
+	}
+	writer.flush()
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+	let norm = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+	if norm == 0.0 {
+		v
+	} else {
+		[v[0] / norm, v[1] / norm, v[2] / norm]
+	}
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+	[
+		a[1] * b[2] - a[2] * b[1],
+		a[2] * b[0] - a[0] * b[2],
+		a[0] * b[1] - a[1] * b[0],
+	]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Nodes, Settings};
+
+	#[test]
+	fn test_ico_sphere() {
+		let sphere = ico_sphere(2.0, 1);
+		assert_eq!(sphere.positions.len(), 42);
+		assert_eq!(sphere.nb_triangles(), 80);
+		for pos in &sphere.positions {
+			assert!(
+				((pos[0] * pos[0] + pos[1] * pos[1] + pos[2] * pos[2]).sqrt() - 2.0).abs() < 1e-5
+			);
+		}
+		// Outward faces
+		for triangle in sphere.indices.chunks_exact(3) {
+			let [a, b, c] = [0, 1, 2].map(|i| sphere.positions[triangle[i] as usize]);
+			let normal = cross(
+				[b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+				[c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+			);
+			assert!(normal[0] * a[0] + normal[1] * a[1] + normal[2] * a[2] > 0.0);
+		}
+	}
+
+	#[test]
+	fn test_cylinder() {
+		let mesh = cylinder([0.0, 0.0, 0.0], [0.0, 0.0, 3.0], 0.5, 6);
+		assert_eq!(mesh.positions.len(), 2 * 6 + 2 * 7);
+		assert_eq!(mesh.nb_triangles(), 4 * 6);
+		assert_eq!(mesh.bounds().0[2], 0.0);
+		assert_eq!(mesh.bounds().1[2], 3.0);
+		assert_eq!(
+			cylinder([1.0, 1.0, 1.0], [1.0, 1.0, 1.0], 0.5, 6),
+			Mesh::default()
+		);
+	}
+
+	#[test]
+	fn test_write() {
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			Nodes::Mass(vec![1.0, 4.0]),
+			vec![0.0, 0.0, 0.0, 10.0, 0.0, 0.0],
+			None,
+			Settings {
+				dimensions: 3,
+				..Default::default()
+			},
+		);
+		let options = Options {
+			sphere_subdivisions: 0,
+			cylinder_segments: 4,
+			..Default::default()
+		};
+
+		let meshes = build(&layout, &options);
+		assert_eq!(meshes.len(), 1);
+		assert_eq!(meshes[0].nb_triangles(), 2 * 20 + 4 * 4);
+		// Radius 2 for mass 4
+		let extent = ico_sphere(1.0, 0).bounds().1[0];
+		let (min, max) = meshes[0].bounds();
+		assert!((min[0] + extent).abs() < 1e-5);
+		assert!((max[0] - 10.0 - 2.0 * extent).abs() < 1e-5);
+		assert!((max[1] - 2.0 * extent).abs() < 1e-5);
+
+		let separate = build(
+			&layout,
+			&Options {
+				merge: false,
+				..options
+			},
+		);
+		assert_eq!(
+			separate
+				.iter()
+				.map(|mesh| mesh.name.as_str())
+				.collect::<Vec<_>>(),
+			vec!["node 0", "node 1", "edge 0"]
+		);
+
+		let mut stl = Vec::new();
+		write_stl(&meshes, &mut stl).unwrap();
+		assert_eq!(stl.len(), 84 + 50 * 56);
+		assert_eq!(&stl[80..84], &56u32.to_le_bytes());
+
+		let mut obj = Vec::new();
+		write_obj(&separate, &mut obj).unwrap();
+		let obj = String::from_utf8(obj).unwrap();
+		assert!(obj.starts_with("o node_0\nv "));
+		assert_eq!(obj.matches("\nf ").count(), 56);
+		// Indices of the second node start after the 12 vertices of the first one
+		assert!(obj.contains("o node_1\n"));
+		assert!(obj.contains("f 13//13 "));
+
+		let mut glb = Vec::new();
+		write_glb(&separate, &mut glb).unwrap();
+		assert_eq!(&glb[..4], b"glTF");
+		assert_eq!(&glb[8..12], &(glb.len() as u32).to_le_bytes());
+		assert_eq!(glb.len() % 4, 0);
+		let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+		let json = std::str::from_utf8(&glb[20..20 + json_len]).unwrap();
+		assert!(json.contains(r#""nodes":[0,1,2]"#));
+		assert!(json.contains(r#""name":"edge 0""#));
+		assert_eq!(&glb[24 + json_len..28 + json_len], b"BIN\0");
+
+		// Empty meshes are skipped, so that the JSON has no infinite bounds nor empty accessors
+		let mut glb = Vec::new();
+		write_glb(
+			&[Mesh::default(), separate[1].clone(), Mesh::default()],
+			&mut glb,
+		)
+		.unwrap();
+		let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+		let json = std::str::from_utf8(&glb[20..20 + json_len]).unwrap();
+		assert!(json.contains(r#""nodes":[0]"#));
+		assert!(json.contains(r#""name":"node 1""#));
+		assert!(!json.contains("inf"));
+		assert!(!json.contains(r#""count":0"#));
+
+		let mut glb = Vec::new();
+		write_glb(&[Mesh::default()], &mut glb).unwrap();
+		assert_eq!(&glb[8..12], &(glb.len() as u32).to_le_bytes());
+		let json = std::str::from_utf8(&glb[20..]).unwrap();
+		assert_eq!(
+			json.trim_end(),
+			r#"{"asset":{"version":"2.0","generator":"forceatlas2"}}"#
+		);
+	}
+}
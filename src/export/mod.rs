@@ -0,0 +1,3 @@
+//! Export layouts to non-graph formats
+
+pub mod mesh;
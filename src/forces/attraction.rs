@@ -307,3 +307,30 @@ pub fn apply_attraction_dh_log_po<T: Coord + std::fmt::Debug>(layout: &mut Layou
 		}
 	}
 }
+
+/// Attraction with cluster multipliers (see `Settings::clusters`)
+///
+/// The multipliers are applied to the edge weights, so that `kernel` (chosen for the other settings) is used.
+pub fn apply_attraction_clusters<T: Coord + std::fmt::Debug>(
+	layout: &mut Layout<T>,
+	kernel: fn(&mut Layout<T>),
+) {
+	let clusters = layout.settings.clusters.as_ref().unwrap();
+	let mut weights = std::mem::take(&mut layout.cluster_buffers.weights);
+	weights.clear();
+	weights.extend(layout.edges.iter().enumerate().map(|(edge, (n1, n2))| {
+		let weight = layout
+			.weights
+			.as_ref()
+			.map_or_else(T::one, |weights| weights[edge].clone());
+		match (layout.clusters.get(*n1), layout.clusters.get(*n2)) {
+			(Some(c1), Some(c2)) if c1 == c2 => weight * clusters.intra_attraction.clone(),
+			(Some(_), Some(_)) => weight * clusters.inter_attraction.clone(),
+			_ => weight,
+		}
+	}));
+
+	let weights = layout.weights.replace(weights);
+	kernel(layout);
+	layout.cluster_buffers.weights = std::mem::replace(&mut layout.weights, weights).unwrap();
+}
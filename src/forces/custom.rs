@@ -0,0 +1,203 @@
+use crate::{layout::Layout, util::*};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// User-defined force, registered with [`Layout::add_force`]
+///
+/// It is applied at each iteration after gravity, by adding to `layout.speeds`.
+/// Pairwise forces can use [`Layout::for_each_pair`] or [`Layout::par_for_each_pair`].
+pub trait Force<T: Coord> {
+	fn apply(&mut self, layout: &mut Layout<T>);
+}
+
+/// Node of a pair visited by [`Layout::for_each_pair`]
+pub struct PairNode<'a, T: Coord> {
+	pub index: usize,
+	pub mass: &'a T,
+	pub pos: &'a Position<T>,
+	pub speed: &'a mut Position<T>,
+}
+
+impl<T: Coord> Layout<T> {
+	/// Registers a force, applied at each iteration
+	pub fn add_force(&mut self, force: Box<dyn Force<T> + Send + Sync>) {
+		self.forces.push(force);
+	}
+
+	/// Removes all the user-defined forces
+	pub fn clear_forces(&mut self) {
+		self.forces.clear();
+	}
+
+	/// Calls `f` once for each unordered pair of distinct nodes
+	pub fn for_each_pair<F>(&mut self, mut f: F)
+	where
+		F: FnMut(PairNode<T>, PairNode<T>),
+	{
+		for n1 in self.iter_nodes() {
+			for n2 in n1.n2_iter {
+				f(
+					PairNode {
+						index: n1.ind,
+						mass: n1.mass,
+						pos: n1.pos,
+						speed: &mut *n1.speed,
+					},
+					PairNode {
+						index: n2.ind,
+						mass: n2.mass,
+						pos: n2.pos,
+						speed: n2.speed,
+					},
+				);
+			}
+		}
+	}
+
+	/// Calls `f` once for each unordered pair of distinct nodes, in parallel
+	///
+	/// Pairs are computed by tiles of `chunk_size²` pairs. Tiles computed concurrently never share a node,
+	/// so `f` has exclusive access to the speeds of the pair.
+	#[cfg(feature = "parallel")]
+	pub fn par_for_each_pair<F>(&mut self, chunk_size: usize, f: F)
+	where
+		F: Fn(PairNode<T>, PairNode<T>) + Sync,
+		T: Send + Sync,
+	{
+		for level in self.iter_par_nodes(chunk_size) {
+			level.for_each(|n1_iter| {
+				for n1 in n1_iter {
+					for n2 in n1.n2_iter {
+						f(
+							PairNode {
+								index: n1.ind,
+								mass: n1.mass,
+								pos: n1.pos,
+								speed: &mut *n1.speed,
+							},
+							PairNode {
+								index: n2.ind,
+								mass: n2.mass,
+								pos: n2.pos,
+								speed: n2.speed,
+							},
+						);
+					}
+				}
+			});
+		}
+	}
+
+	pub(crate) fn apply_custom_forces(&mut self) {
+		if self.forces.is_empty() {
+			return;
+		}
+		// Forces are taken out so that they can borrow the layout
+		let mut forces = std::mem::take(&mut self.forces);
+		for force in forces.iter_mut() {
+			force.apply(self);
+		}
+		// Keep the forces added during application
+		let added = std::mem::replace(&mut self.forces, forces);
+		self.forces.extend(added);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Settings;
+
+	/// Pulls every node toward the centroid of its group
+	struct GroupCentroid {
+		groups: Vec<usize>,
+		k: f64,
+	}
+
+	impl Force<f64> for GroupCentroid {
+		fn apply(&mut self, layout: &mut Layout<f64>) {
+			let nb_groups = self.groups.iter().max().map_or(0, |max| max + 1);
+			let mut centroids = vec![[0.0; 2]; nb_groups];
+			let mut sizes = vec![0.0; nb_groups];
+			for (pos, group) in layout.points.iter().zip(self.groups.iter()) {
+				centroids[*group][0] += pos[0];
+				centroids[*group][1] += pos[1];
+				sizes[*group] += 1.0;
+			}
+			for (speed, (pos, group)) in layout
+				.speeds
+				.iter_mut()
+				.zip(layout.points.iter().zip(self.groups.iter()))
+			{
+				for ((speed, pos), centroid) in speed.iter_mut().zip(pos).zip(centroids[*group]) {
+					*speed += (centroid / sizes[*group] - pos) * self.k;
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn test_custom_force() {
+		let positions = vec![0.0, 0.0, 10.0, 0.0, 0.0, 10.0, 10.0, 10.0];
+		let settings = Settings {
+			ka: 0.0,
+			kg: 0.0,
+			kr: 0.0,
+			..Default::default()
+		};
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![],
+			Nodes::Mass(vec![1.0; 4]),
+			positions,
+			None,
+			settings,
+		);
+		layout.add_force(Box::new(GroupCentroid {
+			groups: vec![0, 0, 1, 1],
+			k: 1.0,
+		}));
+		layout.iteration();
+		// Nodes move toward their group's centroid
+		assert!(layout.points.get(0)[0] > 0.0);
+		assert!(layout.points.get(1)[0] < 10.0);
+		assert_eq!(layout.points.get(0)[1], 0.0);
+		assert_eq!(layout.points.get(2)[1], 10.0);
+
+		layout.clear_forces();
+		let points = layout.points.clone();
+		layout.iteration();
+		assert_eq!(layout.points.points, points.points);
+	}
+
+	#[test]
+	fn test_for_each_pair() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![],
+			Nodes::Mass(vec![1.0; 37]),
+			vec![0.0; 37 * 2],
+			None,
+			Settings::default(),
+		);
+		let mut pairs = 0;
+		layout.for_each_pair(|n1, n2| {
+			assert!(n1.index < n2.index);
+			n1.speed[0] += 1.0;
+			n2.speed[0] += 1.0;
+			pairs += 1;
+		});
+		assert_eq!(pairs, 37 * 36 / 2);
+		assert!(layout.speeds.iter().all(|speed| speed[0] == 36.0));
+
+		// Every node is in 36 pairs: lost updates would be a data race between tiles
+		#[cfg(feature = "parallel")]
+		for chunk_size in [1, 2, 3, 5, 8, 64] {
+			layout.speeds.iter_mut().for_each(|speed| speed[0] = 0.0);
+			layout.par_for_each_pair(chunk_size, |n1, n2| {
+				n1.speed[0] += 1.0;
+				n2.speed[0] += 1.0;
+			});
+			assert!(layout.speeds.iter().all(|speed| speed[0] == 36.0));
+		}
+	}
+}
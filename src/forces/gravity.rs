@@ -31,3 +31,60 @@ pub fn apply_gravity_sg<T: Coord + std::fmt::Debug>(layout: &mut Layout<T>) {
 		}
 	}
 }
+
+/// Gravity toward the centroid of each node's cluster (see `Settings::clusters`), after the global gravity
+pub fn apply_gravity_clusters<T: Coord + std::fmt::Debug>(layout: &mut Layout<T>) {
+	let kc = layout.settings.clusters.as_ref().unwrap().gravity.clone();
+	let Some(nb_clusters) = layout.clusters.iter().max().map(|max| max + 1) else {
+		return;
+	};
+	if kc.is_zero() {
+		return;
+	}
+	let dimensions = layout.settings.dimensions;
+
+	let centroids = &mut layout.cluster_buffers.centroids;
+	let sizes = &mut layout.cluster_buffers.sizes;
+	centroids.clear();
+	centroids.resize(nb_clusters * dimensions, T::zero());
+	sizes.clear();
+	sizes.resize(nb_clusters, T::zero());
+	for (cluster, pos) in layout.clusters.iter().zip(layout.points.iter()) {
+		sizes[*cluster] += T::one();
+		for (centroid, pos) in centroids[cluster * dimensions..(cluster + 1) * dimensions]
+			.iter_mut()
+			.zip(pos.iter())
+		{
+			*centroid += pos.clone();
+		}
+	}
+	for (centroid, size) in centroids.chunks_exact_mut(dimensions).zip(sizes.iter()) {
+		for centroid in centroid.iter_mut() {
+			*centroid /= size.clone();
+		}
+	}
+
+	let mut di = valloc(dimensions);
+	for (cluster, mass, pos, speed) in izip!(
+		layout.clusters.iter(),
+		layout.masses.iter(),
+		layout.points.iter(),
+		layout.speeds.iter_mut()
+	) {
+		let centroid = &centroids[cluster * dimensions..(cluster + 1) * dimensions];
+		for (di, (pos, centroid)) in di.iter_mut().zip(pos.iter().zip(centroid.iter())) {
+			*di = pos.clone() - centroid.clone();
+		}
+		let d = norm(&di);
+		if d.is_zero() {
+			continue;
+		}
+		let mut f = (mass.clone() + T::one()) * kc.clone();
+		if !layout.settings.strong_gravity {
+			f /= d;
+		}
+		for (speed, di) in speed.iter_mut().zip(di.iter()) {
+			*speed -= f.clone() * di.clone();
+		}
+	}
+}
@@ -1,7 +1,10 @@
 pub mod attraction;
+mod custom;
 pub mod gravity;
 pub mod repulsion;
 
+pub use custom::{Force, PairNode};
+
 use crate::{
 	layout::{Layout, Settings},
 	util::*,
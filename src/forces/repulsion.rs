@@ -930,7 +930,7 @@ pub fn apply_repulsion_po<T: Coord + std::fmt::Debug>(layout: &mut Layout<T>) {
 		let mut n2_iter = layout.points.iter();
 		let n1_mass = n1_mass.clone() + T::one();
 		n2_iter.offset = (n1 + 1) * layout.settings.dimensions;
-		for (n2, n2_pos) in (0..n1).zip(&mut n2_iter) {
+		for (n2, n2_pos) in (n1 + 1..).zip(&mut n2_iter) {
 			di.clone_from_slice(n2_pos);
 
 			let d2 = di
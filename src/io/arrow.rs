@@ -0,0 +1,180 @@
+//! [Apache Arrow](https://arrow.apache.org/) export
+//!
+//! Requires the `arrow` feature. Writing Parquet files requires the `parquet` feature.
+//!
+//! Each column is filled in a single pass over the layout, without per-row allocations.
+
+use super::*;
+
+use ::arrow::{
+	array::{ArrayRef, PrimitiveArray, StringArray, UInt64Array},
+	datatypes::{ArrowPrimitiveType, DataType, Field, Float32Type, Float64Type, Schema},
+	error::ArrowError,
+	record_batch::RecordBatch,
+};
+use std::sync::Arc;
+
+/// Coordinate type having an Arrow equivalent
+pub trait ArrowCoord: Coord + Copy {
+	type ArrowType: ArrowPrimitiveType<Native = Self>;
+}
+
+impl ArrowCoord for f32 {
+	type ArrowType = Float32Type;
+}
+
+impl ArrowCoord for f64 {
+	type ArrowType = Float64Type;
+}
+
+/// Name of the position column for this axis: `x`, `y`, `z`, then `d3`, `d4`...
+pub fn axis_name(axis: usize) -> String {
+	["x", "y", "z"]
+		.get(axis)
+		.map_or_else(|| format!("d{}", axis), |name| name.to_string())
+}
+
+/// Nodes as a record batch with columns `id`, `mass`, `x`, `y`[, `z`...]
+///
+/// `id` is `ids` (utf8) if provided, or the node index (uint64).
+pub fn nodes<T: ArrowCoord>(
+	layout: &Layout<T>,
+	ids: Option<&[String]>,
+) -> Result<RecordBatch, ArrowError> {
+	let nb_nodes = layout.masses.len();
+	let dimensions = layout.settings.dimensions;
+	let mut fields = Vec::with_capacity(dimensions + 2);
+	let mut columns = Vec::<ArrayRef>::with_capacity(dimensions + 2);
+
+	if let Some(ids) = ids {
+		assert_eq!(ids.len(), nb_nodes);
+		fields.push(Field::new("id", DataType::Utf8, false));
+		columns.push(Arc::new(StringArray::from_iter_values(ids)));
+	} else {
+		fields.push(Field::new("id", DataType::UInt64, false));
+		columns.push(Arc::new(UInt64Array::from_iter_values(0..nb_nodes as u64)));
+	}
+
+	fields.push(Field::new("mass", T::ArrowType::DATA_TYPE, false));
+	columns.push(Arc::new(PrimitiveArray::<T::ArrowType>::from_iter_values(
+		layout.masses.iter().copied(),
+	)));
+
+	for axis in 0..dimensions {
+		fields.push(Field::new(axis_name(axis), T::ArrowType::DATA_TYPE, false));
+		columns.push(Arc::new(PrimitiveArray::<T::ArrowType>::from_iter_values(
+			layout
+				.points
+				.points
+				.iter()
+				.skip(axis)
+				.step_by(dimensions)
+				.copied(),
+		)));
+	}
+
+	RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+}
+
+/// Edges as a record batch with columns `source`, `target` (node indices, uint64) and `weight` if the layout is weighted
+pub fn edges<T: ArrowCoord>(layout: &Layout<T>) -> Result<RecordBatch, ArrowError> {
+	let mut fields = vec![
+		Field::new("source", DataType::UInt64, false),
+		Field::new("target", DataType::UInt64, false),
+	];
+	let mut columns: Vec<ArrayRef> = vec![
+		Arc::new(UInt64Array::from_iter_values(
+			layout.edges.iter().map(|(n1, _)| *n1 as u64),
+		)),
+		Arc::new(UInt64Array::from_iter_values(
+			layout.edges.iter().map(|(_, n2)| *n2 as u64),
+		)),
+	];
+	if let Some(weights) = &layout.weights {
+		fields.push(Field::new("weight", T::ArrowType::DATA_TYPE, false));
+		columns.push(Arc::new(PrimitiveArray::<T::ArrowType>::from_iter_values(
+			weights.iter().copied(),
+		)));
+	}
+
+	RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+}
+
+/// Writes a record batch as a Parquet file
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: std::io::Write + Send>(
+	batch: &RecordBatch,
+	writer: W,
+) -> Result<(), ::parquet::errors::ParquetError> {
+	let mut writer = ::parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)?;
+	writer.write(batch)?;
+	writer.close()?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use ::arrow::array::{AsArray, Float64Array};
+
+	#[test]
+	fn test_batches() {
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (1, 2)],
+			Nodes::Mass(vec![1.0, 2.0, 3.0]),
+			vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+			Some(vec![0.5, 1.5]),
+			Settings::default(),
+		);
+
+		let batch = nodes(&layout, None).unwrap();
+		assert_eq!(batch.num_rows(), 3);
+		assert_eq!(
+			batch
+				.schema()
+				.fields()
+				.iter()
+				.map(|field| field.name().as_str())
+				.collect::<Vec<_>>(),
+			vec!["id", "mass", "x", "y"]
+		);
+		assert_eq!(
+			batch
+				.column(0)
+				.as_primitive::<::arrow::datatypes::UInt64Type>(),
+			&UInt64Array::from(vec![0, 1, 2])
+		);
+		assert_eq!(
+			batch.column(3).as_primitive::<Float64Type>(),
+			&Float64Array::from(vec![2.0, 4.0, 6.0])
+		);
+
+		let ids = ["a".to_string(), "b".to_string(), "c".to_string()];
+		let batch = nodes(&layout, Some(&ids)).unwrap();
+		assert_eq!(
+			batch.column(0).as_string::<i32>(),
+			&StringArray::from(vec!["a", "b", "c"])
+		);
+
+		let batch = edges(&layout).unwrap();
+		assert_eq!(batch.num_rows(), 2);
+		assert_eq!(
+			batch
+				.column(1)
+				.as_primitive::<::arrow::datatypes::UInt64Type>(),
+			&UInt64Array::from(vec![1, 2])
+		);
+		assert_eq!(
+			batch.column(2).as_primitive::<Float64Type>(),
+			&Float64Array::from(vec![0.5, 1.5])
+		);
+
+		#[cfg(feature = "parquet")]
+		{
+			let mut output = Vec::new();
+			write_parquet(&batch, &mut output).unwrap();
+			assert_eq!(&output[..4], b"PAR1");
+		}
+	}
+}
@@ -0,0 +1,514 @@
+//! [Graphviz DOT](https://graphviz.org/doc/info/lang.html) export and edge list import
+//!
+//! Exported positions are pinned (`pos="x,y!"`), so the layout can be rendered as is by `neato -n`:
+//!
+//! ```sh
+//! neato -n -Tsvg layout.dot > layout.svg
+//! ```
+
+use super::*;
+
+use std::io::{BufRead, Write};
+
+/// Edge attribute for weights that Graphviz does not accept as `weight` (fractional or negative)
+pub const WEIGHT_ATTRIBUTE: &str = "fa2_weight";
+
+pub struct WriteOptions {
+	/// Write a `digraph`, with `->` edges
+	pub directed: bool,
+	/// Factor applied to the coordinates (Graphviz positions are in points)
+	pub scale: f64,
+	/// Write edge weights as `penwidth`
+	pub penwidth: bool,
+	/// If set, nodes have a fixed diameter of `node_size * sqrt(mass)` inches
+	pub node_size: Option<f64>,
+}
+
+impl Default for WriteOptions {
+	fn default() -> Self {
+		Self {
+			directed: false,
+			scale: 1.0,
+			penwidth: true,
+			node_size: None,
+		}
+	}
+}
+
+/// Writes a DOT graph with the layout's positions
+///
+/// Node IDs are `ids` if provided, or the node indices.
+/// Edges are written as `(n1, n2)`, so only directed graphs whose edges are in this order keep their direction.
+/// Graphviz only accepts non-negative integer weights: other weights are written as [`WEIGHT_ATTRIBUTE`].
+pub fn write<T: Coord, W: Write>(
+	layout: &Layout<T>,
+	ids: Option<&[String]>,
+	options: &WriteOptions,
+	mut writer: W,
+) -> std::io::Result<()> {
+	let id = |node: usize| {
+		ids.map_or_else(
+			|| node.to_string(),
+			|ids| ids[node].replace('\\', "\\\\").replace('"', "\\\""),
+		)
+	};
+	let (keyword, edge_op) = if options.directed {
+		("digraph", "->")
+	} else {
+		("graph", "--")
+	};
+
+	writeln!(writer, "{} {{", keyword)?;
+	if options.node_size.is_some() {
+		writeln!(writer, "\tnode [fixedsize=true];")?;
+	}
+	for (node, pos) in layout.points.iter().enumerate() {
+		let coords: Vec<String> = pos
+			.iter()
+			.take(3)
+			.map(|coord| (coord.to_f64().unwrap() * options.scale).to_string())
+			.collect();
+		write!(writer, "\t\"{}\" [pos=\"{}!\"", id(node), coords.join(","))?;
+		if let Some(node_size) = options.node_size {
+			let size = node_size * super::node_size(&layout.masses[node]);
+			write!(writer, ", width={0}, height={0}", size)?;
+		}
+		writeln!(writer, "];")?;
+	}
+	for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
+		write!(writer, "\t\"{}\" {} \"{}\"", id(*n1), edge_op, id(*n2))?;
+		if let Some(weights) = &layout.weights {
+			let weight = weights[edge].to_f64().unwrap();
+			let key = if weight >= 0.0 && weight.fract() == 0.0 {
+				"weight"
+			} else {
+				WEIGHT_ATTRIBUTE
+			};
+			write!(writer, " [{}={}", key, weight)?;
+			if options.penwidth {
+				write!(writer, ", penwidth={}", weight)?;
+			}
+			write!(writer, "]")?;
+		}
+		writeln!(writer, ";")?;
+	}
+	writeln!(writer, "}}")
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+	Id(String),
+	/// `--` or `->`
+	EdgeOp,
+	Eq,
+	Colon,
+	LBracket,
+	RBracket,
+	/// `{`, `}`, `;` or `,`
+	Separator,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, Error> {
+	let mut tokens = Vec::new();
+	let mut chars = input.chars().peekable();
+	let mut line = 1;
+	while let Some(c) = chars.next() {
+		let start_line = line;
+		let token = match c {
+			'\n' => {
+				line += 1;
+				continue;
+			}
+			c if c.is_whitespace() => continue,
+			'#' => {
+				while chars.next_if(|c| *c != '\n').is_some() {}
+				continue;
+			}
+			'/' if chars.next_if_eq(&'/').is_some() => {
+				while chars.next_if(|c| *c != '\n').is_some() {}
+				continue;
+			}
+			'/' if chars.next_if_eq(&'*').is_some() => {
+				let mut star = false;
+				loop {
+					match chars.next() {
+						Some('/') if star => break,
+						Some(c) => {
+							star = c == '*';
+							if c == '\n' {
+								line += 1;
+							}
+						}
+						None => {
+							return Err(Error::Parse {
+								line: start_line,
+								msg: "Unterminated comment".into(),
+							})
+						}
+					}
+				}
+				continue;
+			}
+			'"' => {
+				let mut id = String::new();
+				loop {
+					match chars.next() {
+						Some('"') => break,
+						Some('\\') => match chars.next() {
+							Some(c @ ('"' | '\\')) => id.push(c),
+							// Line continuation
+							Some('\n') => line += 1,
+							Some(c) => {
+								id.push('\\');
+								id.push(c);
+							}
+							None => {}
+						},
+						Some(c) => {
+							if c == '\n' {
+								line += 1;
+							}
+							id.push(c);
+						}
+						None => {
+							return Err(Error::Parse {
+								line: start_line,
+								msg: "Unterminated string".into(),
+							})
+						}
+					}
+				}
+				Token::Id(id)
+			}
+			'<' => {
+				let mut id = String::new();
+				let mut depth = 1;
+				loop {
+					match chars.next() {
+						Some('>') if depth == 1 => break,
+						Some(c) => {
+							match c {
+								'<' => depth += 1,
+								'>' => depth -= 1,
+								'\n' => line += 1,
+								_ => {}
+							}
+							id.push(c);
+						}
+						None => {
+							return Err(Error::Parse {
+								line: start_line,
+								msg: "Unterminated HTML string".into(),
+							})
+						}
+					}
+				}
+				Token::Id(id)
+			}
+			'-' if chars.next_if(|c| *c == '-' || *c == '>').is_some() => Token::EdgeOp,
+			'=' => Token::Eq,
+			':' => Token::Colon,
+			'[' => Token::LBracket,
+			']' => Token::RBracket,
+			'{' | '}' | ';' | ',' => Token::Separator,
+			c if is_id_char(c) || c == '-' => {
+				let mut id = String::from(c);
+				while let Some(c) = chars.next_if(|c| is_id_char(*c)) {
+					id.push(c);
+				}
+				Token::Id(id)
+			}
+			c => {
+				return Err(Error::Parse {
+					line,
+					msg: format!("Unexpected character `{}`", c),
+				})
+			}
+		};
+		tokens.push((start_line, token));
+	}
+	Ok(tokens)
+}
+
+fn is_id_char(c: char) -> bool {
+	c.is_alphanumeric() || c == '_' || c == '.' || !c.is_ascii()
+}
+
+/// Reads the edges of a DOT graph
+///
+/// Only node and edge statements are interpreted: attributes other than the edge `weight` (or [`WEIGHT_ATTRIBUTE`]),
+/// ports and subgraph grouping are ignored, and edges to subgraphs are not supported.
+/// The graph is weighted if at least one edge has a `weight` attribute (missing weights are 1).
+/// In a `graph` (not `digraph`), duplicate edges are merged.
+pub fn read<T: Coord, R: BufRead>(mut reader: R) -> Result<Graph<T>, Error> {
+	let mut input = String::new();
+	reader.read_to_string(&mut input)?;
+	let tokens = tokenize(&input)?;
+	let mut tokens = tokens.iter().peekable();
+
+	// Header: [strict] (graph|digraph) [ID] {
+	let mut directed = None;
+	for (line, token) in tokens.by_ref() {
+		match token {
+			Token::Id(id) if id.eq_ignore_ascii_case("graph") => directed = Some(false),
+			Token::Id(id) if id.eq_ignore_ascii_case("digraph") => directed = Some(true),
+			Token::Separator if directed.is_some() => break,
+			Token::Id(_) => {}
+			_ => {
+				return Err(Error::Parse {
+					line: *line,
+					msg: "Expected `graph` or `digraph`".into(),
+				})
+			}
+		}
+	}
+	let directed = directed.ok_or(Error::Parse {
+		line: 1,
+		msg: "Expected `graph` or `digraph`".into(),
+	})?;
+
+	let mut labels = Labels::default();
+	let mut edges = Vec::<(usize, usize, Option<f64>)>::new();
+
+	while let Some((line, token)) = tokens.next() {
+		match token {
+			Token::Separator => {}
+			Token::Id(keyword)
+				if ["graph", "node", "edge"]
+					.iter()
+					.any(|k| keyword.eq_ignore_ascii_case(k))
+					&& matches!(tokens.peek(), Some((_, Token::LBracket))) =>
+			{
+				read_attributes(&mut tokens)?;
+			}
+			Token::Id(keyword) if keyword.eq_ignore_ascii_case("subgraph") => {
+				tokens.next_if(|(_, token)| matches!(token, Token::Id(_)));
+			}
+			Token::Id(_) if matches!(tokens.peek(), Some((_, Token::Eq))) => {
+				tokens.next();
+				tokens.next();
+			}
+			Token::Id(id) => {
+				let mut chain = vec![labels.get_or_insert(id)];
+				skip_port(&mut tokens);
+				while tokens
+					.next_if(|(_, token)| *token == Token::EdgeOp)
+					.is_some()
+				{
+					match tokens.next() {
+						Some((_, Token::Id(id))) => chain.push(labels.get_or_insert(id)),
+						Some((line, _)) => {
+							return Err(Error::Parse {
+								line: *line,
+								msg: "Expected node ID (edges to subgraphs are not supported)"
+									.into(),
+							})
+						}
+						None => {
+							return Err(Error::Parse {
+								line: *line,
+								msg: "Unexpected end of file".into(),
+							})
+						}
+					}
+					skip_port(&mut tokens);
+				}
+				let weight = read_attributes(&mut tokens)?;
+				edges.extend(chain.windows(2).map(|pair| (pair[0], pair[1], weight)));
+			}
+			_ => {
+				return Err(Error::Parse {
+					line: *line,
+					msg: format!("Unexpected {:?}", token),
+				})
+			}
+		}
+	}
+
+	let weighted = edges.iter().any(|(_, _, weight)| weight.is_some());
+	let mut collector = EdgeCollector::new(weighted, directed);
+	for (n1, n2, weight) in edges {
+		collector.push(n1, n2, weight.map(|weight| T::from_f64(weight).unwrap()));
+	}
+	Ok(Graph {
+		edges: collector.edges,
+		nb_nodes: labels.len(),
+		labels: Some(labels.labels),
+		weights: collector.weights,
+	})
+}
+
+type Tokens<'a> = std::iter::Peekable<std::slice::Iter<'a, (usize, Token)>>;
+
+fn skip_port(tokens: &mut Tokens) {
+	while tokens
+		.next_if(|(_, token)| *token == Token::Colon)
+		.is_some()
+	{
+		tokens.next_if(|(_, token)| matches!(token, Token::Id(_)));
+	}
+}
+
+/// Reads the attribute lists following a statement, returning the `weight` attribute
+fn read_attributes(tokens: &mut Tokens) -> Result<Option<f64>, Error> {
+	let mut weight = None;
+	while let Some((start_line, _)) = tokens.next_if(|(_, token)| *token == Token::LBracket) {
+		loop {
+			match tokens.next() {
+				Some((_, Token::RBracket)) => break,
+				Some((_, Token::Separator)) => {}
+				Some((line, Token::Id(key))) => {
+					if tokens.next_if(|(_, token)| *token == Token::Eq).is_none() {
+						continue;
+					}
+					match tokens.next() {
+						Some((line, Token::Id(value))) => {
+							if key == "weight" || key == WEIGHT_ATTRIBUTE {
+								weight = Some(value.parse().map_err(|_| Error::Parse {
+									line: *line,
+									msg: format!("Invalid weight `{}`", value),
+								})?);
+							}
+						}
+						_ => {
+							return Err(Error::Parse {
+								line: *line,
+								msg: format!("Expected value for attribute `{}`", key),
+							})
+						}
+					}
+				}
+				Some((line, token)) => {
+					return Err(Error::Parse {
+						line: *line,
+						msg: format!("Unexpected {:?} in attribute list", token),
+					})
+				}
+				None => {
+					return Err(Error::Parse {
+						line: *start_line,
+						msg: "Unterminated attribute list".into(),
+					})
+				}
+			}
+		}
+	}
+	Ok(weight)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_read() {
+		let input = r#"
+/* Example */
+strict graph G {
+	graph [splines=true];
+	node [shape=circle]
+	rankdir = LR
+	a -- b -- "c d" [weight=2, color="red"]; // chain
+	b:n -- a [weight=0.5]
+	# single node
+	e [label="E"]
+	subgraph cluster_0 { f -- a }
+}
+"#;
+		let graph = read::<f64, _>(input.as_bytes()).unwrap();
+		assert_eq!(
+			graph.labels,
+			Some(vec![
+				"a".to_string(),
+				"b".to_string(),
+				"c d".to_string(),
+				"e".to_string(),
+				"f".to_string()
+			])
+		);
+		assert_eq!(graph.nb_nodes, 5);
+		assert_eq!(graph.edges, vec![(0, 1), (1, 2), (0, 4)]);
+		assert_eq!(graph.weights, Some(vec![2.5, 2.0, 1.0]));
+
+		let graph = read::<f64, _>("digraph { 1 -> 2; 2 -> 1 }".as_bytes()).unwrap();
+		assert_eq!(graph.edges, vec![(0, 1), (0, 1)]);
+		assert_eq!(graph.weights, None);
+
+		match read::<f64, _>("graph {\na -- b [weight=x]\n}".as_bytes()) {
+			Err(Error::Parse { line: 2, .. }) => {}
+			_ => panic!("Expected parse error at line 2"),
+		}
+		match read::<f64, _>("graph {\na -- b\n[weight=1,\ncolor=red".as_bytes()) {
+			Err(Error::Parse { line: 3, .. }) => {}
+			_ => panic!("Expected parse error at line 3"),
+		}
+	}
+
+	#[test]
+	fn test_write() {
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			Nodes::Mass(vec![1.0, 4.0]),
+			vec![1.0, 2.0, 3.0, -4.5],
+			Some(vec![3.0]),
+			Settings::default(),
+		);
+		let mut output = Vec::new();
+		write(
+			&layout,
+			Some(&["a".to_string(), "\"b\"".to_string()]),
+			&WriteOptions {
+				scale: 2.0,
+				node_size: Some(0.5),
+				..Default::default()
+			},
+			&mut output,
+		)
+		.unwrap();
+		let output = String::from_utf8(output).unwrap();
+		assert_eq!(
+			output,
+			r#"graph {
+	node [fixedsize=true];
+	"a" [pos="2,4!", width=0.5, height=0.5];
+	"\"b\"" [pos="6,-9!", width=1, height=1];
+	"a" -- "\"b\"" [weight=3, penwidth=3];
+}
+"#
+		);
+
+		let graph = read::<f64, _>(output.as_bytes()).unwrap();
+		assert_eq!(
+			graph.labels,
+			Some(vec!["a".to_string(), "\"b\"".to_string()])
+		);
+		assert_eq!(graph.edges, vec![(0, 1)]);
+		assert_eq!(graph.weights, Some(vec![3.0]));
+
+		// Fractional weight and trailing backslash
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			Nodes::Degree(2),
+			vec![0.0, 0.0, 1.0, 1.0],
+			Some(vec![2.5]),
+			Settings::default(),
+		);
+		let ids = ["a\\".to_string(), "b\\\"".to_string()];
+		let mut output = Vec::new();
+		write(
+			&layout,
+			Some(&ids),
+			&WriteOptions {
+				penwidth: false,
+				..Default::default()
+			},
+			&mut output,
+		)
+		.unwrap();
+		let output = String::from_utf8(output).unwrap();
+		assert!(output.contains(r#"	"a\\" -- "b\\\"" [fa2_weight=2.5];"#));
+		let graph = read::<f64, _>(output.as_bytes()).unwrap();
+		assert_eq!(graph.labels, Some(ids.to_vec()));
+		assert_eq!(graph.weights, Some(vec![2.5]));
+	}
+}
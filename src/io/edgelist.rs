@@ -0,0 +1,208 @@
+//! Edge list (CSV-like) reader
+//!
+//! Each line is an edge, with a source column, a target column and optionally a weight column.
+//! Nodes are identified by arbitrary labels, mapped to indices in order of appearance.
+//!
+//! ```no_run
+//! use forceatlas2::{io::edgelist, Settings};
+//!
+//! let file = std::io::BufReader::new(std::fs::File::open("examples/wot.csv").unwrap());
+//! let mut options = edgelist::Options::default();
+//! options.delimiter = Some(';');
+//! options.header = true;
+//! let settings = Settings::default();
+//! let (layout, labels) = edgelist::read_layout::<f32, _>(file, &options, settings).unwrap();
+//! ```
+
+use super::*;
+
+use std::io::BufRead;
+
+pub struct Options {
+	/// Lines starting with this character (after whitespace) are ignored
+	pub comment: Option<char>,
+	/// Column delimiter
+	///
+	/// If `None`, columns are separated by any sequence of whitespace, `,` or `;`.
+	pub delimiter: Option<char>,
+	/// If `false`, edges `a b` and `b a` are the same, and duplicate edges are merged (summing their weights).
+	/// If `true`, each line is an edge, so reciprocal edges attract twice.
+	///
+	/// Loops are always ignored.
+	pub directed: bool,
+	/// Ignore the first line that is not empty nor a comment
+	pub header: bool,
+	/// Index of the source column
+	pub source_column: usize,
+	/// Index of the target column
+	pub target_column: usize,
+	/// Index of the weight column
+	///
+	/// If `None`, the graph is not weighted.
+	pub weight_column: Option<usize>,
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			comment: Some('#'),
+			delimiter: None,
+			directed: false,
+			header: false,
+			source_column: 0,
+			target_column: 1,
+			weight_column: None,
+		}
+	}
+}
+
+/// Reads an edge list
+pub fn read<T: Coord, R: BufRead>(reader: R, options: &Options) -> Result<Graph<T>, Error> {
+	let mut labels = Labels::default();
+	let mut edges = EdgeCollector::new(options.weight_column.is_some(), options.directed);
+	let mut header = options.header;
+	let nb_columns = options
+		.source_column
+		.max(options.target_column)
+		.max(options.weight_column.unwrap_or(0))
+		+ 1;
+
+	for (i, line) in reader.lines().enumerate() {
+		let line = line?;
+		let trimmed = line.trim();
+		if trimmed.is_empty()
+			|| options
+				.comment
+				.is_some_and(|comment| trimmed.starts_with(comment))
+		{
+			continue;
+		}
+		if header {
+			header = false;
+			continue;
+		}
+
+		let mut columns = Vec::with_capacity(nb_columns);
+		if let Some(delimiter) = options.delimiter {
+			columns.extend(line.split(delimiter).map(str::trim).take(nb_columns));
+		} else {
+			columns.extend(
+				line.split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+					.filter(|column| !column.is_empty())
+					.take(nb_columns),
+			);
+		}
+		if columns.len() < nb_columns {
+			return Err(Error::Parse {
+				line: i + 1,
+				msg: format!("Expected {} columns, found {}", nb_columns, columns.len()),
+			});
+		}
+
+		let weight = if let Some(weight_column) = options.weight_column {
+			let weight = columns[weight_column];
+			Some(
+				weight
+					.parse::<f64>()
+					.ok()
+					.and_then(T::from_f64)
+					.ok_or_else(|| Error::Parse {
+						line: i + 1,
+						msg: format!("Invalid weight `{}`", weight),
+					})?,
+			)
+		} else {
+			None
+		};
+		let n1 = labels.get_or_insert(columns[options.source_column]);
+		let n2 = labels.get_or_insert(columns[options.target_column]);
+		edges.push(n1, n2, weight);
+	}
+
+	Ok(Graph {
+		edges: edges.edges,
+		nb_nodes: labels.len(),
+		labels: Some(labels.labels),
+		weights: edges.weights,
+	})
+}
+
+/// Reads an edge list and instantiates a randomly positioned layout, with `Nodes::Degree` masses
+///
+/// Returns the layout and the labels, by node index.
+#[cfg(feature = "rand")]
+pub fn read_layout<T, R: BufRead>(
+	reader: R,
+	options: &Options,
+	settings: Settings<T>,
+) -> Result<(Layout<T>, Vec<String>), Error>
+where
+	Layout<T>: forces::Repulsion<T> + forces::Attraction<T>,
+	rand::distributions::Standard: rand::distributions::Distribution<T>,
+	T: Coord + std::fmt::Debug + rand::distributions::uniform::SampleUniform,
+{
+	let (layout, labels) = read(reader, options)?.into_layout(settings);
+	Ok((layout, labels.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_read() {
+		let input = "\
+# comment
+source;target;weight
+a;b;1.5
+b ; c ; 2
+
+c;a;0.5
+b;a;1
+a;a;3
+";
+		let graph = read::<f64, _>(
+			input.as_bytes(),
+			&Options {
+				delimiter: Some(';'),
+				header: true,
+				weight_column: Some(2),
+				..Default::default()
+			},
+		)
+		.unwrap();
+		assert_eq!(graph.nb_nodes, 3);
+		assert_eq!(
+			graph.labels,
+			Some(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+		);
+		assert_eq!(graph.edges, vec![(0, 1), (1, 2), (0, 2)]);
+		assert_eq!(graph.weights, Some(vec![2.5, 2.0, 0.5]));
+
+		let graph = read::<f64, _>(
+			"1 2\n2\t1\n3,1".as_bytes(),
+			&Options {
+				directed: true,
+				..Default::default()
+			},
+		)
+		.unwrap();
+		assert_eq!(graph.edges, vec![(0, 1), (0, 1), (0, 2)]);
+		assert_eq!(graph.weights, None);
+
+		match read::<f64, _>("a b\nc\n".as_bytes(), &Options::default()) {
+			Err(Error::Parse { line: 2, .. }) => {}
+			_ => panic!("Expected parse error at line 2"),
+		}
+		match read::<f64, _>(
+			"a b x\n".as_bytes(),
+			&Options {
+				weight_column: Some(2),
+				..Default::default()
+			},
+		) {
+			Err(Error::Parse { line: 1, .. }) => {}
+			_ => panic!("Expected parse error at line 1"),
+		}
+	}
+}
@@ -0,0 +1,487 @@
+//! [GEXF](https://gexf.net/) (Gephi) reader and writer
+//!
+//! Requires the `gexf` feature.
+//!
+//! To save a layout computed from a GEXF file without losing its attributes,
+//! use [`write_positions`] with the original file.
+
+use super::{xml::*, *};
+
+use quick_xml::{
+	events::{BytesEnd, BytesStart, Event},
+	Reader, Writer,
+};
+use std::io::{BufRead, Write};
+
+const GEXF_NS: &str = "http://gexf.net/1.3";
+const VIZ_NS: &str = "http://gexf.net/1.3/viz";
+
+/// Whether `ns` is the viz namespace of a GEXF version (e.g. `http://www.gexf.net/1.2draft/viz`)
+fn is_viz_ns(ns: &[u8]) -> bool {
+	let ns = ns
+		.strip_prefix(b"http://")
+		.or_else(|| ns.strip_prefix(b"https://"))
+		.unwrap_or(ns);
+	let ns = ns.strip_prefix(b"www.").unwrap_or(ns);
+	ns.starts_with(b"gexf.net/") && ns.ends_with(b"/viz")
+}
+
+/// Graph read from a GEXF file
+pub struct Gexf<T> {
+	/// `labels` are the node IDs
+	pub graph: Graph<T>,
+	/// Whether the default edge type is directed
+	pub directed: bool,
+	/// `label` attribute of the nodes
+	pub node_labels: Vec<Option<String>>,
+	/// `viz:position` of the nodes
+	pub positions: Vec<Option<[f64; 3]>>,
+	/// `viz:size` of the nodes
+	pub sizes: Vec<Option<f64>>,
+}
+
+impl<T: Coord + std::fmt::Debug> Gexf<T>
+where
+	Layout<T>: forces::Repulsion<T> + forces::Attraction<T>,
+{
+	/// Instantiates a layout with `Nodes::Degree` masses
+	///
+	/// Nodes having a `viz:position` start at this position (only the first 3 dimensions are used),
+	/// others are randomly positioned.
+	///
+	/// If `settings.prevent_overlapping` is set and the nodes have a `viz:size`,
+	/// the node size is replaced by the largest `viz:size`.
+	///
+	/// Returns the layout and the node IDs.
+	#[cfg(feature = "rand")]
+	pub fn into_layout(self, mut settings: Settings<T>) -> (Layout<T>, Vec<String>)
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		if let Some((node_size, _)) = &mut settings.prevent_overlapping {
+			if let Some(max_size) = self.sizes.iter().flatten().copied().reduce(f64::max) {
+				*node_size = T::from_f64(max_size).unwrap();
+			}
+		}
+		let (mut layout, ids) = self.graph.into_layout(settings);
+		let dimensions = layout.settings.dimensions.min(3);
+		for (node, position) in self.positions.into_iter().enumerate() {
+			if let Some(position) = position {
+				for (coord, value) in layout.points.get_mut(node)[..dimensions]
+					.iter_mut()
+					.zip(position)
+				{
+					*coord = T::from_f64(value).unwrap();
+				}
+			}
+		}
+		(layout, ids.unwrap_or_default())
+	}
+}
+
+/// Reads a GEXF document
+///
+/// Edges referring to undeclared nodes declare them implicitly.
+/// The graph is weighted if at least one edge has a `weight` attribute (missing weights are 1).
+/// Unless the default edge type is directed, duplicate edges are merged.
+pub fn read<T: Coord, R: BufRead>(reader: R) -> Result<Gexf<T>, Error> {
+	let mut reader = Reader::from_reader(reader);
+	reader.trim_text(true);
+	let mut buf = Vec::new();
+
+	let mut ids = Labels::default();
+	let mut directed = false;
+	let mut edges = Vec::<(usize, usize, Option<f64>)>::new();
+	let mut node_labels = Vec::new();
+	let mut positions = Vec::new();
+	let mut sizes = Vec::new();
+	// Stack of the open nodes (GEXF allows nested nodes)
+	let mut open_nodes = Vec::<usize>::new();
+
+	fn declare<U>(list: &mut Vec<Option<U>>, index: usize) {
+		if list.len() <= index {
+			list.resize_with(index + 1, || None);
+		}
+	}
+
+	loop {
+		let event = reader
+			.read_event_into(&mut buf)
+			.map_err(|e| error(&reader, e))?;
+		let (e, empty) = match event {
+			Event::Start(e) => (e, false),
+			Event::Empty(e) => (e, true),
+			Event::End(e) => {
+				if e.local_name().as_ref() == b"node" {
+					open_nodes.pop();
+				}
+				buf.clear();
+				continue;
+			}
+			Event::Eof => break,
+			_ => {
+				buf.clear();
+				continue;
+			}
+		};
+		match e.local_name().as_ref() {
+			b"graph" => {
+				directed =
+					get_attr(&reader, &e, b"defaultedgetype")?.as_deref() == Some("directed");
+			}
+			b"node" => {
+				let index = ids.get_or_insert(&require_attr(&reader, &e, b"id")?);
+				declare(&mut node_labels, index);
+				node_labels[index] = get_attr(&reader, &e, b"label")?;
+				if !empty {
+					open_nodes.push(index);
+				}
+			}
+			b"position" => {
+				if let Some(node) = open_nodes.last() {
+					declare(&mut positions, *node);
+					positions[*node] = Some([
+						get_attr_f64(&reader, &e, b"x")?.unwrap_or(0.0),
+						get_attr_f64(&reader, &e, b"y")?.unwrap_or(0.0),
+						get_attr_f64(&reader, &e, b"z")?.unwrap_or(0.0),
+					]);
+				}
+			}
+			b"size" => {
+				if let Some(node) = open_nodes.last() {
+					declare(&mut sizes, *node);
+					sizes[*node] = get_attr_f64(&reader, &e, b"value")?;
+				}
+			}
+			b"edge" => {
+				let n1 = ids.get_or_insert(&require_attr(&reader, &e, b"source")?);
+				let n2 = ids.get_or_insert(&require_attr(&reader, &e, b"target")?);
+				edges.push((n1, n2, get_attr_f64(&reader, &e, b"weight")?));
+			}
+			_ => {}
+		}
+		buf.clear();
+	}
+
+	let weighted = edges.iter().any(|(_, _, weight)| weight.is_some());
+	let mut collector = EdgeCollector::new(weighted, directed);
+	for (n1, n2, weight) in edges {
+		collector.push(n1, n2, weight.map(|weight| T::from_f64(weight).unwrap()));
+	}
+
+	let nb_nodes = ids.len();
+	node_labels.resize_with(nb_nodes, || None);
+	positions.resize_with(nb_nodes, || None);
+	sizes.resize_with(nb_nodes, || None);
+	Ok(Gexf {
+		graph: Graph {
+			edges: collector.edges,
+			labels: Some(ids.labels),
+			nb_nodes,
+			weights: collector.weights,
+		},
+		directed,
+		node_labels,
+		positions,
+		sizes,
+	})
+}
+
+/// Writes a new GEXF document with the layout's positions and weights
+///
+/// Node IDs are `ids` if provided, or the node indices.
+pub fn write<T: Coord, W: Write>(
+	layout: &Layout<T>,
+	ids: Option<&[String]>,
+	mut writer: W,
+) -> std::io::Result<()> {
+	writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+	writeln!(
+		writer,
+		r#"<gexf xmlns="{}" xmlns:viz="{}" version="1.3">"#,
+		GEXF_NS, VIZ_NS
+	)?;
+	writeln!(
+		writer,
+		r#"	<graph defaultedgetype="undirected" mode="static">"#
+	)?;
+	writeln!(writer, "		<nodes>")?;
+	for (node, pos) in layout.points.iter().enumerate() {
+		let id = ids.map_or_else(|| node.to_string().into(), |ids| escape(&ids[node]));
+		write!(writer, r#"			<node id="{}"><viz:position"#, id)?;
+		for (axis, coord) in ["x", "y", "z"].iter().zip(pos) {
+			write!(writer, r#" {}="{}""#, axis, coord.to_f64().unwrap())?;
+		}
+		writeln!(writer, "/></node>")?;
+	}
+	writeln!(writer, "		</nodes>")?;
+	writeln!(writer, "		<edges>")?;
+	for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
+		let (n1, n2) = if let Some(ids) = ids {
+			(escape(&ids[*n1]), escape(&ids[*n2]))
+		} else {
+			(n1.to_string().into(), n2.to_string().into())
+		};
+		write!(
+			writer,
+			r#"			<edge id="{}" source="{}" target="{}""#,
+			edge, n1, n2
+		)?;
+		if let Some(weights) = &layout.weights {
+			write!(writer, r#" weight="{}""#, weights[edge].to_f64().unwrap())?;
+		}
+		writeln!(writer, "/>")?;
+	}
+	writeln!(writer, "		</edges>")?;
+	writeln!(writer, "	</graph>")?;
+	writeln!(writer, "</gexf>")
+}
+
+/// Copies a GEXF document, replacing the nodes' `viz:position` with the layout's positions
+///
+/// Everything else (IDs, attributes, colors, edges...) is kept.
+/// `ids` are the node IDs by index, as returned by [`read`].
+/// Nodes that are not in `ids` keep their position.
+pub fn write_positions<T: Coord, R: BufRead, W: Write>(
+	reader: R,
+	writer: W,
+	layout: &Layout<T>,
+	ids: &[String],
+) -> Result<(), Error> {
+	let indices: HashMap<&str, usize> = ids
+		.iter()
+		.enumerate()
+		.map(|(index, id)| (id.as_str(), index))
+		.collect();
+
+	let mut reader = Reader::from_reader(reader);
+	let mut writer = Writer::new(writer);
+	let mut buf = Vec::new();
+	let mut viz_prefix = String::from("viz");
+	// Stack of the open nodes, `None` for unknown nodes
+	let mut open_nodes = Vec::<Option<usize>>::new();
+	// Depth in a `viz:position` element being skipped
+	let mut skip = 0usize;
+
+	let write_position = |writer: &mut Writer<W>, viz_prefix: &str, node: usize| {
+		let coords: Vec<String> = layout
+			.points
+			.get(node)
+			.iter()
+			.take(3)
+			.map(|coord| coord.to_f64().unwrap().to_string())
+			.collect();
+		let mut position = BytesStart::new(format!("{}:position", viz_prefix));
+		for (axis, coord) in ["x", "y", "z"].iter().zip(coords.iter()) {
+			position.push_attribute((*axis, coord.as_str()));
+		}
+		writer.write_event(Event::Empty(position))
+	};
+
+	loop {
+		let event = reader
+			.read_event_into(&mut buf)
+			.map_err(|e| error(&reader, e))?;
+		if skip > 0 {
+			match event {
+				Event::Start(_) => skip += 1,
+				Event::End(_) => skip -= 1,
+				Event::Eof => break,
+				_ => {}
+			}
+			buf.clear();
+			continue;
+		}
+		match event {
+			Event::Start(mut e) if e.local_name().as_ref() == b"gexf" => {
+				// Reuse the prefix of any GEXF version's viz namespace, or declare one
+				let mut has_viz = false;
+				let mut viz_taken = false;
+				for attr in e.attributes() {
+					let attr = attr.map_err(|e| error(&reader, e))?;
+					let Some(prefix) = attr.key.as_ref().strip_prefix(b"xmlns:") else {
+						continue;
+					};
+					if !has_viz && is_viz_ns(attr.value.as_ref()) {
+						has_viz = true;
+						viz_prefix = String::from_utf8_lossy(prefix).into_owned();
+					}
+					viz_taken |= prefix == b"viz";
+				}
+				if !has_viz {
+					if viz_taken {
+						viz_prefix = String::from("gexfviz");
+					}
+					e.push_attribute((format!("xmlns:{}", viz_prefix).as_str(), VIZ_NS));
+				}
+				writer.write_event(Event::Start(e))
+			}
+			Event::Start(e) if e.local_name().as_ref() == b"node" => {
+				let id = get_attr(&reader, &e, b"id")?;
+				open_nodes.push(id.and_then(|id| indices.get(id.as_str()).copied()));
+				writer.write_event(Event::Start(e))
+			}
+			Event::Empty(e) if e.local_name().as_ref() == b"node" => {
+				let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+				let node =
+					get_attr(&reader, &e, b"id")?.and_then(|id| indices.get(id.as_str()).copied());
+				if let Some(node) = node {
+					writer
+						.write_event(Event::Start(e))
+						.and_then(|()| write_position(&mut writer, &viz_prefix, node))
+						.and_then(|()| writer.write_event(Event::End(BytesEnd::new(name))))
+				} else {
+					writer.write_event(Event::Empty(e))
+				}
+			}
+			Event::Start(e)
+				if e.local_name().as_ref() == b"position"
+					&& matches!(open_nodes.last(), Some(Some(_))) =>
+			{
+				skip = 1;
+				Ok(())
+			}
+			Event::Empty(e)
+				if e.local_name().as_ref() == b"position"
+					&& matches!(open_nodes.last(), Some(Some(_))) =>
+			{
+				Ok(())
+			}
+			Event::End(e) if e.local_name().as_ref() == b"node" => {
+				if let Some(Some(node)) = open_nodes.pop() {
+					write_position(&mut writer, &viz_prefix, node)
+						.and_then(|()| writer.write_event(Event::End(e)))
+				} else {
+					writer.write_event(Event::End(e))
+				}
+			}
+			Event::Eof => break,
+			event => writer.write_event(event),
+		}
+		.map_err(|e| error(&reader, e))?;
+		buf.clear();
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const GEXF: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gexf xmlns="http://gexf.net/1.3" xmlns:viz="http://gexf.net/1.3/viz" version="1.3">
+	<graph defaultedgetype="undirected">
+		<attributes class="node">
+			<attribute id="0" title="group" type="string"/>
+		</attributes>
+		<nodes>
+			<node id="a" label="Node A">
+				<attvalues><attvalue for="0" value="x"/></attvalues>
+				<viz:size value="2.0"/>
+				<viz:position x="1.0" y="2.0" z="0.0"/>
+			</node>
+			<node id="b" label="Node B"/>
+			<node id="c"><viz:size value="5"/></node>
+		</nodes>
+		<edges>
+			<edge id="0" source="a" target="b" weight="2"/>
+			<edge id="1" source="c" target="b"/>
+		</edges>
+	</graph>
+</gexf>
+"#;
+
+	#[test]
+	fn test_read() {
+		let gexf = read::<f64, _>(GEXF.as_bytes()).unwrap();
+		assert_eq!(gexf.graph.nb_nodes, 3);
+		assert_eq!(
+			gexf.graph.labels,
+			Some(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+		);
+		assert_eq!(gexf.graph.edges, vec![(0, 1), (1, 2)]);
+		assert_eq!(gexf.graph.weights, Some(vec![2.0, 1.0]));
+		assert!(!gexf.directed);
+		assert_eq!(
+			gexf.node_labels,
+			vec![Some("Node A".to_string()), Some("Node B".to_string()), None]
+		);
+		assert_eq!(gexf.positions, vec![Some([1.0, 2.0, 0.0]), None, None]);
+		assert_eq!(gexf.sizes, vec![Some(2.0), None, Some(5.0)]);
+	}
+
+	#[test]
+	fn test_write_positions() {
+		let gexf = read::<f64, _>(GEXF.as_bytes()).unwrap();
+		let ids = gexf.graph.labels.clone().unwrap();
+		let layout = Layout::<f64>::from_position_graph(
+			gexf.graph.edges,
+			Nodes::Degree(3),
+			vec![10.0, 11.0, 20.0, 21.0, 30.0, 31.0],
+			gexf.graph.weights,
+			Settings::default(),
+		);
+
+		let mut output = Vec::new();
+		write_positions(GEXF.as_bytes(), &mut output, &layout, &ids).unwrap();
+		let output = String::from_utf8(output).unwrap();
+		assert!(output.contains(r#"<attvalue for="0" value="x"/>"#));
+		assert!(output.contains(r#"<viz:size value="5"/>"#));
+
+		let gexf = read::<f64, _>(output.as_bytes()).unwrap();
+		assert_eq!(
+			gexf.positions,
+			vec![
+				Some([10.0, 11.0, 0.0]),
+				Some([20.0, 21.0, 0.0]),
+				Some([30.0, 31.0, 0.0])
+			]
+		);
+		assert_eq!(gexf.node_labels[0], Some("Node A".to_string()));
+
+		// The viz namespace of an older version is reused
+		let gexf_12 = GEXF
+			.replace(
+				"http://gexf.net/1.3/viz",
+				"http://www.gexf.net/1.2draft/viz",
+			)
+			.replace("http://gexf.net/1.3", "http://www.gexf.net/1.2draft")
+			.replace(r#"version="1.3""#, r#"version="1.2""#);
+		let mut output = Vec::new();
+		write_positions(gexf_12.as_bytes(), &mut output, &layout, &ids).unwrap();
+		let output = String::from_utf8(output).unwrap();
+		assert_eq!(output.matches("xmlns:viz=").count(), 1);
+		assert!(output.contains(r#"<viz:position x="10" y="11"/>"#));
+	}
+
+	#[test]
+	fn test_write() {
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			Nodes::Degree(2),
+			vec![1.0, 2.0, 3.0, 4.0],
+			Some(vec![0.5]),
+			Settings::default(),
+		);
+		let mut output = Vec::new();
+		write(
+			&layout,
+			Some(&["a&b".to_string(), "c".to_string()]),
+			&mut output,
+		)
+		.unwrap();
+
+		let gexf = read::<f64, _>(output.as_slice()).unwrap();
+		assert_eq!(
+			gexf.graph.labels,
+			Some(vec!["a&b".to_string(), "c".to_string()])
+		);
+		assert_eq!(gexf.graph.edges, vec![(0, 1)]);
+		assert_eq!(gexf.graph.weights, Some(vec![0.5]));
+		assert_eq!(
+			gexf.positions,
+			vec![Some([1.0, 2.0, 0.0]), Some([3.0, 4.0, 0.0])]
+		);
+	}
+}
@@ -0,0 +1,425 @@
+//! [GraphML](http://graphml.graphdrawing.org/) reader and writer
+//!
+//! Requires the `graphml` feature.
+//!
+//! `<data>` keys are matched either by their `attr.name` or by their `id`.
+
+use super::{xml::*, *};
+
+use quick_xml::{events::Event, Reader};
+use std::io::{BufRead, Write};
+
+const GRAPHML_NS: &str = "http://graphml.graphdrawing.org/xmlns";
+
+pub struct Options {
+	/// Key of the edge weights
+	///
+	/// The graph is weighted if this key is declared for edges.
+	pub weight_key: Option<String>,
+	/// Key of the node masses
+	///
+	/// Nodes without this data have mass 1 (or the key's default value).
+	pub mass_key: Option<String>,
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			weight_key: Some("weight".into()),
+			mass_key: None,
+		}
+	}
+}
+
+/// Graph read from a GraphML file
+pub struct GraphMl<T> {
+	/// `labels` are the node IDs
+	pub graph: Graph<T>,
+	/// Whether the default edge type is directed
+	pub directed: bool,
+	/// Node masses, if `mass_key` is declared
+	pub masses: Option<Vec<T>>,
+	/// Positions from the `x`, `y` and `z` keys (missing coordinates are 0)
+	pub positions: Vec<Option<[f64; 3]>>,
+}
+
+impl<T: Coord + std::fmt::Debug> GraphMl<T>
+where
+	Layout<T>: forces::Repulsion<T> + forces::Attraction<T>,
+{
+	/// Instantiates a layout, with `Nodes::Mass` masses if any, `Nodes::Degree` otherwise
+	///
+	/// Nodes having a position start at this position (only the first 3 dimensions are used),
+	/// others are randomly positioned.
+	///
+	/// Returns the layout and the node IDs.
+	#[cfg(feature = "rand")]
+	pub fn into_layout(self, settings: Settings<T>) -> (Layout<T>, Vec<String>)
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		let nodes = match self.masses {
+			Some(masses) => Nodes::Mass(masses),
+			None => Nodes::Degree(self.graph.nb_nodes),
+		};
+		let mut layout = Layout::from_graph(self.graph.edges, nodes, self.graph.weights, settings);
+		let dimensions = layout.settings.dimensions.min(3);
+		for (node, position) in self.positions.into_iter().enumerate() {
+			if let Some(position) = position {
+				for (coord, value) in layout.points.get_mut(node)[..dimensions]
+					.iter_mut()
+					.zip(position)
+				{
+					*coord = T::from_f64(value).unwrap();
+				}
+			}
+		}
+		(layout, self.graph.labels.unwrap_or_default())
+	}
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+	Weight,
+	Mass,
+	Coord(usize),
+}
+
+/// Element whose `<data>` children are being read
+#[derive(Clone, Copy)]
+enum Element {
+	Node(usize),
+	Edge(usize),
+	Other,
+}
+
+/// Reads a GraphML document
+///
+/// Edges referring to undeclared nodes declare them implicitly.
+/// Unless the default edge type is directed, duplicate edges are merged.
+pub fn read<T: Coord, R: BufRead>(reader: R, options: &Options) -> Result<GraphMl<T>, Error> {
+	let mut reader = Reader::from_reader(reader);
+	reader.trim_text(true);
+	let mut buf = Vec::new();
+
+	let mut ids = Labels::default();
+	let mut directed = false;
+	// Key ID => (field, default value)
+	let mut keys = HashMap::<String, (Field, Option<f64>)>::new();
+	let mut edges = Vec::<(usize, usize, Option<f64>)>::new();
+	let mut masses = Vec::<Option<f64>>::new();
+	let mut positions = Vec::<Option<[f64; 3]>>::new();
+	let mut elements = Vec::<Element>::new();
+	// Key being declared, or data being read
+	let mut current_key: Option<String> = None;
+	let mut in_default = false;
+	let mut data: Option<Field> = None;
+
+	loop {
+		let event = reader
+			.read_event_into(&mut buf)
+			.map_err(|e| error(&reader, e))?;
+		let (e, empty) = match event {
+			Event::Start(e) => (e, false),
+			Event::Empty(e) => (e, true),
+			Event::Text(e) => {
+				let text = e.unescape().map_err(|e| error(&reader, e))?;
+				let value = || {
+					text.trim()
+						.parse::<f64>()
+						.map_err(|_| error(&reader, format!("Invalid number `{}` in data", text)))
+				};
+				if in_default {
+					if let Some((_, default)) = current_key.as_ref().and_then(|id| keys.get_mut(id))
+					{
+						*default = Some(value()?);
+					}
+				} else if let Some(field) = data {
+					let value = value()?;
+					match (field, elements.last()) {
+						(Field::Weight, Some(Element::Edge(edge))) => edges[*edge].2 = Some(value),
+						(Field::Mass, Some(Element::Node(node))) => masses[*node] = Some(value),
+						(Field::Coord(axis), Some(Element::Node(node))) => {
+							positions[*node].get_or_insert([0.0; 3])[axis] = value;
+						}
+						_ => {}
+					}
+				}
+				buf.clear();
+				continue;
+			}
+			Event::End(e) => {
+				match e.local_name().as_ref() {
+					b"node" | b"edge" | b"graph" | b"hyperedge" | b"port" => {
+						elements.pop();
+					}
+					b"key" => current_key = None,
+					b"default" => in_default = false,
+					b"data" => data = None,
+					_ => {}
+				}
+				buf.clear();
+				continue;
+			}
+			Event::Eof => break,
+			_ => {
+				buf.clear();
+				continue;
+			}
+		};
+		match e.local_name().as_ref() {
+			b"key" => {
+				let id = require_attr(&reader, &e, b"id")?;
+				let name = get_attr(&reader, &e, b"attr.name")?.unwrap_or_else(|| id.clone());
+				let domain = get_attr(&reader, &e, b"for")?;
+				let for_nodes = matches!(domain.as_deref(), None | Some("node") | Some("all"));
+				let for_edges = matches!(domain.as_deref(), None | Some("edge") | Some("all"));
+				let matches = |key: &Option<String>| {
+					key.as_ref().is_some_and(|key| *key == name || *key == id)
+				};
+				let field = if for_edges && matches(&options.weight_key) {
+					Some(Field::Weight)
+				} else if for_nodes && matches(&options.mass_key) {
+					Some(Field::Mass)
+				} else if for_nodes {
+					["x", "y", "z"]
+						.iter()
+						.position(|axis| name == *axis)
+						.map(Field::Coord)
+				} else {
+					None
+				};
+				if let Some(field) = field {
+					keys.insert(id.clone(), (field, None));
+					if !empty {
+						current_key = Some(id);
+					}
+				}
+			}
+			b"default" => in_default = !empty,
+			b"graph" => {
+				directed = get_attr(&reader, &e, b"edgedefault")?.as_deref() == Some("directed");
+				if !empty {
+					elements.push(Element::Other);
+				}
+			}
+			b"node" => {
+				let index = ids.get_or_insert(&require_attr(&reader, &e, b"id")?);
+				if masses.len() <= index {
+					masses.resize(index + 1, None);
+					positions.resize(index + 1, None);
+				}
+				if !empty {
+					elements.push(Element::Node(index));
+				}
+			}
+			b"edge" => {
+				let n1 = ids.get_or_insert(&require_attr(&reader, &e, b"source")?);
+				let n2 = ids.get_or_insert(&require_attr(&reader, &e, b"target")?);
+				edges.push((n1, n2, None));
+				if !empty {
+					elements.push(Element::Edge(edges.len() - 1));
+				}
+			}
+			b"data" if !empty => {
+				let key = require_attr(&reader, &e, b"key")?;
+				data = keys.get(&key).map(|(field, _)| *field);
+			}
+			b"hyperedge" | b"port" if !empty => elements.push(Element::Other),
+			_ => {}
+		}
+		buf.clear();
+	}
+
+	let default = |field: Field| {
+		keys.values()
+			.find(|(f, _)| *f == field)
+			.map(|(_, default)| default.unwrap_or(1.0))
+	};
+	let nb_nodes = ids.len();
+
+	let default_weight = default(Field::Weight);
+	let mut collector = EdgeCollector::new(default_weight.is_some(), directed);
+	for (n1, n2, weight) in edges {
+		collector.push(
+			n1,
+			n2,
+			weight
+				.or(default_weight)
+				.map(|weight| T::from_f64(weight).unwrap()),
+		);
+	}
+
+	masses.resize(nb_nodes, None);
+	positions.resize(nb_nodes, None);
+	Ok(GraphMl {
+		graph: Graph {
+			edges: collector.edges,
+			labels: Some(ids.labels),
+			nb_nodes,
+			weights: collector.weights,
+		},
+		directed,
+		masses: default(Field::Mass).map(|default_mass| {
+			masses
+				.into_iter()
+				.map(|mass| T::from_f64(mass.unwrap_or(default_mass)).unwrap())
+				.collect()
+		}),
+		positions,
+	})
+}
+
+/// Writes a GraphML document with the layout's positions (keys `x`, `y`, `z`), masses (key `mass`) and weights (key `weight`)
+///
+/// Node IDs are `ids` if provided, or the node indices.
+pub fn write<T: Coord, W: Write>(
+	layout: &Layout<T>,
+	ids: Option<&[String]>,
+	mut writer: W,
+) -> std::io::Result<()> {
+	let axes = &["x", "y", "z"][..layout.settings.dimensions.min(3)];
+	writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+	writeln!(writer, r#"<graphml xmlns="{}">"#, GRAPHML_NS)?;
+	for axis in axes {
+		writeln!(
+			writer,
+			r#"	<key id="{0}" for="node" attr.name="{0}" attr.type="double"/>"#,
+			axis
+		)?;
+	}
+	writeln!(
+		writer,
+		r#"	<key id="mass" for="node" attr.name="mass" attr.type="double"/>"#
+	)?;
+	if layout.weights.is_some() {
+		writeln!(
+			writer,
+			r#"	<key id="weight" for="edge" attr.name="weight" attr.type="double"/>"#
+		)?;
+	}
+	writeln!(writer, r#"	<graph id="G" edgedefault="undirected">"#)?;
+	for (node, pos) in layout.points.iter().enumerate() {
+		let id = ids.map_or_else(|| node.to_string().into(), |ids| escape(&ids[node]));
+		write!(writer, r#"		<node id="{}">"#, id)?;
+		for (axis, coord) in axes.iter().zip(pos) {
+			write!(
+				writer,
+				r#"<data key="{}">{}</data>"#,
+				axis,
+				coord.to_f64().unwrap()
+			)?;
+		}
+		writeln!(
+			writer,
+			r#"<data key="mass">{}</data></node>"#,
+			layout.masses[node].to_f64().unwrap()
+		)?;
+	}
+	for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
+		let (n1, n2) = if let Some(ids) = ids {
+			(escape(&ids[*n1]), escape(&ids[*n2]))
+		} else {
+			(n1.to_string().into(), n2.to_string().into())
+		};
+		write!(writer, r#"		<edge source="{}" target="{}""#, n1, n2)?;
+		if let Some(weights) = &layout.weights {
+			writeln!(
+				writer,
+				r#"><data key="weight">{}</data></edge>"#,
+				weights[edge].to_f64().unwrap()
+			)?;
+		} else {
+			writeln!(writer, "/>")?;
+		}
+	}
+	writeln!(writer, "	</graph>")?;
+	writeln!(writer, "</graphml>")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const GRAPHML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+	<key id="d0" for="edge" attr.name="strength" attr.type="double"/>
+	<key id="d1" for="node" attr.name="size" attr.type="double">
+		<default>2.0</default>
+	</key>
+	<key id="d2" for="node" attr.name="color" attr.type="string"/>
+	<graph id="G" edgedefault="undirected">
+		<node id="n0"><data key="d1">5</data><data key="d2">red</data></node>
+		<node id="n1"><data key="d1"/><desc>9</desc></node>
+		<node id="n2"><data key="d1">3.5</data></node>
+		<edge source="n0" target="n1"><data key="d0">0.5</data></edge>
+		<edge source="n2" target="n1"/>
+		<edge source="n1" target="n0"><data key="d0">1.5</data></edge>
+	</graph>
+</graphml>
+"#;
+
+	#[test]
+	fn test_read() {
+		let graphml = read::<f64, _>(
+			GRAPHML.as_bytes(),
+			&Options {
+				weight_key: Some("strength".into()),
+				mass_key: Some("d1".into()),
+			},
+		)
+		.unwrap();
+		assert_eq!(graphml.graph.nb_nodes, 3);
+		assert_eq!(
+			graphml.graph.labels,
+			Some(vec!["n0".to_string(), "n1".to_string(), "n2".to_string()])
+		);
+		assert_eq!(graphml.graph.edges, vec![(0, 1), (1, 2)]);
+		assert_eq!(graphml.graph.weights, Some(vec![2.0, 1.0]));
+		assert_eq!(graphml.masses, Some(vec![5.0, 2.0, 3.5]));
+		assert!(!graphml.directed);
+
+		let graphml = read::<f64, _>(GRAPHML.as_bytes(), &Options::default()).unwrap();
+		assert_eq!(graphml.graph.weights, None);
+		assert_eq!(graphml.masses, None);
+	}
+
+	#[test]
+	fn test_write() {
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			Nodes::Mass(vec![1.0, 3.0]),
+			vec![1.0, 2.0, 3.0, 4.0],
+			Some(vec![0.5]),
+			Settings::default(),
+		);
+		let mut output = Vec::new();
+		write(
+			&layout,
+			Some(&["a<b".to_string(), "c".to_string()]),
+			&mut output,
+		)
+		.unwrap();
+
+		let graphml = read::<f64, _>(
+			output.as_slice(),
+			&Options {
+				mass_key: Some("mass".into()),
+				..Default::default()
+			},
+		)
+		.unwrap();
+		assert_eq!(
+			graphml.graph.labels,
+			Some(vec!["a<b".to_string(), "c".to_string()])
+		);
+		assert_eq!(graphml.graph.edges, vec![(0, 1)]);
+		assert_eq!(graphml.graph.weights, Some(vec![0.5]));
+		assert_eq!(graphml.masses, Some(vec![1.0, 3.0]));
+		assert_eq!(
+			graphml.positions,
+			vec![Some([1.0, 2.0, 0.0]), Some([3.0, 4.0, 0.0])]
+		);
+	}
+}
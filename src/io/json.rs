@@ -0,0 +1,238 @@
+//! JSON export for web libraries
+//!
+//! Nodes carry their position (`x`, `y` and `z` for the first 3 dimensions) and a `size` proportional to the square root of their mass.
+//! Edges carry their `weight`, if the layout is weighted.
+
+use super::*;
+
+use std::{collections::HashSet, io::Write};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+	/// [Graphology](https://graphology.github.io/serialization.html) serialized graph, also used by sigma.js
+	///
+	/// Load with `Graph.from(json)` or `graph.import(json)`.
+	Graphology,
+	/// [Cytoscape.js](https://js.cytoscape.org/#notation/elements-json) `elements` JSON
+	///
+	/// Positions use the `preset` layout. `z` is stored in the node data.
+	Cytoscape,
+	/// [NetworkX](https://networkx.org/documentation/stable/reference/readwrite/json_graph.html) node-link format
+	///
+	/// Load with `networkx.node_link_graph(json)`.
+	NodeLink,
+}
+
+/// Writes the layout as JSON
+///
+/// Node IDs are `ids` if provided, or the node indices.
+/// The graph is declared as a multigraph if it has duplicate edges.
+pub fn write<T: Coord, W: Write>(
+	layout: &Layout<T>,
+	ids: Option<&[String]>,
+	format: Format,
+	mut writer: W,
+) -> std::io::Result<()> {
+	let id = |writer: &mut W, node: usize| -> std::io::Result<()> {
+		match ids {
+			Some(ids) => write_str(writer, &ids[node]),
+			// Graphology keys and Cytoscape IDs must be strings
+			None if format != Format::NodeLink => write!(writer, "\"{}\"", node),
+			None => write!(writer, "{}", node),
+		}
+	};
+	let axes = ["x", "y", "z"];
+	// Importers reject duplicate edges unless they are declared
+	let multi = {
+		let mut edges = HashSet::with_capacity(layout.edges.len());
+		!layout.edges.iter().all(|edge| edges.insert(*edge))
+	};
+
+	match format {
+		Format::Graphology => write!(
+			writer,
+			r#"{{"attributes":{{}},"options":{{"type":"undirected","multi":{},"allowSelfLoops":false}},"nodes":["#,
+			multi
+		)?,
+		Format::Cytoscape => writer.write_all(br#"{"elements":{"nodes":["#)?,
+		Format::NodeLink => write!(
+			writer,
+			r#"{{"directed":false,"multigraph":{},"graph":{{}},"nodes":["#,
+			multi
+		)?,
+	}
+	for (node, pos) in layout.points.iter().enumerate() {
+		if node != 0 {
+			writer.write_all(b",")?;
+		}
+		match format {
+			Format::Graphology => {
+				writer.write_all(br#"{"key":"#)?;
+				id(&mut writer, node)?;
+				writer.write_all(br#","attributes":{"#)?;
+				for (axis, coord) in axes.iter().zip(pos) {
+					write!(writer, r#""{}":"#, axis)?;
+					write_number(&mut writer, coord.to_f64().unwrap())?;
+					writer.write_all(b",")?;
+				}
+			}
+			Format::Cytoscape => {
+				writer.write_all(br#"{"position":{"#)?;
+				for (i, (axis, coord)) in axes.iter().zip(pos).take(2).enumerate() {
+					if i != 0 {
+						writer.write_all(b",")?;
+					}
+					write!(writer, r#""{}":"#, axis)?;
+					write_number(&mut writer, coord.to_f64().unwrap())?;
+				}
+				writer.write_all(br#"},"data":{"id":"#)?;
+				id(&mut writer, node)?;
+				writer.write_all(b",")?;
+				if let Some(z) = pos.get(2) {
+					writer.write_all(br#""z":"#)?;
+					write_number(&mut writer, z.to_f64().unwrap())?;
+					writer.write_all(b",")?;
+				}
+			}
+			Format::NodeLink => {
+				writer.write_all(br#"{"id":"#)?;
+				id(&mut writer, node)?;
+				for (axis, coord) in axes.iter().zip(pos) {
+					write!(writer, r#","{}":"#, axis)?;
+					write_number(&mut writer, coord.to_f64().unwrap())?;
+				}
+				writer.write_all(b",")?;
+			}
+		}
+		writer.write_all(br#""size":"#)?;
+		write_number(&mut writer, node_size(&layout.masses[node]))?;
+		match format {
+			Format::Graphology | Format::Cytoscape => writer.write_all(b"}}")?,
+			Format::NodeLink => writer.write_all(b"}")?,
+		}
+	}
+
+	match format {
+		Format::Graphology | Format::Cytoscape => writer.write_all(br#"],"edges":["#)?,
+		Format::NodeLink => writer.write_all(br#"],"links":["#)?,
+	}
+	for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
+		if edge != 0 {
+			writer.write_all(b",")?;
+		}
+		match format {
+			Format::Graphology => write!(writer, r#"{{"key":"{}","source":"#, edge)?,
+			Format::Cytoscape => write!(writer, r#"{{"data":{{"id":"e{}","source":"#, edge)?,
+			Format::NodeLink => writer.write_all(br#"{"source":"#)?,
+		}
+		id(&mut writer, *n1)?;
+		writer.write_all(br#","target":"#)?;
+		id(&mut writer, *n2)?;
+		let weight = layout
+			.weights
+			.as_ref()
+			.map(|weights| weights[edge].to_f64().unwrap());
+		match format {
+			Format::Graphology => {
+				writer.write_all(br#","attributes":{"#)?;
+				if let Some(weight) = weight {
+					writer.write_all(br#""weight":"#)?;
+					write_number(&mut writer, weight)?;
+				}
+				writer.write_all(b"}}")?;
+			}
+			Format::Cytoscape | Format::NodeLink => {
+				if let Some(weight) = weight {
+					writer.write_all(br#","weight":"#)?;
+					write_number(&mut writer, weight)?;
+				}
+				writer.write_all(b"}")?;
+				if format == Format::Cytoscape {
+					writer.write_all(b"}")?;
+				}
+			}
+		}
+	}
+
+	match format {
+		Format::Graphology | Format::NodeLink => writer.write_all(b"]}"),
+		Format::Cytoscape => writer.write_all(b"]}}"),
+	}
+}
+
+pub(crate) fn write_str<W: Write>(writer: &mut W, s: &str) -> std::io::Result<()> {
+	writer.write_all(b"\"")?;
+	for c in s.chars() {
+		match c {
+			'"' => writer.write_all(b"\\\"")?,
+			'\\' => writer.write_all(b"\\\\")?,
+			'\n' => writer.write_all(b"\\n")?,
+			'\r' => writer.write_all(b"\\r")?,
+			'\t' => writer.write_all(b"\\t")?,
+			c if c.is_control() => write!(writer, "\\u{:04x}", c as u32)?,
+			c => write!(writer, "{}", c)?,
+		}
+	}
+	writer.write_all(b"\"")
+}
+
+/// Writes a number, or `null` if it is not finite (JSON has no NaN nor infinity)
+pub(crate) fn write_number<W: Write>(writer: &mut W, x: f64) -> std::io::Result<()> {
+	if x.is_finite() {
+		write!(writer, "{}", x)
+	} else {
+		writer.write_all(b"null")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_write() {
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			Nodes::Mass(vec![1.0, 4.0]),
+			vec![1.0, 2.0, 3.0, -4.5],
+			Some(vec![0.5]),
+			Settings::default(),
+		);
+		let ids = ["a".to_string(), "\"b\"\n".to_string()];
+		let output = |ids: Option<&[String]>, format| {
+			let mut output = Vec::new();
+			write(&layout, ids, format, &mut output).unwrap();
+			String::from_utf8(output).unwrap()
+		};
+
+		assert_eq!(
+			output(Some(&ids), Format::Graphology),
+			r#"{"attributes":{},"options":{"type":"undirected","multi":false,"allowSelfLoops":false},"nodes":[{"key":"a","attributes":{"x":1,"y":2,"size":1}},{"key":"\"b\"\n","attributes":{"x":3,"y":-4.5,"size":2}}],"edges":[{"key":"0","source":"a","target":"\"b\"\n","attributes":{"weight":0.5}}]}"#
+		);
+		assert_eq!(
+			output(None, Format::Cytoscape),
+			r#"{"elements":{"nodes":[{"position":{"x":1,"y":2},"data":{"id":"0","size":1}},{"position":{"x":3,"y":-4.5},"data":{"id":"1","size":2}}],"edges":[{"data":{"id":"e0","source":"0","target":"1","weight":0.5}}]}}"#
+		);
+		assert_eq!(
+			output(None, Format::NodeLink),
+			r#"{"directed":false,"multigraph":false,"graph":{},"nodes":[{"id":0,"x":1,"y":2,"size":1},{"id":1,"x":3,"y":-4.5,"size":2}],"links":[{"source":0,"target":1,"weight":0.5}]}"#
+		);
+
+		// Duplicate edges
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (0, 1)],
+			Nodes::Degree(2),
+			vec![0.0, 0.0, 1.0, 1.0],
+			None,
+			Settings::default(),
+		);
+		let output = |format| {
+			let mut output = Vec::new();
+			write(&layout, None, format, &mut output).unwrap();
+			String::from_utf8(output).unwrap()
+		};
+		assert!(output(Format::Graphology)
+			.starts_with(r#"{"attributes":{},"options":{"type":"undirected","multi":true,"#));
+		assert!(output(Format::NodeLink).starts_with(r#"{"directed":false,"multigraph":true,"#));
+	}
+}
@@ -0,0 +1,165 @@
+//! Graph file formats
+//!
+//! Readers return a [`Graph`], which can be turned into a [`Layout`].
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod dot;
+pub mod edgelist;
+#[cfg(feature = "gexf")]
+pub mod gexf;
+#[cfg(feature = "graphml")]
+pub mod graphml;
+pub mod json;
+pub mod mtx;
+#[cfg(any(feature = "gexf", feature = "graphml"))]
+mod xml;
+
+use crate::{forces, layout::*, util::*};
+
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub enum Error {
+	Io(std::io::Error),
+	/// Invalid content at `line` (starting at 1)
+	Parse {
+		line: usize,
+		msg: String,
+	},
+	/// Invalid XML document, at `position` (in bytes)
+	Xml {
+		position: usize,
+		msg: String,
+	},
+}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Error::Io(e) => write!(f, "I/O error: {}", e),
+			Error::Parse { line, msg } => write!(f, "Line {}: {}", line, msg),
+			Error::Xml { position, msg } => write!(f, "Byte {}: {}", position, msg),
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Io(e) => Some(e),
+			_ => None,
+		}
+	}
+}
+
+impl From<std::io::Error> for Error {
+	fn from(e: std::io::Error) -> Self {
+		Error::Io(e)
+	}
+}
+
+/// Graph read from a file
+pub struct Graph<T> {
+	/// Edges `(n1, n2)` respect `n1 < n2`
+	pub edges: Vec<Edge>,
+	/// Node labels (or IDs) from the file, by node index
+	pub labels: Option<Vec<String>>,
+	pub nb_nodes: usize,
+	pub weights: Option<Vec<T>>,
+}
+
+impl<T: Coord + std::fmt::Debug> Graph<T>
+where
+	Layout<T>: forces::Repulsion<T> + forces::Attraction<T>,
+{
+	/// Instantiates a randomly positioned layout, with `Nodes::Degree` masses
+	///
+	/// Returns the layout and the labels.
+	#[cfg(feature = "rand")]
+	pub fn into_layout(self, settings: Settings<T>) -> (Layout<T>, Option<Vec<String>>)
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		(
+			Layout::from_graph(
+				self.edges,
+				Nodes::Degree(self.nb_nodes),
+				self.weights,
+				settings,
+			),
+			self.labels,
+		)
+	}
+}
+
+/// Node diameter for a given mass, proportional to its square root so that the area is proportional to the mass
+pub(crate) fn node_size<T: Coord>(mass: &T) -> f64 {
+	mass.to_f64().unwrap().max(0.0).sqrt()
+}
+
+/// Maps node labels to indices, in order of appearance
+#[derive(Default)]
+pub(crate) struct Labels {
+	pub indices: HashMap<String, usize>,
+	pub labels: Vec<String>,
+}
+
+impl Labels {
+	pub fn get_or_insert(&mut self, label: &str) -> usize {
+		if let Some(index) = self.indices.get(label) {
+			return *index;
+		}
+		let index = self.labels.len();
+		self.indices.insert(label.to_string(), index);
+		self.labels.push(label.to_string());
+		index
+	}
+
+	pub fn len(&self) -> usize {
+		self.labels.len()
+	}
+}
+
+/// Collects edges, normalizing them as `(n1, n2)` with `n1 < n2`
+///
+/// Loops are ignored. Unless `directed`, duplicate edges are merged and their weights are summed.
+pub(crate) struct EdgeCollector<T> {
+	directed: bool,
+	pub edges: Vec<Edge>,
+	indices: HashMap<Edge, usize>,
+	pub weights: Option<Vec<T>>,
+}
+
+impl<T: Coord> EdgeCollector<T> {
+	pub fn new(weighted: bool, directed: bool) -> Self {
+		Self {
+			directed,
+			edges: Vec::new(),
+			indices: HashMap::new(),
+			weights: if weighted { Some(Vec::new()) } else { None },
+		}
+	}
+
+	pub fn push(&mut self, n1: usize, n2: usize, weight: Option<T>) {
+		let edge = match n1.cmp(&n2) {
+			std::cmp::Ordering::Less => (n1, n2),
+			std::cmp::Ordering::Greater => (n2, n1),
+			std::cmp::Ordering::Equal => return,
+		};
+		if !self.directed {
+			if let Some(index) = self.indices.get(&edge) {
+				if let Some(weights) = &mut self.weights {
+					weights[*index] += weight.unwrap_or_else(T::one);
+				}
+				return;
+			}
+			self.indices.insert(edge, self.edges.len());
+		}
+		self.edges.push(edge);
+		if let Some(weights) = &mut self.weights {
+			weights.push(weight.unwrap_or_else(T::one));
+		}
+	}
+}
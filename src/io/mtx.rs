@@ -0,0 +1,184 @@
+//! [Matrix Market](https://math.nist.gov/MatrixMarket/formats.html) reader
+//!
+//! Reads the coordinate format (sparse matrices, e.g. from the SuiteSparse collection) as an adjacency matrix.
+//! Node `i` is row and column `i + 1`.
+
+use super::*;
+
+use std::io::BufRead;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+	Real,
+	Integer,
+	Complex,
+	Pattern,
+}
+
+/// Reads a square sparse matrix in Matrix Market coordinate format
+///
+/// Diagonal entries are ignored.
+/// - `general` matrices: only the upper triangle (row < column) is read.
+/// - `symmetric`, `skew-symmetric` and `hermitian` matrices: every entry is read, whatever the stored triangle.
+///
+/// Weights are the absolute values of the entries (modulus for complex entries), except for `pattern` matrices which are not weighted.
+/// Duplicate entries are summed.
+pub fn read<T: Coord, R: BufRead>(reader: R) -> Result<Graph<T>, Error> {
+	let mut lines = reader.lines().enumerate();
+
+	let (line, header) = lines.next().ok_or(Error::Parse {
+		line: 1,
+		msg: "Empty file".into(),
+	})?;
+	let header = header?.to_lowercase();
+	let header: Vec<&str> = header.split_whitespace().collect();
+	let parse_error = |line: usize, msg: &str| Error::Parse {
+		line: line + 1,
+		msg: msg.to_string(),
+	};
+	if header.len() != 5 || header[0] != "%%matrixmarket" || header[1] != "matrix" {
+		return Err(parse_error(line, "Expected `%%MatrixMarket matrix` header"));
+	}
+	if header[2] != "coordinate" {
+		return Err(parse_error(line, "Only the coordinate format is supported"));
+	}
+	let field = match header[3] {
+		"real" | "double" => Field::Real,
+		"integer" => Field::Integer,
+		"complex" => Field::Complex,
+		"pattern" => Field::Pattern,
+		_ => return Err(parse_error(line, "Unknown field")),
+	};
+	let symmetric = match header[4] {
+		"general" => false,
+		"symmetric" | "skew-symmetric" | "hermitian" => true,
+		_ => return Err(parse_error(line, "Unknown symmetry")),
+	};
+	let nb_values = match field {
+		Field::Pattern => 0,
+		Field::Real | Field::Integer => 1,
+		Field::Complex => 2,
+	};
+
+	let mut size = None;
+	let mut nb_entries = 0;
+	let mut edges = EdgeCollector::new(field != Field::Pattern, false);
+	for (line, content) in lines {
+		let content = content?;
+		let content = content.trim();
+		if content.is_empty() || content.starts_with('%') {
+			continue;
+		}
+		let columns: Vec<&str> = content.split_whitespace().collect();
+
+		let Some((nb_nodes, nnz)) = size else {
+			let dims: Vec<usize> = columns
+				.iter()
+				.map(|column| column.parse())
+				.collect::<Result<_, _>>()
+				.map_err(|_| parse_error(line, "Invalid size line"))?;
+			if dims.len() != 3 {
+				return Err(parse_error(line, "Expected `rows columns entries`"));
+			}
+			if dims[0] != dims[1] {
+				return Err(parse_error(line, "Matrix must be square"));
+			}
+			size = Some((dims[0], dims[2]));
+			continue;
+		};
+
+		if nb_entries == nnz {
+			return Err(parse_error(line, "Too many entries"));
+		}
+		nb_entries += 1;
+		if columns.len() < 2 + nb_values {
+			return Err(parse_error(
+				line,
+				&format!("Expected {} columns", 2 + nb_values),
+			));
+		}
+		let index = |column: &str| -> Result<usize, Error> {
+			match column.parse::<usize>() {
+				Ok(index) if (1..=nb_nodes).contains(&index) => Ok(index - 1),
+				_ => Err(parse_error(line, &format!("Invalid index `{}`", column))),
+			}
+		};
+		let row = index(columns[0])?;
+		let col = index(columns[1])?;
+		if row == col || (!symmetric && row > col) {
+			continue;
+		}
+		let values: Vec<f64> = columns[2..2 + nb_values]
+			.iter()
+			.map(|value| value.parse())
+			.collect::<Result<_, _>>()
+			.map_err(|_| parse_error(line, "Invalid value"))?;
+		let weight = match field {
+			Field::Pattern => None,
+			Field::Real | Field::Integer => Some(values[0].abs()),
+			Field::Complex => Some(values[0].hypot(values[1])),
+		};
+		edges.push(row, col, weight.map(|weight| T::from_f64(weight).unwrap()));
+	}
+
+	let Some((nb_nodes, nnz)) = size else {
+		return Err(Error::Parse {
+			line: 1,
+			msg: "Missing size line".into(),
+		});
+	};
+	if nb_entries < nnz {
+		return Err(Error::Parse {
+			line: 1,
+			msg: format!("Expected {} entries, found {}", nnz, nb_entries),
+		});
+	}
+	Ok(Graph {
+		edges: edges.edges,
+		labels: None,
+		nb_nodes,
+		weights: edges.weights,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_read() {
+		let input = "\
+%%MatrixMarket matrix coordinate real symmetric
+% comment
+4 4 5
+1 1 10.0
+2 1 -1.5
+3 2 2
+4 1 1e0
+4 1 0.5
+";
+		let graph = read::<f64, _>(input.as_bytes()).unwrap();
+		assert_eq!(graph.nb_nodes, 4);
+		assert_eq!(graph.edges, vec![(0, 1), (1, 2), (0, 3)]);
+		assert_eq!(graph.weights, Some(vec![1.5, 2.0, 1.5]));
+
+		let input = "\
+%%MatrixMarket matrix coordinate pattern general
+3 3 4
+1 2
+2 1
+3 1
+2 3
+";
+		let graph = read::<f64, _>(input.as_bytes()).unwrap();
+		assert_eq!(graph.edges, vec![(0, 1), (1, 2)]);
+		assert_eq!(graph.weights, None);
+
+		match read::<f64, _>(
+			"%%MatrixMarket matrix coordinate real general\n2 2 1\n1 3 1\n".as_bytes(),
+		) {
+			Err(Error::Parse { line: 3, .. }) => {}
+			_ => panic!("Expected parse error at line 3"),
+		}
+	}
+}
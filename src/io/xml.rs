@@ -0,0 +1,73 @@
+//! Helpers for XML formats
+
+use super::Error;
+
+use quick_xml::{events::BytesStart, Reader};
+
+pub(crate) fn escape(s: &str) -> std::borrow::Cow<'_, str> {
+	quick_xml::escape::escape(s)
+}
+
+pub(crate) fn error<R>(reader: &Reader<R>, e: impl std::fmt::Display) -> Error {
+	Error::Xml {
+		position: reader.buffer_position(),
+		msg: e.to_string(),
+	}
+}
+
+/// Returns the unescaped value of an attribute, ignoring its prefix
+pub(crate) fn get_attr<R>(
+	reader: &Reader<R>,
+	e: &BytesStart,
+	name: &[u8],
+) -> Result<Option<String>, Error> {
+	for attr in e.attributes() {
+		let attr = attr.map_err(|e| error(reader, e))?;
+		if attr.key.local_name().as_ref() == name {
+			return Ok(Some(
+				attr.unescape_value()
+					.map_err(|e| error(reader, e))?
+					.into_owned(),
+			));
+		}
+	}
+	Ok(None)
+}
+
+pub(crate) fn get_attr_f64<R>(
+	reader: &Reader<R>,
+	e: &BytesStart,
+	name: &[u8],
+) -> Result<Option<f64>, Error> {
+	get_attr(reader, e, name)?
+		.map(|value| {
+			value.trim().parse().map_err(|_| {
+				error(
+					reader,
+					format!(
+						"Invalid number `{}` in attribute `{}`",
+						value,
+						String::from_utf8_lossy(name)
+					),
+				)
+			})
+		})
+		.transpose()
+}
+
+pub(crate) fn require_attr<R>(
+	reader: &Reader<R>,
+	e: &BytesStart,
+	name: &[u8],
+) -> Result<String, Error> {
+	get_attr(reader, e, name)?.ok_or_else(|| {
+		error(
+			reader,
+			format!(
+				"Missing attribute `{}` in `{}`",
+				String::from_utf8_lossy(name),
+				String::from_utf8_lossy(e.name().as_ref())
+			),
+		)
+	})
+}
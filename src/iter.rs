@@ -8,7 +8,6 @@ pub use parallel::*;
 pub use parallel_simd::*;
 
 pub struct Node<'a, T: Coord> {
-	#[cfg(test)]
 	pub ind: usize,
 	pub mass: &'a T,
 	pub n2_iter: NodeIter2<'a, T>,
@@ -24,7 +23,6 @@ pub struct NodeIter<'a, T: Coord> {
 }
 
 pub struct Node2<'a, T: Coord> {
-	#[cfg(test)]
 	pub ind: usize,
 	pub mass: &'a T,
 	pub pos: &'a [T],
@@ -48,7 +46,6 @@ impl<'a, T: Coord> Iterator for NodeIter<'a, T> {
 				let next_offset = self.offset + layout.settings.dimensions;
 				let next_ind = self.ind + 1;
 				let ret = Node {
-					#[cfg(test)]
 					ind: self.ind,
 					mass,
 					n2_iter: NodeIter2 {
@@ -86,8 +83,7 @@ impl<'a, T: Coord> Iterator for NodeIter2<'a, T> {
 			Some({
 				let next_offset = self.offset + layout.settings.dimensions;
 				let ret = Node2 {
-					#[cfg(test)]
-					ind: self.offset / layout.settings.dimensions,
+					ind: self.ind,
 					mass,
 					pos: unsafe { layout.points.points.get_unchecked(self.offset..next_offset) },
 					speed: unsafe {
@@ -114,7 +110,6 @@ mod parallel {
 	use super::*;
 
 	pub struct NodePar<'a, T: Coord> {
-		#[cfg(test)]
 		pub ind: usize,
 		pub mass: &'a T,
 		pub n2_iter: NodeParIter2<'a, T>,
@@ -134,7 +129,6 @@ mod parallel {
 	}
 
 	pub struct NodePar2<'a, T: Coord> {
-		#[cfg(test)]
 		pub ind: usize,
 		pub mass: &'a T,
 		pub pos: &'a [T],
@@ -159,7 +153,6 @@ mod parallel {
 					let next_offset = self.offset + layout.settings.dimensions;
 					let next_ind = self.ind + 1;
 					let ret = NodePar {
-						#[cfg(test)]
 						ind: self.ind,
 						mass: unsafe { layout.masses.get_unchecked(self.ind) },
 						n2_iter: NodeParIter2 {
@@ -200,7 +193,6 @@ mod parallel {
 					let layout = unsafe { self.layout.0.as_mut() };
 					let next_offset = self.offset + layout.settings.dimensions;
 					let ret = NodePar2 {
-						#[cfg(test)]
 						ind: self.ind,
 						mass: unsafe { layout.masses.get_unchecked(self.ind) },
 						pos: unsafe {
@@ -231,6 +223,7 @@ mod parallel_simd {
 	use super::*;
 
 	pub struct NodeParSimd<'a, T: Coord, const N: usize> {
+		#[cfg(test)]
 		pub ind: usize,
 		pub mass: &'a T,
 		pub n2_iter: NodeParSimdIter2<'a, T, N>,
@@ -251,7 +244,6 @@ mod parallel_simd {
 	}
 
 	pub struct NodeParSimd2<T: Coord, const N: usize> {
-		#[cfg(test)]
 		pub ind: usize,
 		pub mass: *mut T,
 		pub pos: *mut T,
@@ -277,6 +269,7 @@ mod parallel_simd {
 					let next_ind = self.ind + 1;
 					let n2_start_ind = self.n2_start_ind.max(next_ind);
 					let ret = NodeParSimd {
+						#[cfg(test)]
 						ind: self.ind,
 						mass: unsafe { layout.masses.get_unchecked(self.ind) },
 						n2_iter: NodeParSimdIter2 {
@@ -320,7 +313,6 @@ mod parallel_simd {
 					let layout = unsafe { self.layout.0.as_mut() };
 					let next_offset = self.offset + N * layout.settings.dimensions;
 					let ret = NodeParSimd2 {
-						#[cfg(test)]
 						ind: self.ind,
 						mass: unsafe { layout.masses.as_mut_ptr().add(self.ind) },
 						pos: unsafe { layout.points.points.as_mut_ptr().add(self.offset) },
@@ -375,7 +367,6 @@ mod parallel_simd_cleaner {
 	}
 
 	pub struct NodeParSimd2<T: Coord, const N: usize> {
-		#[cfg(test)]
 		pub ind: usize,
 		pub mass: *mut T,
 		pub pos: *mut T,
@@ -434,7 +425,6 @@ mod parallel_simd_cleaner {
 					let layout = unsafe { self.layout.0.as_mut() };
 					let next_offset = self.offset + N * layout.settings.dimensions;
 					let ret = NodeParSimd2 {
-						#[cfg(test)]
 						ind: self.ind,
 						mass: unsafe { layout.masses.as_mut_ptr().add(self.ind) },
 						pos: unsafe { layout.points.points.as_mut_ptr().add(self.offset) },
@@ -4,6 +4,7 @@ use rayon::prelude::*;
 use std::marker::PhantomData;
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Settings<T: Coord> {
 	/// Optimize repulsion using Barnes-Hut algorithm (time passes from N^2 to NlogN)
 	/// The argument is theta.
@@ -20,6 +21,8 @@ pub struct Settings<T: Coord> {
 	/// Requires `T: Send + Sync`
 	#[cfg(feature = "parallel")]
 	pub chunk_size: Option<usize>,
+	/// Cluster-aware attraction and gravity, using the partition given by [`Layout::set_clusters`]
+	pub clusters: Option<ClusterSettings<T>>,
 	/// Number of spatial dimensions
 	pub dimensions: usize,
 	/// Move hubs (high degree nodes) to the center
@@ -50,6 +53,7 @@ impl<T: Coord> Default for Settings<T> {
 			barnes_hut: None,
 			#[cfg(feature = "parallel")]
 			chunk_size: Some(256),
+			clusters: None,
 			dimensions: 2,
 			dissuade_hubs: false,
 			ka: T::one(),
@@ -63,14 +67,76 @@ impl<T: Coord> Default for Settings<T> {
 	}
 }
 
+/// Parameters of the cluster-aware forces
+///
+/// Nodes without a cluster are not affected.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClusterSettings<T: Coord> {
+	/// Attraction multiplier for the edges between nodes of the same cluster
+	pub intra_attraction: T,
+	/// Attraction multiplier for the edges between different clusters (less than 1 to separate clusters)
+	pub inter_attraction: T,
+	/// Gravity coefficient toward the centroid of the node's cluster
+	///
+	/// Follows `Settings::strong_gravity`.
+	pub gravity: T,
+}
+
+impl<T: Coord> Default for ClusterSettings<T> {
+	fn default() -> Self {
+		Self {
+			intra_attraction: T::one(),
+			inter_attraction: T::from(0.25).unwrap_or_else(T::one),
+			gravity: T::one(),
+		}
+	}
+}
+
+/// Buffers of the cluster-aware forces, reused between iterations
+pub(crate) struct ClusterBuffers<T> {
+	/// Edge weights multiplied by the cluster multipliers
+	pub weights: Vec<T>,
+	/// Centroid of each cluster
+	pub centroids: Vec<T>,
+	/// Number of nodes of each cluster
+	pub sizes: Vec<T>,
+}
+
+impl<T> Default for ClusterBuffers<T> {
+	fn default() -> Self {
+		Self {
+			weights: Vec::new(),
+			centroids: Vec::new(),
+			sizes: Vec::new(),
+		}
+	}
+}
+
 pub struct Layout<T: Coord> {
+	pub(crate) cluster_buffers: ClusterBuffers<T>,
+	/// Cluster of each node (nodes after the end have no cluster)
+	pub(crate) clusters: Vec<usize>,
+	/// Applied after each iteration
+	pub(crate) constraints: Vec<Box<dyn crate::constraint::Constraint<T> + Send + Sync>>,
+	pub(crate) degrees: Degrees<T>,
 	pub edges: Vec<Edge>,
+	/// User-defined forces
+	pub(crate) forces: Vec<Box<dyn crate::forces::Force<T> + Send + Sync>>,
+	/// Masses are updated according to `mass_mode` when the graph changes
 	pub masses: Vec<T>,
+	pub(crate) mass_mode: MassMode<T>,
 	/// List of the nodes' positions
 	pub points: PointList<T>,
 	pub(crate) settings: Settings<T>,
 	pub speeds: PointList<T>,
 	pub old_speeds: PointList<T>,
+	pub(crate) observers: crate::observer::Observers<T>,
+	/// Pinned nodes, by index (nodes after the end are not pinned)
+	pub(crate) pinned: Vec<bool>,
+	/// Publication of the positions after each iteration
+	#[cfg(feature = "publish")]
+	pub(crate) publisher: Option<crate::publish::Publisher<T>>,
 	pub weights: Option<Vec<T>>,
 
 	pub(crate) fn_attraction: fn(&mut Self),
@@ -79,7 +145,7 @@ pub struct Layout<T: Coord> {
 }
 
 impl<T: Coord> Layout<T> {
-	pub fn iter_nodes(&mut self) -> NodeIter<T> {
+	pub(crate) fn iter_nodes(&mut self) -> NodeIter<T> {
 		NodeIter {
 			ind: 0,
 			layout: SendPtr(self.into()),
@@ -89,21 +155,38 @@ impl<T: Coord> Layout<T> {
 	}
 }
 
+/// Diagonals of tiles, split so that the tiles of a level do not share nodes
+///
+/// The tile `(x0, y0)` updates the chunks starting at `x0` and `x0 + y0`, so it conflicts with the tiles
+/// `(x0 ± y0, y0)`. Each diagonal `y0 > 0` is then computed in two levels, by parity of `x0 / y0`.
+/// Yields `(y0, parity)`.
 #[cfg(feature = "parallel")]
-impl<T: Coord + Send> Layout<T> {
-	pub fn iter_par_nodes(
+fn par_levels(nb_nodes: usize, chunk_size: usize) -> impl Iterator<Item = (usize, usize)> {
+	(0..nb_nodes)
+		.step_by(chunk_size)
+		.flat_map(|y0| (0..if y0 == 0 { 1 } else { 2 }).map(move |parity| (y0, parity)))
+}
+
+#[cfg(feature = "parallel")]
+impl<T: Coord + Send + Sync> Layout<T> {
+	/// Parallel iterators over the pairs of nodes, by tiles of `chunk_size²` pairs
+	///
+	/// The parallel iterators must be consumed one after the other: tiles are only disjoint within a level.
+	pub(crate) fn iter_par_nodes(
 		&mut self,
 		chunk_size: usize,
 	) -> impl Iterator<Item = impl ParallelIterator<Item = NodeParIter<T>>> {
 		let ptr = SendPtr(self.into());
 		let dimensions = self.settings.dimensions;
 		let chunk_size_d = chunk_size * dimensions;
-		let n = self.masses.len() * dimensions;
-		(0..self.masses.len()).step_by(chunk_size).map(move |y0| {
+		let nb_nodes = self.masses.len();
+		let n = nb_nodes * dimensions;
+		par_levels(nb_nodes, chunk_size).map(move |(y0, parity)| {
 			let y0_d = y0 * dimensions;
-			(0..self.masses.len() - y0)
+			(0..nb_nodes - y0)
 				.into_par_iter()
 				.step_by(chunk_size)
+				.filter(move |x0| y0 == 0 || x0 / y0 % 2 == parity)
 				.map(move |x0| {
 					let x0_d = x0 * dimensions;
 					NodeParIter {
@@ -122,8 +205,8 @@ impl<T: Coord + Send> Layout<T> {
 }
 
 #[cfg(all(feature = "parallel", any(target_arch = "x86", target_arch = "x86_64")))]
-impl<T: Coord + Send> Layout<T> {
-	pub fn iter_par_simd_nodes<const N: usize>(
+impl<T: Coord + Send + Sync> Layout<T> {
+	pub(crate) fn iter_par_simd_nodes<const N: usize>(
 		&mut self,
 		chunk_size: usize,
 	) -> impl Iterator<Item = impl ParallelIterator<Item = NodeParSimdIter<T, N>>> {
@@ -132,11 +215,12 @@ impl<T: Coord + Send> Layout<T> {
 		let chunk_size_d = chunk_size * dimensions;
 		let n = self.masses.len();
 		let n_d = n * dimensions;
-		(0..n).step_by(chunk_size).map(move |y0| {
+		par_levels(n, chunk_size).map(move |(y0, parity)| {
 			let y0_d = y0 * dimensions;
 			(0..n - y0)
 				.into_par_iter()
 				.step_by(chunk_size)
+				.filter(move |x0| y0 == 0 || x0 / y0 % 2 == parity)
 				.map(move |x0| {
 					let x0_d = x0 * dimensions;
 					NodeParSimdIter {
@@ -229,6 +313,36 @@ mod test {
 		}
 	}
 
+	#[test]
+	#[cfg(feature = "parallel")]
+	fn test_par_levels_disjoint() {
+		for n_nodes in 1usize..40 {
+			let mut layout = Layout::<f32>::from_graph(
+				vec![],
+				Nodes::Degree(n_nodes),
+				None,
+				Settings::default(),
+			);
+			for chunk_iter in layout.iter_par_nodes(3) {
+				let tiles: Vec<BTreeSet<usize>> = chunk_iter
+					.map(|n1_iter| {
+						let mut nodes = BTreeSet::new();
+						for n1 in n1_iter {
+							nodes.insert(n1.ind);
+							nodes.extend(n1.n2_iter.map(|n2| n2.ind));
+						}
+						nodes
+					})
+					.collect();
+				for (i, tile1) in tiles.iter().enumerate() {
+					for tile2 in &tiles[i + 1..] {
+						assert!(tile1.is_disjoint(tile2));
+					}
+				}
+			}
+		}
+	}
+
 	#[test]
 	#[cfg(feature = "parallel")]
 	fn test_iter_par_simd_nodes() {
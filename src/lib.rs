@@ -1,22 +1,41 @@
-#![feature(drain_filter)]
 #![feature(specialization)]
-#![feature(stdsimd)]
 #![feature(trait_alias)]
 #![allow(incomplete_features)]
-#![feature(core_intrinsics)]
 
+#[cfg(feature = "capi")]
+pub mod capi;
+mod checkpoint;
+pub mod constraint;
+pub mod export;
 mod forces;
+pub mod io;
 mod iter;
 mod layout;
+mod observer;
+#[cfg(feature = "petgraph")]
+mod petgraph;
+#[cfg(feature = "publish")]
+mod publish;
+pub mod render;
+mod runner;
+mod snapshot;
+mod source;
 mod util;
 
 use forces::{Attraction, Repulsion};
 
-pub use layout::{Layout, Settings};
-pub use util::{Coord, Edge, Nodes, PointIter, PointIterMut, PointList, Position};
+pub use checkpoint::CheckpointCoord;
+pub use forces::{Force, PairNode};
+pub use layout::{ClusterSettings, Layout, Settings};
+pub use observer::{Control, Observer, Phase};
+#[cfg(feature = "publish")]
+pub use publish::{PointsFrame, PointsReader};
+pub use runner::{Command, Frame, FrameReader, LayoutRunner};
+pub use snapshot::LayoutSnapshot;
+pub use source::{GraphSource, SliceEdges};
+pub use util::{Coord, Edge, MassMode, Nodes, PointIter, PointIterMut, PointList, Position};
 
 use itertools::izip;
-use num_traits::cast::NumCast;
 
 impl<'a, T: Coord + std::fmt::Debug> Layout<T>
 where
@@ -25,12 +44,17 @@ where
 	/// Instantiates an empty layout
 	pub fn empty(weighted: bool, settings: Settings<T>) -> Self {
 		Self {
+			cluster_buffers: Default::default(),
+			clusters: Vec::new(),
+			constraints: Vec::new(),
+			degrees: util::Degrees::new(0),
 			edges: Vec::new(),
 			points: PointList {
 				dimensions: settings.dimensions,
 				points: Vec::new(),
 			},
 			masses: Vec::new(),
+			mass_mode: MassMode::Fixed,
 			speeds: PointList {
 				dimensions: settings.dimensions,
 				points: Vec::new(),
@@ -39,6 +63,11 @@ where
 				dimensions: settings.dimensions,
 				points: Vec::new(),
 			},
+			forces: Vec::new(),
+			observers: Default::default(),
+			pinned: Vec::new(),
+			#[cfg(feature = "publish")]
+			publisher: None,
 			weights: if weighted { Some(Vec::new()) } else { None },
 			fn_attraction: Self::choose_attraction(&settings),
 			fn_gravity: forces::choose_gravity(&settings),
@@ -61,38 +90,92 @@ where
 		rand::distributions::Standard: rand::distributions::Distribution<T>,
 		T: rand::distributions::uniform::SampleUniform,
 	{
+		let positions = util::random_positions(nodes.len(), settings.dimensions);
+		Self::from_parts(edges, nodes, positions, weights, settings)
+	}
+
+	/// Instanciates a layout from any graph representation
+	///
+	/// If `source` has no positions, nodes are positioned randomly.
+	#[cfg(feature = "rand")]
+	pub fn from_source<S: GraphSource<T> + ?Sized>(source: &S, settings: Settings<T>) -> Self
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		let positions = match source.positions() {
+			Some(positions) => positions.into_owned(),
+			None => util::random_positions(source.nb_nodes(), settings.dimensions),
+		};
+		Self::from_source_with_positions(source, positions, settings)
+	}
+
+	/// Instanciates a layout from any graph representation, using its positions
+	///
+	/// # Panics
+	/// Panics if `source` has no positions, or if an edge `(n1, n2)` does not respect `n1 < n2`.
+	pub fn from_positioned_source<S: GraphSource<T> + ?Sized>(
+		source: &S,
+		settings: Settings<T>,
+	) -> Self {
+		let positions = source
+			.positions()
+			.expect("Graph source has no positions")
+			.into_owned();
+		Self::from_source_with_positions(source, positions, settings)
+	}
+
+	fn from_source_with_positions<S: GraphSource<T> + ?Sized>(
+		source: &S,
+		positions: Vec<T>,
+		settings: Settings<T>,
+	) -> Self {
+		let mut weights = source.weighted().then(Vec::new);
+		let edges: Vec<Edge> = source
+			.edges()
+			.map(|(edge, weight)| {
+				if let Some(weights) = &mut weights {
+					weights.push(weight.unwrap_or_else(T::one));
+				}
+				edge
+			})
+			.collect();
+
+		let nodes = source.nodes();
+		assert_eq!(nodes.len(), source.nb_nodes());
+		Self::from_parts(edges, nodes, positions, weights, settings)
+	}
+
+	/// Common part of the constructors, taking ownership of the vectors
+	fn from_parts(
+		edges: Vec<Edge>,
+		nodes: Nodes<T>,
+		positions: Vec<T>,
+		weights: Option<Vec<T>>,
+		settings: Settings<T>,
+	) -> Self {
+		let nb_nodes = nodes.len();
+		// Edges are used as unchecked indices, so a safe `GraphSource` must not be trusted
+		assert!(edges.iter().all(|(n1, n2)| n1 < n2 && *n2 < nb_nodes));
 		if let Some(weights) = &weights {
 			assert_eq!(weights.len(), edges.len());
 		}
-
-		let nodes = match nodes {
-			Nodes::Degree(nb_nodes) => {
-				let mut degrees: Vec<usize> = vec![0; nb_nodes];
-				for (n1, n2) in edges.iter() {
-					*degrees.get_mut(*n1).unwrap() += 1;
-					*degrees.get_mut(*n2).unwrap() += 1;
-				}
-				degrees
-					.into_iter()
-					.map(|degree| <T as NumCast>::from(degree).unwrap())
-					.collect()
-			}
-			Nodes::Mass(masses) => masses,
-		};
+		let (nodes, mass_mode, degrees) = util::init_masses(nodes, &edges, weights.as_deref());
 
 		let nb = nodes.len() * settings.dimensions;
+		assert_eq!(positions.len(), nb);
 		Self {
+			cluster_buffers: Default::default(),
+			clusters: Vec::new(),
+			constraints: Vec::new(),
+			degrees,
 			edges,
+			masses: nodes,
+			mass_mode,
 			points: PointList {
 				dimensions: settings.dimensions,
-				points: {
-					let mut rng = rand::thread_rng();
-					(0..nodes.len())
-						.flat_map(|_| util::sample_unit_ncube(&mut rng, settings.dimensions))
-						.collect()
-				},
+				points: positions,
 			},
-			masses: nodes,
 			speeds: PointList {
 				dimensions: settings.dimensions,
 				points: (0..nb).map(|_| T::zero()).collect(),
@@ -101,6 +184,11 @@ where
 				dimensions: settings.dimensions,
 				points: (0..nb).map(|_| T::zero()).collect(),
 			},
+			forces: Vec::new(),
+			observers: Default::default(),
+			pinned: Vec::new(),
+			#[cfg(feature = "publish")]
+			publisher: None,
 			weights,
 			fn_attraction: Self::choose_attraction(&settings),
 			fn_gravity: forces::choose_gravity(&settings),
@@ -109,6 +197,45 @@ where
 		}
 	}
 
+	/// Instanciates a randomly positioned layout from the adjacency matrix of an undirected graph, in CSR format, with `Nodes::Degree` masses
+	///
+	/// Row `i` has columns `indices[indptr[i]..indptr[i+1]]` and values `data[indptr[i]..indptr[i+1]]`.
+	/// Only the upper triangle is read: entries `(i, j)` with `i < j` are edges, with weight `data`.
+	#[cfg(feature = "rand")]
+	pub fn from_csr(
+		indptr: &[usize],
+		indices: &[usize],
+		data: Option<&[T]>,
+		settings: Settings<T>,
+	) -> Self
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		assert!(!indptr.is_empty());
+		if let Some(data) = data {
+			assert_eq!(data.len(), indices.len());
+		}
+
+		let nb_nodes = indptr.len() - 1;
+		let mut edges = Vec::new();
+		let mut weights = data.map(|_| Vec::new());
+		for (row, range) in indptr.windows(2).enumerate() {
+			for k in range[0]..range[1] {
+				let col = indices[k];
+				assert!(col < nb_nodes);
+				if col > row {
+					edges.push((row, col));
+					if let (Some(weights), Some(data)) = (&mut weights, data) {
+						weights.push(data[k].clone());
+					}
+				}
+			}
+		}
+
+		Self::from_graph(edges, Nodes::Degree(nb_nodes), weights, settings)
+	}
+
 	/// Instanciates layout from an undirected graph, using initial positions
 	///
 	/// Assumes edges `(n1, n2)` respect `n1 < n2`
@@ -124,55 +251,39 @@ where
 	where
 		T: 'a,
 	{
-		if let Some(weights) = &weights {
-			assert_eq!(weights.len(), edges.len());
-		}
+		Self::from_parts(edges, nodes, positions, weights, settings)
+	}
 
-		let nodes = match nodes {
-			Nodes::Degree(nb_nodes) => {
-				let mut degrees: Vec<usize> = vec![0; nb_nodes];
-				for (n1, n2) in edges.iter() {
-					*degrees.get_mut(*n1).unwrap() += 1;
-					*degrees.get_mut(*n2).unwrap() += 1;
-				}
-				degrees
-					.into_iter()
-					.map(|degree| <T as NumCast>::from(degree).unwrap())
-					.collect()
-			}
-			Nodes::Mass(masses) => masses,
-		};
+	pub fn get_settings(&self) -> &Settings<T> {
+		&self.settings
+	}
 
-		let nb = nodes.len() * settings.dimensions;
-		assert_eq!(positions.len(), nb);
-		Self {
-			edges,
-			masses: nodes,
-			points: PointList {
-				dimensions: settings.dimensions,
-				points: positions,
-			},
-			speeds: PointList {
-				dimensions: settings.dimensions,
-				points: (0..nb).map(|_| T::zero()).collect(),
-			},
-			old_speeds: PointList {
-				dimensions: settings.dimensions,
-				points: (0..nb).map(|_| T::zero()).collect(),
-			},
-			weights,
-			fn_attraction: Self::choose_attraction(&settings),
-			fn_gravity: forces::choose_gravity(&settings),
-			fn_repulsion: Self::choose_repulsion(&settings),
-			settings,
+	/// Returns how masses are updated when the graph changes
+	pub fn get_mass_mode(&self) -> MassMode<T> {
+		self.mass_mode
+	}
+
+	/// Changes how masses are updated when the graph changes
+	///
+	/// All the masses are recomputed, unless `mass_mode` is `MassMode::Fixed`.
+	pub fn set_mass_mode(&mut self, mass_mode: MassMode<T>) {
+		self.mass_mode = mass_mode;
+		for node in 0..self.masses.len() {
+			self.update_mass(node);
 		}
 	}
 
-	pub fn get_settings(&self) -> &Settings<T> {
-		&self.settings
+	fn update_mass(&mut self, node: usize) {
+		if let Some(mass) = self.degrees.mass(&self.mass_mode, node) {
+			self.masses[node] = mass;
+		}
 	}
 
 	/// New node indices in arguments start at the current number of nodes
+	///
+	/// Masses of existing nodes are updated according to the mass mode.
+	/// Masses of new nodes are given by `nodes`, or derived from the graph as `nodes` specifies,
+	/// even if it differs from the mass mode. An empty layout takes the mass mode of `nodes`.
 	pub fn add_nodes(
 		&mut self,
 		edges: &[Edge],
@@ -180,19 +291,16 @@ where
 		positions: &[T],
 		weights: Option<&[T]>,
 	) {
-		let new_nodes;
+		let old_nodes = self.masses.len();
+		let new_nodes = nodes.len();
+		let new_mass_mode = nodes.mass_mode();
 		match nodes {
-			Nodes::Degree(nb_nodes) => {
-				new_nodes = nb_nodes;
-				self.masses.extend((0..nb_nodes).map(|_| T::zero()));
-				for (n1, n2) in edges.iter() {
-					self.masses[*n1] += T::one();
-					self.masses[*n2] += T::one();
+			Nodes::Mass(masses) => self.masses.extend(masses),
+			_ => {
+				if old_nodes == 0 {
+					self.mass_mode = new_mass_mode;
 				}
-			}
-			Nodes::Mass(masses) => {
-				new_nodes = masses.len();
-				self.masses.extend_from_slice(&masses);
+				self.masses.extend((0..new_nodes).map(|_| T::zero()));
 			}
 		}
 		assert_eq!(positions.len(), new_nodes * self.settings.dimensions);
@@ -212,14 +320,32 @@ where
 			(None, None) => {}
 			_ => panic!("Inconsistent weighting"),
 		}
+
+		self.degrees.extend(new_nodes);
+		for (edge, (n1, n2)) in edges.iter().enumerate() {
+			self.degrees
+				.add_edge(*n1, *n2, weights.map(|weights| &weights[edge]));
+			if *n1 < old_nodes {
+				self.update_mass(*n1);
+			}
+			if *n2 < old_nodes {
+				self.update_mass(*n2);
+			}
+		}
+		for node in old_nodes..old_nodes + new_nodes {
+			if let Some(mass) = self.degrees.mass(&new_mass_mode, node) {
+				self.masses[node] = mass;
+			}
+		}
 	}
 
 	/// Remove edges by index
 	pub fn remove_edge(&mut self, edge: usize) {
-		self.edges.remove(edge);
-		if let Some(weights) = &mut self.weights {
-			weights.remove(edge);
-		}
+		let (n1, n2) = self.edges.remove(edge);
+		let weight = self.weights.as_mut().map(|weights| weights.remove(edge));
+		self.degrees.remove_edge(n1, n2, weight.as_ref());
+		self.update_mass(n1);
+		self.update_mass(n2);
 	}
 
 	/// Remove a node by index
@@ -233,23 +359,57 @@ where
 		self.masses.remove(node);
 		self.speeds.remove(node);
 		self.old_speeds.remove(node);
+		self.degrees.remove(node);
+		if node < self.pinned.len() {
+			self.pinned.remove(node);
+		}
+		if node < self.clusters.len() {
+			self.clusters.remove(node);
+		}
 	}
 
 	/// Remove a node's incident edges
+	///
+	/// Indices greater than `node` are decremented in the remaining edges,
+	/// so the node should then be removed with [`Layout::remove_node`].
 	pub fn remove_incident_edges(&mut self, node: usize) {
-		self.edges.drain_filter(|(n1, n2)| {
+		for (edge, (n1, n2)) in self.edges.iter().enumerate() {
 			if *n1 == node || *n2 == node {
-				true
-			} else {
-				if *n1 > node {
-					*n1 -= 1;
+				self.degrees.remove_edge(
+					*n1,
+					*n2,
+					self.weights.as_ref().map(|weights| &weights[edge]),
+				);
+				if let Some(mass) = self.degrees.mass(&self.mass_mode, *n1) {
+					self.masses[*n1] = mass;
 				}
-				if *n2 > node {
-					*n2 -= 1;
+				if let Some(mass) = self.degrees.mass(&self.mass_mode, *n2) {
+					self.masses[*n2] = mass;
 				}
-				false
 			}
-		});
+		}
+		if let Some(weights) = &mut self.weights {
+			let mut edges = self.edges.iter();
+			weights.retain(|_| {
+				let (n1, n2) = edges.next().unwrap();
+				*n1 != node && *n2 != node
+			});
+		}
+		self.edges
+			.extract_if(.., |(n1, n2)| {
+				if *n1 == node || *n2 == node {
+					true
+				} else {
+					if *n1 > node {
+						*n1 -= 1;
+					}
+					if *n2 > node {
+						*n2 -= 1;
+					}
+					false
+				}
+			})
+			.for_each(drop);
 	}
 
 	/// Remove a node by index, automatically removing all its incident edges
@@ -261,6 +421,41 @@ where
 		self.remove_node(node);
 	}
 
+	/// Pins or unpins a node
+	///
+	/// A pinned node is not moved by iterations, but still attracts and repels the other nodes.
+	pub fn set_pinned(&mut self, node: usize, pinned: bool) {
+		assert!(node < self.masses.len());
+		if node >= self.pinned.len() {
+			if !pinned {
+				return;
+			}
+			self.pinned.resize(node + 1, false);
+		}
+		self.pinned[node] = pinned;
+	}
+
+	pub fn is_pinned(&self, node: usize) -> bool {
+		self.pinned.get(node).copied().unwrap_or(false)
+	}
+
+	/// Sets the cluster of each node (e.g. communities), used if `Settings::clusters` is set
+	///
+	/// Clusters are numbered from 0 and must be dense, as per-cluster data is indexed by cluster.
+	/// Nodes after the end of `clusters` (e.g. nodes added later) have no cluster.
+	///
+	/// # Panics
+	/// Panics if `clusters` is longer than the number of nodes, or if a cluster is not less than its length.
+	pub fn set_clusters(&mut self, clusters: Vec<usize>) {
+		assert!(clusters.len() <= self.masses.len());
+		assert!(clusters.iter().all(|cluster| *cluster < clusters.len()));
+		self.clusters = clusters;
+	}
+
+	pub fn get_clusters(&self) -> &[usize] {
+		&self.clusters
+	}
+
 	/// Changes layout settings
 	///
 	/// # Panics
@@ -274,12 +469,26 @@ where
 	}
 
 	/// Computes an iteration of ForceAtlas2
+	///
+	/// Does nothing if an observer requested a stop (see [`Layout::resume`]).
 	pub fn iteration(&mut self) {
-		self.init_iteration();
-		self.apply_attraction();
-		self.apply_repulsion();
-		self.apply_gravity();
-		self.apply_forces();
+		if self.observers.stopped {
+			return;
+		}
+		if self.observers.list.is_empty() {
+			self.init_iteration();
+			self.apply_attraction();
+			self.apply_repulsion();
+			self.apply_gravity();
+			self.apply_custom_forces();
+			self.apply_forces();
+			self.apply_constraints();
+		} else {
+			self.observed_iteration();
+		}
+		self.observers.iteration += 1;
+		#[cfg(feature = "publish")]
+		self.publish_points();
 	}
 
 	fn init_iteration(&mut self) {
@@ -295,11 +504,18 @@ where
 	}
 
 	fn apply_attraction(&mut self) {
-		(self.fn_attraction)(self)
+		if self.settings.clusters.is_some() {
+			forces::attraction::apply_attraction_clusters(self, self.fn_attraction)
+		} else {
+			(self.fn_attraction)(self)
+		}
 	}
 
 	fn apply_gravity(&mut self) {
-		(self.fn_gravity)(self)
+		(self.fn_gravity)(self);
+		if self.settings.clusters.is_some() {
+			forces::gravity::apply_gravity_clusters(self)
+		}
 	}
 
 	fn apply_repulsion(&mut self) {
@@ -307,11 +523,16 @@ where
 	}
 
 	fn apply_forces(&mut self) {
-		for (pos, speed, old_speed) in izip!(
+		for (node, (pos, speed, old_speed)) in izip!(
 			self.points.iter_mut(),
 			self.speeds.iter_mut(),
 			self.old_speeds.iter()
-		) {
+		)
+		.enumerate()
+		{
+			if self.pinned.get(node) == Some(&true) {
+				continue;
+			}
 			let swinging = speed
 				.iter()
 				.zip(old_speed.iter())
@@ -471,6 +692,7 @@ mod tests {
 			Settings {
 				#[cfg(feature = "parallel")]
 				chunk_size: None,
+				clusters: None,
 				dimensions: 2,
 				dissuade_hubs: false,
 				ka: 0.5,
@@ -506,6 +728,188 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn test_mass_modes() {
+		fn mass(degree: usize, strength: f64) -> f64 {
+			degree as f64 * 10.0 + strength
+		}
+
+		let edges = vec![(0, 1), (0, 2), (1, 2)];
+		let positions = vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+		let weights = Some(vec![1.0, 2.0, 4.0]);
+
+		let layout = Layout::<f64>::from_position_graph(
+			edges.clone(),
+			Nodes::WeightedDegree(3),
+			positions.clone(),
+			weights.clone(),
+			Settings::default(),
+		);
+		assert_eq!(layout.masses, vec![3.0, 5.0, 6.0]);
+
+		let layout = Layout::<f64>::from_position_graph(
+			edges.clone(),
+			Nodes::LogDegree(3),
+			positions.clone(),
+			weights.clone(),
+			Settings::default(),
+		);
+		assert_eq!(layout.masses, vec![2.0f64.ln_1p(); 3]);
+
+		let mut layout = Layout::<f64>::from_position_graph(
+			edges,
+			Nodes::Derived(3, mass),
+			positions,
+			weights,
+			Settings::default(),
+		);
+		assert_eq!(layout.masses, vec![23.0, 25.0, 26.0]);
+
+		layout.add_nodes(
+			&[(2, 3)],
+			Nodes::Derived(1, mass),
+			&[1.0, 1.0],
+			Some(&[0.5]),
+		);
+		assert_eq!(layout.masses, vec![23.0, 25.0, 36.5, 10.5]);
+
+		layout.remove_node_with_edges(1);
+		assert_eq!(layout.edges, vec![(0, 1), (1, 2)]);
+		assert_eq!(layout.weights, Some(vec![2.0, 0.5]));
+		assert_eq!(layout.masses, vec![12.0, 22.5, 10.5]);
+
+		// Given masses are kept, other masses follow the mass mode
+		layout.add_nodes(&[(0, 3)], Nodes::Mass(vec![7.0]), &[2.0, 2.0], Some(&[1.0]));
+		assert_eq!(layout.masses, vec![23.0, 22.5, 10.5, 7.0]);
+
+		// New nodes can derive their masses differently
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			Nodes::Mass(vec![5.0, 5.0]),
+			vec![0.0, 0.0, 1.0, 0.0],
+			None,
+			Settings::default(),
+		);
+		layout.add_nodes(
+			&[(1, 2), (1, 3)],
+			Nodes::Degree(2),
+			&[0.0, 1.0, 1.0, 1.0],
+			None,
+		);
+		assert_eq!(layout.masses, vec![5.0, 5.0, 1.0, 1.0]);
+		assert!(layout.get_mass_mode() == MassMode::Fixed);
+	}
+
+	#[cfg(feature = "rand")]
+	#[test]
+	fn test_from_csr() {
+		// [[0, 1, 2],
+		//  [1, 5, 0],
+		//  [2, 0, 0]]
+		let layout = Layout::<f64>::from_csr(
+			&[0, 2, 4, 5],
+			&[1, 2, 0, 1, 0],
+			Some(&[1.0, 2.0, 1.0, 5.0, 2.0]),
+			Settings::default(),
+		);
+		assert_eq!(layout.edges, vec![(0, 1), (0, 2)]);
+		assert_eq!(layout.weights, Some(vec![1.0, 2.0]));
+		assert_eq!(layout.masses, vec![2.0, 1.0, 1.0]);
+	}
+
+	#[test]
+	fn test_pinned() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (1, 2)],
+			Nodes::Degree(3),
+			vec![0.0, 0.0, 1.0, 0.0, 5.0, 5.0],
+			None,
+			Settings::default(),
+		);
+		layout.set_pinned(0, true);
+		layout.set_pinned(2, false);
+		assert!(layout.is_pinned(0));
+		assert!(!layout.is_pinned(1));
+		assert!(!layout.is_pinned(2));
+
+		layout.iteration();
+		assert_eq!(layout.points.get(0), &[0.0, 0.0]);
+		assert_ne!(layout.points.get(1), &[1.0, 0.0]);
+
+		layout.remove_incident_edges(0);
+		layout.remove_node(0);
+		assert!(!layout.is_pinned(0));
+	}
+
+	#[test]
+	fn test_clusters() {
+		let positions = vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 3.0, 3.0, 4.0, 3.0, 3.0, 4.0];
+		let mut clustered = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (0, 2), (1, 2), (2, 3), (3, 4), (3, 5), (4, 5)],
+			Nodes::Mass(vec![1.0; 6]),
+			positions.clone(),
+			None,
+			Settings {
+				clusters: Some(ClusterSettings {
+					intra_attraction: 2.0,
+					inter_attraction: 0.0,
+					gravity: 0.0,
+				}),
+				..Default::default()
+			},
+		);
+		clustered.set_clusters(vec![0, 0, 0, 1, 1, 1]);
+
+		// Same as doubling the intra-cluster edges' weights and removing the inter-cluster edge
+		let mut reference = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (0, 2), (1, 2), (3, 4), (3, 5), (4, 5)],
+			Nodes::Mass(vec![1.0; 6]),
+			positions.clone(),
+			Some(vec![2.0; 6]),
+			Settings::default(),
+		);
+		for _ in 0..10 {
+			clustered.iteration();
+			reference.iteration();
+		}
+		assert_eq!(clustered.points.points, reference.points.points);
+
+		// Gravity toward the cluster's centroid only
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![],
+			Nodes::Mass(vec![1.0; 6]),
+			positions,
+			None,
+			Settings {
+				clusters: Some(ClusterSettings {
+					gravity: 1.0,
+					..Default::default()
+				}),
+				ka: 0.0,
+				kg: 0.0,
+				kr: 0.0,
+				..Default::default()
+			},
+		);
+		layout.set_clusters(vec![0, 0, 0, 1, 1]);
+		layout.iteration();
+		// Node 0 moves toward (1/3, 1/3), node 5 has no cluster
+		assert!(layout.points.get(0)[0] > 0.0);
+		assert!(layout.points.get(0)[1] > 0.0);
+		assert_eq!(layout.points.get(5), &[3.0, 4.0]);
+
+		layout.remove_node(0);
+		assert_eq!(layout.get_clusters(), &[0, 0, 1, 1]);
+	}
+
+	#[test]
+	#[should_panic]
+	fn test_sparse_clusters() {
+		let mut layout =
+			Layout::<f64>::from_graph(vec![(0, 1)], Nodes::Degree(2), None, Settings::default());
+		layout.set_clusters(vec![0, usize::MAX]);
+	}
+
 	#[test]
 	fn check_alloc() {
 		let mut layout = Layout::<f64>::from_graph(
@@ -0,0 +1,223 @@
+//! Callbacks around each phase of an iteration
+
+use crate::{
+	forces::{Attraction, Repulsion},
+	layout::{Layout, Settings},
+	util::*,
+};
+
+/// Phase of an iteration, in order of execution
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Phase {
+	/// Speeds are moved to `old_speeds` and reset
+	InitIteration,
+	Attraction,
+	Repulsion,
+	Gravity,
+	/// User-defined forces (see [`Force`](crate::Force))
+	CustomForces,
+	/// Positions are updated according to speeds, then constrained (see [`constraint`](crate::constraint))
+	ApplyForces,
+}
+
+impl Phase {
+	pub const ALL: [Phase; 6] = [
+		Phase::InitIteration,
+		Phase::Attraction,
+		Phase::Repulsion,
+		Phase::Gravity,
+		Phase::CustomForces,
+		Phase::ApplyForces,
+	];
+}
+
+/// Returned by observers to control the layout
+pub enum Control<T: Coord> {
+	Continue,
+	/// Stops after the current iteration: the next ones do nothing until [`Layout::resume`]
+	///
+	/// The current iteration is completed, so that the layout stays consistent and can be resumed.
+	Stop,
+	/// Changes the settings before the next phase (see [`Layout::set_settings`])
+	SetSettings(Settings<T>),
+}
+
+/// Callbacks called before and after each phase of an iteration
+///
+/// The layout (positions, speeds, settings, iteration number) is read-only during the callbacks.
+/// When several observers are registered, they are all called, in order of registration.
+pub trait Observer<T: Coord> {
+	fn before(&mut self, _phase: Phase, _layout: &Layout<T>) -> Control<T> {
+		Control::Continue
+	}
+
+	fn after(&mut self, _phase: Phase, _layout: &Layout<T>) -> Control<T> {
+		Control::Continue
+	}
+}
+
+pub(crate) struct Observers<T: Coord> {
+	/// Number of completed iterations
+	pub iteration: u64,
+	pub list: Vec<Box<dyn Observer<T> + Send + Sync>>,
+	pub stopped: bool,
+}
+
+impl<T: Coord> Default for Observers<T> {
+	fn default() -> Self {
+		Self {
+			iteration: 0,
+			list: Vec::new(),
+			stopped: false,
+		}
+	}
+}
+
+impl<T: Coord> Layout<T> {
+	/// Registers an observer, called at each iteration
+	pub fn add_observer(&mut self, observer: Box<dyn Observer<T> + Send + Sync>) {
+		self.observers.list.push(observer);
+	}
+
+	/// Removes all the observers
+	pub fn clear_observers(&mut self) {
+		self.observers.list.clear();
+	}
+
+	/// Number of completed iterations (the current one during observer callbacks)
+	pub fn iteration_number(&self) -> u64 {
+		self.observers.iteration
+	}
+
+	/// Whether an observer requested a stop: iterations do nothing until [`Layout::resume`]
+	pub fn is_stopped(&self) -> bool {
+		self.observers.stopped
+	}
+
+	pub fn resume(&mut self) {
+		self.observers.stopped = false;
+	}
+}
+
+impl<T: Coord + std::fmt::Debug> Layout<T>
+where
+	Layout<T>: Repulsion<T> + Attraction<T>,
+{
+	/// Iteration with the observer callbacks
+	pub(crate) fn observed_iteration(&mut self) {
+		// Observers are taken out so that they can borrow the layout
+		let mut observers = std::mem::take(&mut self.observers.list);
+		for phase in Phase::ALL {
+			self.notify(&mut observers, |observer, layout| {
+				observer.before(phase, layout)
+			});
+			match phase {
+				Phase::InitIteration => self.init_iteration(),
+				Phase::Attraction => self.apply_attraction(),
+				Phase::Repulsion => self.apply_repulsion(),
+				Phase::Gravity => self.apply_gravity(),
+				Phase::CustomForces => self.apply_custom_forces(),
+				Phase::ApplyForces => {
+					self.apply_forces();
+					self.apply_constraints();
+				}
+			}
+			self.notify(&mut observers, |observer, layout| {
+				observer.after(phase, layout)
+			});
+		}
+		self.observers.list = observers;
+	}
+
+	/// Calls every observer, then applies their requests
+	fn notify<F>(&mut self, observers: &mut [Box<dyn Observer<T> + Send + Sync>], mut callback: F)
+	where
+		F: FnMut(&mut dyn Observer<T>, &Self) -> Control<T>,
+	{
+		for observer in observers.iter_mut() {
+			match callback(observer.as_mut(), self) {
+				Control::Continue => {}
+				Control::Stop => self.observers.stopped = true,
+				Control::SetSettings(settings) => self.set_settings(settings),
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::sync::{Arc, Mutex};
+
+	/// Records the calls, stops after 2 iterations and doubles the speed after the first attraction
+	struct Recorder(Arc<Mutex<Vec<(u64, Phase, bool)>>>);
+
+	impl Observer<f64> for Recorder {
+		fn before(&mut self, phase: Phase, layout: &Layout<f64>) -> Control<f64> {
+			self.0
+				.lock()
+				.unwrap()
+				.push((layout.iteration_number(), phase, true));
+			if layout.iteration_number() == 2 && phase == Phase::Attraction {
+				Control::Stop
+			} else {
+				Control::Continue
+			}
+		}
+
+		fn after(&mut self, phase: Phase, layout: &Layout<f64>) -> Control<f64> {
+			self.0
+				.lock()
+				.unwrap()
+				.push((layout.iteration_number(), phase, false));
+			if layout.iteration_number() == 0 && phase == Phase::Attraction {
+				let mut settings = layout.get_settings().clone();
+				settings.speed *= 2.0;
+				Control::SetSettings(settings)
+			} else {
+				Control::Continue
+			}
+		}
+	}
+
+	#[test]
+	fn test_observer() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (0, 2), (0, 3)],
+			Nodes::Degree(4),
+			vec![0.0, 0.0, 1.0, 0.0, -1.0, 0.0, 0.0, 1.0],
+			None,
+			Settings::default(),
+		);
+		let calls = Arc::new(Mutex::new(Vec::new()));
+		layout.add_observer(Box::new(Recorder(calls.clone())));
+
+		for _ in 0..5 {
+			layout.iteration();
+		}
+		assert!(layout.is_stopped());
+		// The iteration during which the stop was requested is completed
+		assert_eq!(layout.iteration_number(), 3);
+		assert_eq!(layout.get_settings().speed, 0.02);
+
+		let calls = calls.lock().unwrap();
+		assert_eq!(calls.len(), 3 * 12);
+		assert_eq!(calls[0], (0, Phase::InitIteration, true));
+		assert_eq!(calls[11], (0, Phase::ApplyForces, false));
+		assert_eq!(calls[26], (2, Phase::Attraction, true));
+		assert_eq!(calls[35], (2, Phase::ApplyForces, false));
+
+		// Stopped iterations do nothing, and resuming continues from a consistent state
+		let points = layout.points.points.clone();
+		let speeds = layout.speeds.points.clone();
+		layout.clear_observers();
+		layout.iteration();
+		assert_eq!(layout.points.points, points);
+		layout.resume();
+		layout.iteration();
+		assert_eq!(layout.iteration_number(), 4);
+		assert_eq!(layout.old_speeds.points, speeds);
+		assert_ne!(layout.points.points, points);
+	}
+}
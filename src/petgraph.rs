@@ -0,0 +1,148 @@
+//! [petgraph](https://docs.rs/petgraph) integration
+//!
+//! Requires the `petgraph` feature.
+
+use crate::{
+	forces::{Attraction, Repulsion},
+	io::EdgeCollector,
+	layout::{Layout, Settings},
+	util::*,
+};
+
+use ::petgraph::visit::{EdgeRef, IntoEdgeReferences, IntoNodeIdentifiers};
+use std::{collections::HashMap, hash::Hash, ops::IndexMut};
+
+impl<T: Coord + std::fmt::Debug> Layout<T>
+where
+	Layout<T>: Repulsion<T> + Attraction<T>,
+{
+	/// Instantiates a randomly positioned layout from a petgraph graph (`&Graph`, `&StableGraph`, `&GraphMap`...)
+	///
+	/// `weight_fn` gives the weight of each edge. If it returns `None` for every edge, the layout is not weighted;
+	/// otherwise `None` means 1. Direction is ignored: loops are removed and parallel edges are merged, summing their weights.
+	/// Masses are `Nodes::Degree`.
+	///
+	/// Returns the layout and the original ID of each node, by layout index.
+	///
+	/// ```no_run
+	/// use forceatlas2::{Layout, Settings};
+	/// use petgraph::{graph::UnGraph, visit::EdgeRef};
+	///
+	/// let graph = UnGraph::<(), f32>::from_edges([(0, 1, 1.0), (1, 2, 3.0)]);
+	/// let weight = |edge: petgraph::graph::EdgeReference<f32>| Some(*edge.weight());
+	/// let (layout, ids) = Layout::<f32>::from_petgraph(&graph, weight, Settings::default());
+	/// ```
+	#[cfg(feature = "rand")]
+	pub fn from_petgraph<G, F>(
+		graph: G,
+		mut weight_fn: F,
+		settings: Settings<T>,
+	) -> (Self, Vec<G::NodeId>)
+	where
+		G: IntoNodeIdentifiers + IntoEdgeReferences,
+		G::NodeId: Hash + Eq,
+		F: FnMut(G::EdgeRef) -> Option<T>,
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		let ids: Vec<G::NodeId> = graph.node_identifiers().collect();
+		let indices: HashMap<G::NodeId, usize> = ids
+			.iter()
+			.enumerate()
+			.map(|(index, id)| (*id, index))
+			.collect();
+
+		let edges: Vec<(usize, usize, Option<T>)> = graph
+			.edge_references()
+			.map(|edge| {
+				(
+					indices[&edge.source()],
+					indices[&edge.target()],
+					weight_fn(edge),
+				)
+			})
+			.collect();
+		let mut collector =
+			EdgeCollector::new(edges.iter().any(|(_, _, weight)| weight.is_some()), false);
+		for (n1, n2, weight) in edges {
+			collector.push(n1, n2, weight);
+		}
+
+		(
+			Self::from_graph(
+				collector.edges,
+				Nodes::Degree(ids.len()),
+				collector.weights,
+				settings,
+			),
+			ids,
+		)
+	}
+
+	/// Stores the positions into the node weights of a graph (e.g. `Graph` or `StableGraph`)
+	///
+	/// `ids` is the original ID of each node, as returned by [`Layout::from_petgraph`].
+	///
+	/// ```no_run
+	/// # use forceatlas2::Layout;
+	/// # fn f(layout: &Layout<f32>, graph: &mut petgraph::graph::UnGraph<(f32, f32), ()>, ids: &[petgraph::graph::NodeIndex]) {
+	/// layout.write_back(graph, ids, |weight, pos| *weight = (pos[0], pos[1]));
+	/// # }
+	/// ```
+	pub fn write_back<G, I, N, F>(&self, graph: &mut G, ids: &[I], mut f: F)
+	where
+		G: IndexMut<I, Output = N>,
+		I: Copy,
+		F: FnMut(&mut N, &Position<T>),
+	{
+		assert_eq!(ids.len(), self.masses.len());
+		for (id, pos) in ids.iter().zip(self.points.iter()) {
+			f(&mut graph[*id], pos);
+		}
+	}
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod tests {
+	use super::*;
+
+	use ::petgraph::{
+		graph::{DiGraph, UnGraph},
+		graphmap::UnGraphMap,
+		stable_graph::StableUnGraph,
+	};
+
+	#[test]
+	fn test_from_petgraph() {
+		// Edges in both directions are merged, loops are removed
+		let graph =
+			DiGraph::<(), f64>::from_edges([(0, 1, 1.0), (1, 0, 2.0), (1, 2, 0.5), (2, 2, 1.0)]);
+		let (layout, ids) =
+			Layout::<f64>::from_petgraph(&graph, |edge| Some(*edge.weight()), Settings::default());
+		assert_eq!(ids.len(), 3);
+		assert_eq!(layout.edges, vec![(0, 1), (1, 2)]);
+		assert_eq!(layout.weights, Some(vec![3.0, 0.5]));
+		assert_eq!(layout.masses, vec![1.0, 2.0, 1.0]);
+
+		// Removed nodes leave holes in the indices
+		let mut graph = StableUnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+		graph.remove_node(1.into());
+		let (layout, ids) = Layout::<f64>::from_petgraph(&graph, |_| None, Settings::default());
+		assert_eq!(ids, vec![0.into(), 2.into(), 3.into()]);
+		assert_eq!(layout.edges, vec![(1, 2)]);
+		assert_eq!(layout.weights, None);
+
+		let graph = UnGraphMap::<&str, ()>::from_edges([("a", "b"), ("b", "c")]);
+		let (layout, ids) = Layout::<f64>::from_petgraph(&graph, |_| None, Settings::default());
+		assert_eq!(ids, vec!["a", "b", "c"]);
+		assert_eq!(layout.edges, vec![(0, 1), (1, 2)]);
+
+		// Write back
+		let mut graph = UnGraph::<(f64, f64), ()>::from_edges([(0, 1)]);
+		let (layout, ids) = Layout::<f64>::from_petgraph(&graph, |_| None, Settings::default());
+		layout.write_back(&mut graph, &ids, |weight, pos| *weight = (pos[0], pos[1]));
+		for (id, pos) in ids.iter().zip(layout.points.iter()) {
+			assert_eq!(graph[*id], (pos[0], pos[1]));
+		}
+	}
+}
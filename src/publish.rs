@@ -0,0 +1,137 @@
+//! Lock-free publication of the positions to concurrent readers
+//!
+//! Requires the `publish` feature.
+
+use crate::{layout::Layout, util::*};
+
+use arc_swap::ArcSwap;
+use std::sync::Arc;
+
+/// Immutable copy of the positions, published after an iteration
+pub struct PointsFrame<T: Coord> {
+	/// Number of iterations since publication was enabled
+	pub epoch: u64,
+	pub points: PointList<T>,
+}
+
+/// Handle to the latest published positions
+///
+/// Reading never blocks the layout, nor other readers. A loaded frame stays valid (and consistent)
+/// as long as it is held, even after newer frames are published.
+pub struct PointsReader<T: Coord>(Arc<ArcSwap<PointsFrame<T>>>);
+
+impl<T: Coord> Clone for PointsReader<T> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+
+impl<T: Coord> PointsReader<T> {
+	/// Latest published frame
+	pub fn load(&self) -> Arc<PointsFrame<T>> {
+		self.0.load_full()
+	}
+}
+
+pub(crate) struct Publisher<T: Coord> {
+	current: Arc<ArcSwap<PointsFrame<T>>>,
+	epoch: u64,
+	spare: SpareFrame<PointsFrame<T>>,
+}
+
+impl<T: Coord> Publisher<T> {
+	fn new(points: &PointList<T>) -> Self {
+		Self {
+			current: Arc::new(ArcSwap::from_pointee(PointsFrame {
+				epoch: 0,
+				points: points.clone(),
+			})),
+			epoch: 0,
+			spare: SpareFrame::default(),
+		}
+	}
+
+	fn publish(&mut self, points: &PointList<T>) {
+		self.epoch += 1;
+		let frame = match self.spare.take() {
+			Some(mut frame) if frame.points.points.len() == points.points.len() => {
+				frame.epoch = self.epoch;
+				frame.points.points.clone_from_slice(&points.points);
+				frame
+			}
+			_ => PointsFrame {
+				epoch: self.epoch,
+				points: points.clone(),
+			},
+		};
+		self.spare.set(self.current.swap(Arc::new(frame)));
+	}
+}
+
+impl<T: Coord> Layout<T> {
+	/// Publishes a copy of the positions after each iteration, and returns a handle to read them
+	///
+	/// The current positions are published immediately with epoch 0.
+	/// If publication is already enabled, returns a new handle to the same frames.
+	pub fn publish(&mut self) -> PointsReader<T> {
+		let publisher = self
+			.publisher
+			.get_or_insert_with(|| Publisher::new(&self.points));
+		PointsReader(publisher.current.clone())
+	}
+
+	/// Stops publishing the positions
+	///
+	/// Readers keep the last published frame.
+	pub fn stop_publishing(&mut self) {
+		self.publisher = None;
+	}
+
+	pub(crate) fn publish_points(&mut self) {
+		if let Some(publisher) = &mut self.publisher {
+			publisher.publish(&self.points);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Settings;
+
+	#[test]
+	fn test_publish() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			Nodes::Degree(2),
+			vec![-1.0, 0.0, 1.0, 0.0],
+			None,
+			Settings::default(),
+		);
+		let reader = layout.publish();
+		let first = reader.load();
+		assert_eq!(first.epoch, 0);
+
+		for _ in 0..3 {
+			layout.iteration();
+		}
+		let frame = reader.load();
+		assert_eq!(frame.epoch, 3);
+		assert_eq!(frame.points.points, layout.points.points);
+		// Held frames are not modified
+		assert_eq!(first.points.points, vec![-1.0, 0.0, 1.0, 0.0]);
+
+		// Readers are not blocked by iterations in another thread
+		let handle = std::thread::spawn(move || {
+			for _ in 0..10 {
+				layout.iteration();
+			}
+			layout
+		});
+		let epoch = reader.load().epoch;
+		assert!((3..=13).contains(&epoch));
+		let layout = handle.join().unwrap();
+		assert_eq!(reader.load().epoch, 13);
+		assert_eq!(reader.load().points.points, layout.points.points);
+	}
+}
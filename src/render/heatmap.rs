@@ -0,0 +1,306 @@
+//! Density rendering for very large layouts
+//!
+//! Instead of drawing every node and edge, points (and optionally edge segments) are binned
+//! into a 2D histogram, whose counts are then mapped to colors.
+//!
+//! Requires the `raster` feature. Binning is parallel with the `parallel` feature.
+
+use super::{raster::Image, *};
+use crate::layout::Layout;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Blue gradient, from light to dark
+pub const BLUES: &[Rgb] = &[
+	(198, 219, 239),
+	(107, 174, 214),
+	(33, 113, 181),
+	(8, 48, 107),
+];
+
+/// Mapping from bin counts to the colormap
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mapping {
+	/// Proportional to the count
+	Linear,
+	/// Proportional to the logarithm of the count
+	Log,
+	/// Proportional to the rank of the count among the non-empty bins, so every color is used as much
+	HistogramEqualization,
+}
+
+pub struct Options<'a> {
+	/// Space left around the graph, in pixels
+	pub margin: f64,
+	/// Zoom factor around the center, after fitting the graph in the image
+	pub zoom: f64,
+	/// Direction of the camera for 3D layouts (see [`project`])
+	pub camera_angle: (f64, f64),
+	/// Contribution of each pixel of an edge segment, relative to a node
+	///
+	/// If zero, edges are not binned.
+	pub edge_weight: f64,
+	pub mapping: Mapping,
+	/// Color of the empty bins
+	pub background: Rgb,
+	/// Gradient stops, from the lowest to the highest density
+	pub colormap: &'a [Rgb],
+}
+
+impl<'a> Default for Options<'a> {
+	fn default() -> Self {
+		Self {
+			margin: 0.0,
+			zoom: 1.0,
+			camera_angle: (0.0, 0.0),
+			edge_weight: 0.0,
+			mapping: Mapping::HistogramEqualization,
+			background: (255, 255, 255),
+			colormap: BLUES,
+		}
+	}
+}
+
+/// 2D histogram, one bin per pixel
+pub struct Histogram {
+	pub width: usize,
+	pub height: usize,
+	/// Counts, row by row
+	pub counts: Vec<f64>,
+}
+
+impl Histogram {
+	/// Bins the layout in a histogram of `size` pixels
+	pub fn new<T: Coord>(layout: &Layout<T>, options: &Options, size: (usize, usize)) -> Self {
+		let nb_bins = size.0 * size.1;
+		let fsize = (size.0 as f64, size.1 as f64);
+		let points = project(&layout.points, options.camera_angle);
+		let viewport = Viewport::fit(&points, fsize, options.margin).zoom(options.zoom, fsize);
+
+		let bin_points = |counts: &mut [f64], chunk: &[f64]| {
+			for pos in chunk.chunks_exact(2) {
+				let (x, y) = viewport.project(pos);
+				if let Some(bin) = bin(size, x, y) {
+					counts[bin] += 1.0;
+				}
+			}
+		};
+		let bin_edges = |counts: &mut [f64], chunk: &[Edge]| {
+			for (n1, n2) in chunk {
+				let p1 = viewport.project(points.get(*n1));
+				let p2 = viewport.project(points.get(*n2));
+				if !(p1.0.is_finite() && p1.1.is_finite() && p2.0.is_finite() && p2.1.is_finite()) {
+					continue;
+				}
+				// One sample per pixel along the major axis, limited to the image diagonal
+				let steps = (p2.0 - p1.0)
+					.abs()
+					.max((p2.1 - p1.1).abs())
+					.min(fsize.0 + fsize.1)
+					.ceil()
+					.max(1.0) as usize;
+				for step in 0..=steps {
+					let t = step as f64 / steps as f64;
+					if let Some(bin) = bin(size, p1.0 + (p2.0 - p1.0) * t, p1.1 + (p2.1 - p1.1) * t)
+					{
+						counts[bin] += options.edge_weight;
+					}
+				}
+			}
+		};
+		let with_edges = options.edge_weight != 0.0;
+
+		#[cfg(feature = "parallel")]
+		let counts = {
+			let mut counts = par_bin(&points.points, 2, nb_bins, bin_points)
+				.unwrap_or_else(|| vec![0.0; nb_bins]);
+			if with_edges {
+				if let Some(edge_counts) = par_bin(&layout.edges, 1, nb_bins, bin_edges) {
+					counts
+						.iter_mut()
+						.zip(edge_counts)
+						.for_each(|(count, edge_count)| *count += edge_count);
+				}
+			}
+			counts
+		};
+
+		#[cfg(not(feature = "parallel"))]
+		let counts = {
+			let mut counts = vec![0.0; nb_bins];
+			bin_points(&mut counts, &points.points);
+			if with_edges {
+				bin_edges(&mut counts, &layout.edges);
+			}
+			counts
+		};
+
+		Self {
+			width: size.0,
+			height: size.1,
+			counts,
+		}
+	}
+
+	/// Maps the counts to colors
+	pub fn to_image(&self, options: &Options) -> Image {
+		let levels: Vec<f64> = match options.mapping {
+			Mapping::Linear => {
+				let max = self.counts.iter().copied().fold(0.0, f64::max);
+				self.counts.iter().map(|count| count / max).collect()
+			}
+			Mapping::Log => {
+				let max = self.counts.iter().copied().fold(0.0, f64::max).ln_1p();
+				self.counts
+					.iter()
+					.map(|count| count.ln_1p() / max)
+					.collect()
+			}
+			Mapping::HistogramEqualization => {
+				let mut sorted: Vec<f64> = self
+					.counts
+					.iter()
+					.copied()
+					.filter(|count| *count > 0.0)
+					.collect();
+				sorted.sort_unstable_by(f64::total_cmp);
+				let nb = sorted.len() as f64;
+				self.counts
+					.iter()
+					.map(|count| sorted.partition_point(|c| c <= count) as f64 / nb)
+					.collect()
+			}
+		};
+
+		let mut image = Image::new(self.width, self.height);
+		for ((count, level), pixel) in self
+			.counts
+			.iter()
+			.zip(levels)
+			.zip(image.pixels.chunks_exact_mut(3))
+		{
+			let color = if *count > 0.0 {
+				gradient(options.colormap, level)
+			} else {
+				options.background
+			};
+			pixel.copy_from_slice(&[color.0, color.1, color.2]);
+		}
+		image
+	}
+}
+
+/// Bins `items` (by groups of `stride`) with one histogram per thread, then sums them
+///
+/// Returns `None` if there are no items.
+#[cfg(feature = "parallel")]
+fn par_bin<I: Sync>(
+	items: &[I],
+	stride: usize,
+	nb_bins: usize,
+	bin_items: impl Fn(&mut [f64], &[I]) + Sync,
+) -> Option<Vec<f64>> {
+	let part = (items.len() / stride)
+		.div_ceil(rayon::current_num_threads())
+		.max(1)
+		* stride;
+	items
+		.par_chunks(part)
+		.map(|part| {
+			let mut counts = vec![0.0; nb_bins];
+			bin_items(&mut counts, part);
+			counts
+		})
+		.reduce_with(|mut a, b| {
+			a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+			a
+		})
+}
+
+/// Renders the density of the layout to a new image of `size` pixels
+pub fn render<T: Coord>(layout: &Layout<T>, options: &Options, size: (usize, usize)) -> Image {
+	Histogram::new(layout, options, size).to_image(options)
+}
+
+/// Index of the bin containing a point, the bottom and right borders being included in the last bins
+fn bin(size: (usize, usize), x: f64, y: f64) -> Option<usize> {
+	if x >= 0.0 && y >= 0.0 && x <= size.0 as f64 && y <= size.1 as f64 && size.0 * size.1 != 0 {
+		Some((y as usize).min(size.1 - 1) * size.0 + (x as usize).min(size.0 - 1))
+	} else {
+		None
+	}
+}
+
+/// Color at `level` (between 0 and 1) of a gradient
+fn gradient(colormap: &[Rgb], level: f64) -> Rgb {
+	let Some(last) = colormap.len().checked_sub(1) else {
+		return (0, 0, 0);
+	};
+	let position = level.clamp(0.0, 1.0) * last as f64;
+	let i = (position.floor() as usize).min(last.saturating_sub(1));
+	let Some(next) = colormap.get(i + 1) else {
+		return colormap[i];
+	};
+	let t = position - i as f64;
+	let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+	let color = colormap[i];
+	(
+		mix(color.0, next.0),
+		mix(color.1, next.1),
+		mix(color.2, next.2),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Nodes, Settings};
+
+	#[test]
+	fn test_heatmap() {
+		// Three points in the top-left corner, one in each other corner (on the borders)
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 3)],
+			Nodes::Degree(6),
+			vec![
+				0.0, 0.0, 0.1, 0.1, 0.0, 0.1, 10.0, 0.0, 0.0, 10.0, 10.0, 10.0,
+			],
+			None,
+			Settings::default(),
+		);
+		let options = Options {
+			mapping: Mapping::Linear,
+			colormap: &[(0, 0, 0), (200, 200, 200)],
+			..Default::default()
+		};
+		let histogram = Histogram::new(&layout, &options, (4, 4));
+		assert_eq!(histogram.counts.iter().sum::<f64>(), 6.0);
+		assert_eq!(histogram.counts[0], 3.0);
+		assert_eq!(histogram.counts[15], 1.0);
+
+		let image = histogram.to_image(&options);
+		assert_eq!(&image.pixels[..3], &[200, 200, 200]);
+		assert_eq!(&image.pixels[3..6], &[255, 255, 255]);
+		assert_eq!(&image.pixels[15 * 3..], &[67, 67, 67]);
+
+		let image = histogram.to_image(&Options {
+			mapping: Mapping::HistogramEqualization,
+			..options
+		});
+		// 3 bins of count 1, 1 of count 3
+		assert_eq!(&image.pixels[..3], &[200, 200, 200]);
+		assert_eq!(&image.pixels[15 * 3..], &[150, 150, 150]);
+
+		let histogram = Histogram::new(
+			&layout,
+			&Options {
+				edge_weight: 0.5,
+				..options
+			},
+			(4, 4),
+		);
+		// Edge from (0, 0) to (10, 0) on the first row
+		assert_eq!(histogram.counts[1], 0.5);
+	}
+}
@@ -0,0 +1,186 @@
+//! Drawing layouts
+
+#[cfg(feature = "raster")]
+pub mod heatmap;
+#[cfg(feature = "raster")]
+pub mod raster;
+pub mod svg;
+#[cfg(feature = "raster")]
+pub mod tiles;
+
+use crate::util::*;
+
+/// RGB color
+pub type Rgb = (u8, u8, u8);
+
+/// Maps the first two dimensions of the layout to image coordinates, preserving the aspect ratio
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+	pub scale: f64,
+	pub offset: (f64, f64),
+}
+
+impl Viewport {
+	/// Fits all the points in an image of `size` pixels, leaving `margin` pixels on every side
+	pub fn fit<T: Coord>(points: &PointList<T>, size: (f64, f64), margin: f64) -> Self {
+		let mut min = (f64::INFINITY, f64::INFINITY);
+		let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+		for pos in points.iter() {
+			let (x, y) = coords_2d(pos);
+			min = (min.0.min(x), min.1.min(y));
+			max = (max.0.max(x), max.1.max(y));
+		}
+		if min.0 > max.0 {
+			// No points
+			return Self {
+				scale: 1.0,
+				offset: (size.0 / 2.0, size.1 / 2.0),
+			};
+		}
+
+		let extent = (max.0 - min.0, max.1 - min.1);
+		let available = (
+			(size.0 - 2.0 * margin).max(1.0),
+			(size.1 - 2.0 * margin).max(1.0),
+		);
+		let scale = match (extent.0 > 0.0, extent.1 > 0.0) {
+			(true, true) => (available.0 / extent.0).min(available.1 / extent.1),
+			(true, false) => available.0 / extent.0,
+			(false, true) => available.1 / extent.1,
+			(false, false) => 1.0,
+		};
+		let center = ((min.0 + max.0) / 2.0, (min.1 + max.1) / 2.0);
+		Self {
+			scale,
+			offset: (
+				size.0 / 2.0 - center.0 * scale,
+				size.1 / 2.0 - center.1 * scale,
+			),
+		}
+	}
+
+	/// Zooms around the center of an image of `size` pixels
+	pub fn zoom(self, factor: f64, size: (f64, f64)) -> Self {
+		let center = (size.0 / 2.0, size.1 / 2.0);
+		Self {
+			scale: self.scale * factor,
+			offset: (
+				center.0 + (self.offset.0 - center.0) * factor,
+				center.1 + (self.offset.1 - center.1) * factor,
+			),
+		}
+	}
+
+	/// Image coordinates of a point
+	pub fn project<T: Coord>(&self, pos: &Position<T>) -> (f64, f64) {
+		let (x, y) = coords_2d(pos);
+		(
+			x * self.scale + self.offset.0,
+			y * self.scale + self.offset.1,
+		)
+	}
+}
+
+/// First two coordinates, missing ones being 0
+fn coords_2d<T: Coord>(pos: &Position<T>) -> (f64, f64) {
+	(
+		pos.first().map_or(0.0, |x| x.to_f64().unwrap()),
+		pos.get(1).map_or(0.0, |y| y.to_f64().unwrap()),
+	)
+}
+
+/// Projects the points on a plane
+///
+/// 3D layouts are seen by a perspective camera looking at their centroid from the direction `camera_angle`
+/// (`(0, 0)` looks along the x axis, with z up).
+/// Other layouts are projected on their first two dimensions.
+pub fn project<T: Coord>(points: &PointList<T>, camera_angle: (f64, f64)) -> PointList<f64> {
+	if points.dimensions != 3 {
+		return PointList {
+			dimensions: 2,
+			points: points
+				.iter()
+				.flat_map(|pos| {
+					let (x, y) = coords_2d(pos);
+					[x, y]
+				})
+				.collect(),
+		};
+	}
+
+	type Vec3 = [f64; 3];
+	fn sub(a: Vec3, b: Vec3) -> Vec3 {
+		[a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+	}
+	fn dot(a: Vec3, b: Vec3) -> f64 {
+		a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+	}
+	fn cross(a: Vec3, b: Vec3) -> Vec3 {
+		[
+			a[1] * b[2] - a[2] * b[1],
+			a[2] * b[0] - a[0] * b[2],
+			a[0] * b[1] - a[1] * b[0],
+		]
+	}
+	fn normalize(a: Vec3) -> Vec3 {
+		let norm = dot(a, a).sqrt();
+		[a[0] / norm, a[1] / norm, a[2] / norm]
+	}
+	let to_vec3 = |pos: &Position<T>| -> Vec3 {
+		[
+			pos[0].to_f64().unwrap(),
+			pos[1].to_f64().unwrap(),
+			pos[2].to_f64().unwrap(),
+		]
+	};
+
+	let mut centroid = [0.0; 3];
+	let mut nb_points = 0;
+	for pos in points.iter() {
+		let pos = to_vec3(pos);
+		centroid = [
+			centroid[0] + pos[0],
+			centroid[1] + pos[1],
+			centroid[2] + pos[2],
+		];
+		nb_points += 1;
+	}
+	if nb_points != 0 {
+		centroid = centroid.map(|c| c / nb_points as f64);
+	}
+	let radius = points
+		.iter()
+		.map(|pos| {
+			let d = sub(to_vec3(pos), centroid);
+			dot(d, d).sqrt()
+		})
+		.fold(0.0, f64::max);
+	// Far enough to keep every point in front of the camera
+	let distance = 3.0 * if radius > 0.0 { radius } else { 1.0 };
+
+	let eye = [
+		centroid[0] + distance * camera_angle.1.cos(),
+		centroid[1] + distance * camera_angle.0.cos() * camera_angle.1.sin(),
+		centroid[2] + distance * camera_angle.0.sin() * camera_angle.1.sin(),
+	];
+	let forward = normalize(sub(centroid, eye));
+	let mut right = cross(forward, [0.0, 0.0, 1.0]);
+	if dot(right, right) < 1e-12 {
+		// Looking along z
+		right = cross(forward, [0.0, 1.0, 0.0]);
+	}
+	let right = normalize(right);
+	let up = cross(right, forward);
+
+	PointList {
+		dimensions: 2,
+		points: points
+			.iter()
+			.flat_map(|pos| {
+				let d = sub(to_vec3(pos), eye);
+				let depth = dot(d, forward);
+				[dot(d, right) / depth, -dot(d, up) / depth]
+			})
+			.collect(),
+	}
+}
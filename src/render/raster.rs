@@ -0,0 +1,341 @@
+//! Anti-aliased raster renderer, with PNG output
+//!
+//! Requires the `raster` feature. Works headlessly, e.g. for regression images:
+//!
+//! ```no_run
+//! use forceatlas2::{render::raster, Layout};
+//!
+//! # fn f(layout: &Layout<f32>) {
+//! let image = raster::render(layout, &raster::Options::default(), (800, 600));
+//! let file = std::fs::File::create("layout.png").unwrap();
+//! image.write_png(file).unwrap();
+//! # }
+//! ```
+
+use super::*;
+use crate::{layout::Layout, Frame};
+
+use std::io::Write;
+
+/// Mutable view on an RGB buffer (3 bytes per pixel)
+pub struct Canvas<'a> {
+	pixels: &'a mut [u8],
+	width: usize,
+	height: usize,
+	/// Bytes per row
+	rowstride: usize,
+}
+
+impl<'a> Canvas<'a> {
+	pub fn new(pixels: &'a mut [u8], width: usize, height: usize, rowstride: usize) -> Self {
+		assert!(rowstride >= width * 3);
+		assert!(height == 0 || pixels.len() >= (height - 1) * rowstride + width * 3);
+		Self {
+			pixels,
+			width,
+			height,
+			rowstride,
+		}
+	}
+
+	pub fn width(&self) -> usize {
+		self.width
+	}
+
+	pub fn height(&self) -> usize {
+		self.height
+	}
+
+	pub fn fill(&mut self, color: Rgb) {
+		for y in 0..self.height {
+			let row = &mut self.pixels[y * self.rowstride..y * self.rowstride + self.width * 3];
+			for pixel in row.chunks_exact_mut(3) {
+				pixel.copy_from_slice(&[color.0, color.1, color.2]);
+			}
+		}
+	}
+
+	/// Blends a color on a pixel, with opacity `alpha` (between 0 and 1)
+	///
+	/// Pixels outside the canvas are ignored.
+	pub fn blend(&mut self, x: i64, y: i64, color: Rgb, alpha: f64) {
+		if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 || alpha <= 0.0 {
+			return;
+		}
+		let a = (alpha.min(1.0) * 256.0) as u16;
+		let offset = y as usize * self.rowstride + x as usize * 3;
+		for (dst, src) in self.pixels[offset..offset + 3]
+			.iter_mut()
+			.zip([color.0, color.1, color.2])
+		{
+			*dst = ((src as u16 * a + *dst as u16 * (256 - a)) >> 8) as u8;
+		}
+	}
+
+	/// Draws an anti-aliased line (Xiaolin Wu's algorithm)
+	///
+	/// Coordinates are in pixels, the center of pixel `(0, 0)` being `(0.5, 0.5)`.
+	pub fn draw_line(&mut self, p1: (f64, f64), p2: (f64, f64), color: Rgb, alpha: f64) {
+		if !(p1.0.is_finite() && p1.1.is_finite() && p2.0.is_finite() && p2.1.is_finite()) {
+			return;
+		}
+		let (mut x0, mut y0, mut x1, mut y1) = (p1.0 - 0.5, p1.1 - 0.5, p2.0 - 0.5, p2.1 - 0.5);
+		let steep = (y1 - y0).abs() > (x1 - x0).abs();
+		if steep {
+			std::mem::swap(&mut x0, &mut y0);
+			std::mem::swap(&mut x1, &mut y1);
+		}
+		if x0 > x1 {
+			std::mem::swap(&mut x0, &mut x1);
+			std::mem::swap(&mut y0, &mut y1);
+		}
+		let gradient = if x1 > x0 { (y1 - y0) / (x1 - x0) } else { 0.0 };
+		// Only iterate over the visible part
+		let limit = if steep { self.height } else { self.width } as f64;
+		let start = x0.round().max(0.0);
+		let end = x1.round().min(limit - 1.0);
+		if start > end {
+			return;
+		}
+		for x in start as i64..=end as i64 {
+			let y = y0 + gradient * (x as f64 - x0);
+			let y_floor = y.floor();
+			let frac = y - y_floor;
+			let y_floor = y_floor as i64;
+			if steep {
+				self.blend(y_floor, x, color, alpha * (1.0 - frac));
+				self.blend(y_floor + 1, x, color, alpha * frac);
+			} else {
+				self.blend(x, y_floor, color, alpha * (1.0 - frac));
+				self.blend(x, y_floor + 1, color, alpha * frac);
+			}
+		}
+	}
+
+	/// Draws an anti-aliased disc
+	pub fn draw_disc(&mut self, center: (f64, f64), radius: f64, color: Rgb, alpha: f64) {
+		if !(center.0.is_finite() && center.1.is_finite()) || radius <= 0.0 {
+			return;
+		}
+		let x_min = (center.0 - radius - 1.0).floor().max(0.0) as i64;
+		let x_max = (center.0 + radius + 1.0)
+			.ceil()
+			.min(self.width as f64 - 1.0) as i64;
+		let y_min = (center.1 - radius - 1.0).floor().max(0.0) as i64;
+		let y_max = (center.1 + radius + 1.0)
+			.ceil()
+			.min(self.height as f64 - 1.0) as i64;
+		for y in y_min..=y_max {
+			let dy = y as f64 + 0.5 - center.1;
+			for x in x_min..=x_max {
+				let dx = x as f64 + 0.5 - center.0;
+				let coverage = (radius + 0.5 - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+				if coverage > 0.0 {
+					self.blend(x, y, color, alpha * coverage);
+				}
+			}
+		}
+	}
+}
+
+/// Owned RGB image
+pub struct Image {
+	pub width: usize,
+	pub height: usize,
+	/// RGB pixels, row by row
+	pub pixels: Vec<u8>,
+}
+
+impl Image {
+	pub fn new(width: usize, height: usize) -> Self {
+		Self {
+			width,
+			height,
+			pixels: vec![0; width * height * 3],
+		}
+	}
+
+	pub fn canvas(&mut self) -> Canvas<'_> {
+		Canvas::new(&mut self.pixels, self.width, self.height, self.width * 3)
+	}
+
+	pub fn write_png<W: Write>(&self, writer: W) -> Result<(), png::EncodingError> {
+		let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+		encoder.set_color(png::ColorType::Rgb);
+		encoder.set_depth(png::BitDepth::Eight);
+		let mut writer = encoder.write_header()?;
+		writer.write_image_data(&self.pixels)?;
+		writer.finish()
+	}
+}
+
+pub struct Options<'a> {
+	/// Space left around the graph, in pixels
+	pub margin: f64,
+	/// Zoom factor around the center, after fitting the graph in the image
+	pub zoom: f64,
+	/// Direction of the camera for 3D layouts (see [`project`])
+	pub camera_angle: (f64, f64),
+	/// If `None`, the canvas is not cleared
+	pub background: Option<Rgb>,
+	pub draw_edges: bool,
+	pub edge_color: Rgb,
+	/// Opacity of the edges, between 0 and 1
+	pub edge_alpha: f64,
+	pub draw_nodes: bool,
+	pub node_color: Rgb,
+	/// Color of each node, overriding `node_color`
+	pub node_colors: Option<&'a [Rgb]>,
+	/// Radius of the nodes (of mass 1 if `node_size_by_mass`), in pixels
+	pub node_radius: f64,
+	/// Make the node radius proportional to the square root of the mass
+	pub node_size_by_mass: bool,
+}
+
+impl<'a> Default for Options<'a> {
+	fn default() -> Self {
+		Self {
+			margin: 20.0,
+			zoom: 1.0,
+			camera_angle: (0.0, 0.0),
+			background: Some((255, 255, 255)),
+			draw_edges: true,
+			edge_color: (0, 0, 0),
+			edge_alpha: 0.1,
+			draw_nodes: true,
+			node_color: (255, 0, 0),
+			node_colors: None,
+			node_radius: 2.0,
+			node_size_by_mass: false,
+		}
+	}
+}
+
+/// Draws the layout on a canvas, fitting it in the canvas
+pub fn draw<T: Coord>(layout: &Layout<T>, options: &Options, canvas: &mut Canvas) {
+	draw_graph(
+		&layout.points,
+		&layout.edges,
+		&layout.masses,
+		options,
+		canvas,
+	);
+}
+
+/// Draws a frame published by a [`LayoutRunner`](crate::LayoutRunner), fitting it in the canvas
+pub fn draw_frame<T: Coord>(frame: &Frame<T>, options: &Options, canvas: &mut Canvas) {
+	draw_graph(&frame.points, &frame.edges, &frame.masses, options, canvas);
+}
+
+fn draw_graph<T: Coord>(
+	points: &PointList<T>,
+	edges: &[Edge],
+	masses: &[T],
+	options: &Options,
+	canvas: &mut Canvas,
+) {
+	if let Some(background) = options.background {
+		canvas.fill(background);
+	}
+
+	let size = (canvas.width as f64, canvas.height as f64);
+	let points = project(points, options.camera_angle);
+	let viewport = Viewport::fit(&points, size, options.margin).zoom(options.zoom, size);
+
+	if options.draw_edges {
+		for (n1, n2) in edges.iter() {
+			canvas.draw_line(
+				viewport.project(points.get(*n1)),
+				viewport.project(points.get(*n2)),
+				options.edge_color,
+				options.edge_alpha,
+			);
+		}
+	}
+
+	if options.draw_nodes {
+		for (node, pos) in points.iter().enumerate() {
+			let radius = if options.node_size_by_mass {
+				options.node_radius * crate::io::node_size(&masses[node])
+			} else {
+				options.node_radius
+			};
+			canvas.draw_disc(
+				viewport.project(pos),
+				radius,
+				options
+					.node_colors
+					.map_or(options.node_color, |node_colors| node_colors[node]),
+				1.0,
+			);
+		}
+	}
+}
+
+/// Renders the layout to a new image of `size` pixels
+pub fn render<T: Coord>(layout: &Layout<T>, options: &Options, size: (usize, usize)) -> Image {
+	let mut image = Image::new(size.0, size.1);
+	draw(layout, options, &mut image.canvas());
+	image
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Nodes, Settings};
+
+	#[test]
+	fn test_canvas() {
+		let mut image = Image::new(10, 10);
+		let mut canvas = image.canvas();
+		canvas.fill((255, 255, 255));
+
+		// Horizontal line on pixel centers: full coverage on row 2
+		canvas.draw_line((0.5, 2.5), (9.5, 2.5), (0, 0, 0), 1.0);
+		// Half coverage on rows 5 and 6
+		canvas.draw_line((0.5, 6.0), (9.5, 6.0), (0, 0, 0), 1.0);
+		// Out of bounds
+		canvas.draw_line((-100.0, -100.0), (-10.0, 1000.0), (0, 0, 0), 1.0);
+		canvas.draw_disc((5.0, 9.0), 0.0, (0, 0, 0), 1.0);
+
+		let pixel = |x: usize, y: usize| image.pixels[(y * 10 + x) * 3];
+		assert_eq!(pixel(4, 2), 0);
+		assert_eq!(pixel(4, 1), 255);
+		assert_eq!(pixel(4, 3), 255);
+		assert_eq!(pixel(4, 5), 127);
+		assert_eq!(pixel(4, 6), 127);
+		assert_eq!(pixel(5, 9), 255);
+
+		let mut canvas = image.canvas();
+		canvas.draw_disc((5.0, 5.0), 2.0, (255, 0, 0), 1.0);
+		assert_eq!(&image.pixels[(5 * 10 + 5) * 3..][..3], &[255, 0, 0]);
+		// Anti-aliased border
+		let border = image.pixels[(5 * 10 + 6) * 3 + 1];
+		assert!(border > 0 && border < 255);
+	}
+
+	#[test]
+	fn test_render() {
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (1, 2)],
+			Nodes::Degree(3),
+			vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+			None,
+			Settings {
+				dimensions: 3,
+				..Default::default()
+			},
+		);
+		let image = render(&layout, &Options::default(), (64, 48));
+		assert_eq!(image.pixels.len(), 64 * 48 * 3);
+		// Some red node pixels
+		assert!(image
+			.pixels
+			.chunks_exact(3)
+			.any(|pixel| pixel == [255, 0, 0]));
+
+		let mut png = Vec::new();
+		image.write_png(&mut png).unwrap();
+		assert_eq!(&png[1..4], b"PNG");
+	}
+}
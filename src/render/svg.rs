@@ -0,0 +1,248 @@
+//! SVG renderer
+//!
+//! ```no_run
+//! use forceatlas2::{render::svg, Layout};
+//!
+//! # fn f(layout: &Layout<f32>, labels: &[String]) {
+//! let file = std::fs::File::create("layout.svg").unwrap();
+//! let mut options = svg::Options::default();
+//! options.labels = Some(labels);
+//! svg::write(layout, &options, std::io::BufWriter::new(file)).unwrap();
+//! # }
+//! ```
+
+use super::*;
+use crate::layout::Layout;
+
+use std::io::Write;
+
+pub struct Options<'a> {
+	/// Image size in pixels
+	pub size: (f64, f64),
+	/// Space left around the graph, in pixels
+	pub margin: f64,
+	/// If `None`, the background is transparent
+	pub background: Option<Rgb>,
+	/// Radius of a node of mass 1, in pixels
+	///
+	/// The radius is proportional to the square root of the mass.
+	pub node_radius: f64,
+	pub node_color: Rgb,
+	/// Color of each node, overriding `node_color`
+	pub node_colors: Option<&'a [Rgb]>,
+	/// Text next to each node (empty labels are not drawn)
+	pub labels: Option<&'a [String]>,
+	/// Font size of the labels, in pixels
+	pub label_size: f64,
+	pub edge_color: Rgb,
+	/// Stroke width of the edges, in pixels
+	pub edge_width: f64,
+	/// Opacity of the edges
+	///
+	/// If the layout is weighted, the opacity is proportional to the weight, and is `edge_opacity` for the heaviest edge.
+	pub edge_opacity: f64,
+	/// Nodes to draw, in order (the last one is on top)
+	///
+	/// May be a subset of the nodes, e.g. the heaviest ones in a large graph: edges are only drawn between listed nodes.
+	/// If `None`, every node is drawn, by index.
+	pub order: Option<&'a [usize]>,
+}
+
+impl<'a> Default for Options<'a> {
+	fn default() -> Self {
+		Self {
+			size: (1000.0, 1000.0),
+			margin: 20.0,
+			background: Some((255, 255, 255)),
+			node_radius: 3.0,
+			node_color: (0, 0, 0),
+			node_colors: None,
+			labels: None,
+			label_size: 12.0,
+			edge_color: (128, 128, 128),
+			edge_width: 1.0,
+			edge_opacity: 0.5,
+			order: None,
+		}
+	}
+}
+
+/// Writes an SVG document, fitting the first two dimensions of the layout in the image
+pub fn write<T: Coord, W: Write>(
+	layout: &Layout<T>,
+	options: &Options,
+	mut writer: W,
+) -> std::io::Result<()> {
+	let viewport = Viewport::fit(&layout.points, options.size, options.margin);
+	let nb_nodes = layout.masses.len();
+	let order: Vec<usize> = options
+		.order
+		.map_or_else(|| (0..nb_nodes).collect(), <[usize]>::to_vec);
+
+	writeln!(
+		writer,
+		r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+		options.size.0, options.size.1
+	)?;
+	if let Some(background) = options.background {
+		writeln!(
+			writer,
+			r#"<rect width="100%" height="100%" fill="{}"/>"#,
+			hex(background)
+		)?;
+	}
+
+	// Edges
+	let drawn = options.order.map(|order| {
+		let mut drawn = vec![false; nb_nodes];
+		for node in order {
+			drawn[*node] = true;
+		}
+		drawn
+	});
+	let max_weight = layout.weights.as_ref().map(|weights| {
+		weights
+			.iter()
+			.map(|weight| weight.to_f64().unwrap())
+			.fold(0.0, f64::max)
+	});
+	writeln!(
+		writer,
+		r#"<g stroke="{}" stroke-width="{}" stroke-opacity="{}" stroke-linecap="round">"#,
+		hex(options.edge_color),
+		options.edge_width,
+		options.edge_opacity
+	)?;
+	for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
+		if let Some(drawn) = &drawn {
+			if !drawn[*n1] || !drawn[*n2] {
+				continue;
+			}
+		}
+		let p1 = viewport.project(layout.points.get(*n1));
+		let p2 = viewport.project(layout.points.get(*n2));
+		write!(
+			writer,
+			r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}""#,
+			p1.0, p1.1, p2.0, p2.1
+		)?;
+		if let (Some(weights), Some(max_weight)) = (&layout.weights, max_weight) {
+			if max_weight > 0.0 {
+				write!(
+					writer,
+					r#" stroke-opacity="{:.3}""#,
+					options.edge_opacity * (weights[edge].to_f64().unwrap() / max_weight).max(0.0)
+				)?;
+			}
+		}
+		writeln!(writer, "/>")?;
+	}
+	writeln!(writer, "</g>")?;
+
+	// Nodes
+	writeln!(writer, r#"<g fill="{}">"#, hex(options.node_color))?;
+	for node in order.iter().copied() {
+		let (x, y) = viewport.project(layout.points.get(node));
+		let radius = options.node_radius * crate::io::node_size(&layout.masses[node]);
+		write!(
+			writer,
+			r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}""#,
+			x, y, radius
+		)?;
+		if let Some(node_colors) = options.node_colors {
+			write!(writer, r#" fill="{}""#, hex(node_colors[node]))?;
+		}
+		writeln!(writer, "/>")?;
+	}
+	writeln!(writer, "</g>")?;
+
+	// Labels
+	if let Some(labels) = options.labels {
+		writeln!(
+			writer,
+			r#"<g font-family="sans-serif" font-size="{}" dominant-baseline="middle">"#,
+			options.label_size
+		)?;
+		for node in order.iter().copied() {
+			if labels[node].is_empty() {
+				continue;
+			}
+			let (x, y) = viewport.project(layout.points.get(node));
+			let radius = options.node_radius * crate::io::node_size(&layout.masses[node]);
+			writeln!(
+				writer,
+				r#"<text x="{:.2}" y="{:.2}">{}</text>"#,
+				x + radius + options.label_size / 4.0,
+				y,
+				escape(&labels[node])
+			)?;
+		}
+		writeln!(writer, "</g>")?;
+	}
+
+	writeln!(writer, "</svg>")
+}
+
+fn hex(color: Rgb) -> String {
+	format!("#{:02x}{:02x}{:02x}", color.0, color.1, color.2)
+}
+
+fn escape(s: &str) -> String {
+	s.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Nodes, Settings};
+
+	#[test]
+	fn test_write() {
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (1, 2)],
+			Nodes::Mass(vec![1.0, 4.0, 1.0]),
+			vec![-1.0, 0.0, 0.0, 0.0, 1.0, 1.0],
+			Some(vec![1.0, 2.0]),
+			Settings::default(),
+		);
+		let labels = ["a".to_string(), String::new(), "<c>".to_string()];
+		let mut output = Vec::new();
+		write(
+			&layout,
+			&Options {
+				size: (120.0, 60.0),
+				margin: 10.0,
+				background: None,
+				labels: Some(&labels),
+				order: Some(&[2, 1]),
+				..Default::default()
+			},
+			&mut output,
+		)
+		.unwrap();
+		let output = String::from_utf8(output).unwrap();
+
+		// Scale is 40 (limited by height), centered on (0, 0.5)
+		assert_eq!(
+			Viewport::fit(&layout.points, (120.0, 60.0), 10.0),
+			Viewport {
+				scale: 40.0,
+				offset: (60.0, 10.0)
+			}
+		);
+		assert!(!output.contains("<rect"));
+		// Only the edge between listed nodes, the heaviest one having `edge_opacity`
+		assert_eq!(output.matches("<line").count(), 1);
+		assert!(output.contains(
+			r#"<line x1="60.00" y1="10.00" x2="100.00" y2="50.00" stroke-opacity="0.500"/>"#
+		));
+		assert!(output.contains(
+			r#"<circle cx="100.00" cy="50.00" r="3.00"/>
+<circle cx="60.00" cy="10.00" r="6.00"/>"#
+		));
+		assert!(output.contains(r#"<text x="106.00" y="50.00">&lt;c&gt;</text>"#));
+		assert_eq!(output.matches("<text").count(), 1);
+	}
+}
@@ -0,0 +1,318 @@
+//! Deep-zoom tile pyramid export
+//!
+//! Requires the `raster` feature.
+//!
+//! Writes `{z}/{x}/{y}.png` tiles (XYZ scheme: `y` goes down) and a `metadata.json` file.
+//! At zoom `z`, the whole graph fits in `2^z` × `2^z` tiles. Only non-empty tiles are written.
+//!
+//! Level of detail: low zoom levels only show the heaviest nodes, and the deepest level shows all of them.
+//!
+//! With Leaflet:
+//!
+//! ```js
+//! const map = L.map("map", { crs: L.CRS.Simple });
+//! L.tileLayer("tiles/{z}/{x}/{y}.png", { tileSize: 256, maxZoom: 6, noWrap: true }).addTo(map);
+//! map.setView(map.unproject([128, 128], 0), 0);
+//! ```
+
+use super::{
+	raster::{self, Image},
+	*,
+};
+use crate::layout::Layout;
+
+use std::{collections::HashMap, io::Write, path::Path};
+
+pub struct Options<'a> {
+	/// Tile width and height, in pixels
+	pub tile_size: usize,
+	/// Deepest zoom level
+	pub max_zoom: u32,
+	/// Style
+	///
+	/// `margin` is in pixels at zoom 0, and `zoom` is ignored.
+	pub raster: raster::Options<'a>,
+	/// Number of nodes (the heaviest ones) drawn at zoom 0, multiplied by 4 at each level
+	///
+	/// All nodes are drawn at `max_zoom`.
+	pub nodes_at_zoom_0: usize,
+	/// Zoom level from which edges are drawn (only between drawn nodes)
+	pub edges_min_zoom: u32,
+}
+
+impl<'a> Default for Options<'a> {
+	fn default() -> Self {
+		Self {
+			tile_size: 256,
+			max_zoom: 6,
+			raster: raster::Options {
+				margin: 8.0,
+				..Default::default()
+			},
+			nodes_at_zoom_0: 256,
+			edges_min_zoom: 0,
+		}
+	}
+}
+
+#[derive(Default)]
+struct Tile {
+	edges: Vec<usize>,
+	nodes: Vec<usize>,
+}
+
+/// Writes the tile pyramid in `dir`
+pub fn write<T: Coord>(layout: &Layout<T>, options: &Options, dir: &Path) -> std::io::Result<()> {
+	let style = &options.raster;
+	let tile_size = options.tile_size as f64;
+	let nb_nodes = layout.masses.len();
+	let points = project(&layout.points, style.camera_angle);
+	let base = Viewport::fit(&points, (tile_size, tile_size), style.margin);
+
+	// Heaviest first
+	let mut order: Vec<usize> = (0..nb_nodes).collect();
+	order.sort_by(|a, b| {
+		layout.masses[*b]
+			.to_f64()
+			.unwrap()
+			.total_cmp(&layout.masses[*a].to_f64().unwrap())
+	});
+
+	for zoom in 0..=options.max_zoom {
+		let factor = (1u64 << zoom) as f64;
+		let nb_tiles = 1u64 << zoom;
+		let viewport = Viewport {
+			scale: base.scale * factor,
+			offset: (base.offset.0 * factor, base.offset.1 * factor),
+		};
+		let positions: Vec<(f64, f64)> = points.iter().map(|pos| viewport.project(pos)).collect();
+		let radius = |node: usize| {
+			if style.node_size_by_mass {
+				style.node_radius * crate::io::node_size(&layout.masses[node])
+			} else {
+				style.node_radius
+			}
+		};
+		let tile_of = |coord: f64| {
+			let tile = (coord / tile_size).floor();
+			if tile >= 0.0 && tile < nb_tiles as f64 {
+				Some(tile as u64)
+			} else {
+				None
+			}
+		};
+
+		let nb_visible = if zoom == options.max_zoom {
+			nb_nodes
+		} else {
+			options
+				.nodes_at_zoom_0
+				.saturating_mul(1 << (2 * zoom).min(63))
+				.min(nb_nodes)
+		};
+		let mut visible = vec![false; nb_nodes];
+		for node in &order[..nb_visible] {
+			visible[*node] = true;
+		}
+
+		let mut tiles = HashMap::<(u64, u64), Tile>::new();
+		if style.draw_edges && zoom >= options.edges_min_zoom {
+			for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
+				if !visible[*n1] || !visible[*n2] {
+					continue;
+				}
+				let (p1, p2) = (positions[*n1], positions[*n2]);
+				if !(p1.0.is_finite() && p1.1.is_finite() && p2.0.is_finite() && p2.1.is_finite()) {
+					continue;
+				}
+				crossed_tiles(
+					(p1.0 / tile_size, p1.1 / tile_size),
+					(p2.0 / tile_size, p2.1 / tile_size),
+					|x, y| {
+						if (0..nb_tiles as i64).contains(&x) && (0..nb_tiles as i64).contains(&y) {
+							tiles
+								.entry((x as u64, y as u64))
+								.or_default()
+								.edges
+								.push(edge);
+						}
+					},
+				);
+			}
+		}
+		if style.draw_nodes {
+			// Lightest first, so the heaviest are on top
+			for node in order[..nb_visible].iter().rev().copied() {
+				let (x, y) = positions[node];
+				let r = radius(node) + 1.0;
+				let (Some(x_min), Some(y_min)) =
+					(tile_of((x - r).max(0.0)), tile_of((y - r).max(0.0)))
+				else {
+					continue;
+				};
+				let x_max = tile_of(x + r).unwrap_or(nb_tiles - 1);
+				let y_max = tile_of(y + r).unwrap_or(nb_tiles - 1);
+				for tx in x_min..=x_max {
+					for ty in y_min..=y_max {
+						tiles.entry((tx, ty)).or_default().nodes.push(node);
+					}
+				}
+			}
+		}
+
+		for ((tx, ty), tile) in tiles {
+			let mut image = Image::new(options.tile_size, options.tile_size);
+			let mut canvas = image.canvas();
+			canvas.fill(style.background.unwrap_or((255, 255, 255)));
+			let shift = |(x, y): (f64, f64)| (x - tx as f64 * tile_size, y - ty as f64 * tile_size);
+			for edge in tile.edges {
+				let (n1, n2) = layout.edges[edge];
+				canvas.draw_line(
+					shift(positions[n1]),
+					shift(positions[n2]),
+					style.edge_color,
+					style.edge_alpha,
+				);
+			}
+			for node in tile.nodes {
+				canvas.draw_disc(
+					shift(positions[node]),
+					radius(node),
+					style
+						.node_colors
+						.map_or(style.node_color, |node_colors| node_colors[node]),
+					1.0,
+				);
+			}
+
+			let tile_dir = dir.join(zoom.to_string()).join(tx.to_string());
+			std::fs::create_dir_all(&tile_dir)?;
+			image
+				.write_png(std::io::BufWriter::new(std::fs::File::create(
+					tile_dir.join(format!("{}.png", ty)),
+				)?))
+				.map_err(std::io::Error::other)?;
+		}
+	}
+
+	let mut metadata = std::fs::File::create(dir.join("metadata.json"))?;
+	writeln!(
+		metadata,
+		r#"{{"format":"png","scheme":"xyz","tileSize":{},"minZoom":0,"maxZoom":{},"nodes":{},"edges":{},"scale":{},"offset":[{},{}]}}"#,
+		options.tile_size,
+		options.max_zoom,
+		nb_nodes,
+		layout.edges.len(),
+		base.scale,
+		base.offset.0,
+		base.offset.1
+	)
+}
+
+/// Calls `visit` on every grid cell crossed by the segment from `p1` to `p2` (in cell units), in order
+///
+/// Exact grid traversal (Amanatides & Woo): each crossed cell is visited once, even if only a corner is crossed.
+fn crossed_tiles(p1: (f64, f64), p2: (f64, f64), mut visit: impl FnMut(i64, i64)) {
+	let (mut x, mut y) = (p1.0.floor() as i64, p1.1.floor() as i64);
+	let (x_end, y_end) = (p2.0.floor() as i64, p2.1.floor() as i64);
+	let (dx, dy) = (p2.0 - p1.0, p2.1 - p1.1);
+	// Segment parameter at the next vertical (resp. horizontal) grid line, and between two of them
+	let axis = |start: f64, cell: i64, d: f64| {
+		if d > 0.0 {
+			(1, (cell as f64 + 1.0 - start) / d, 1.0 / d)
+		} else if d < 0.0 {
+			(-1, (cell as f64 - start) / d, -1.0 / d)
+		} else {
+			(0, f64::INFINITY, f64::INFINITY)
+		}
+	};
+	let (step_x, mut t_x, delta_x) = axis(p1.0, x, dx);
+	let (step_y, mut t_y, delta_y) = axis(p1.1, y, dy);
+
+	visit(x, y);
+	// Stepping an axis only until it reaches the end cell guards against rounding errors
+	while (x, y) != (x_end, y_end) {
+		if x != x_end && (y == y_end || t_x < t_y) {
+			x += step_x;
+			t_x += delta_x;
+		} else {
+			y += step_y;
+			t_y += delta_y;
+		}
+		visit(x, y);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Nodes, Settings};
+
+	#[test]
+	fn test_write() {
+		let dir = std::env::temp_dir().join(format!("forceatlas2-tiles-{}", std::process::id()));
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (0, 2)],
+			Nodes::Mass(vec![3.0, 2.0, 1.0]),
+			vec![0.0, 0.0, 10.0, 0.0, 10.0, 10.0],
+			None,
+			Settings::default(),
+		);
+		write(
+			&layout,
+			&Options {
+				tile_size: 64,
+				max_zoom: 2,
+				nodes_at_zoom_0: 0,
+				..Default::default()
+			},
+			&dir,
+		)
+		.unwrap();
+
+		// No nodes nor edges before the deepest level
+		assert!(!dir.join("0").exists());
+		assert!(!dir.join("1").exists());
+		// Nodes at (32, 32), (224, 32) and (224, 224) at zoom 2
+		assert!(dir.join("2/0/0.png").exists());
+		assert!(dir.join("2/3/0.png").exists());
+		assert!(dir.join("2/3/3.png").exists());
+		// Edges
+		assert!(dir.join("2/1/0.png").exists());
+		assert!(dir.join("2/1/1.png").exists());
+		assert!(!dir.join("2/0/3.png").exists());
+
+		let metadata = std::fs::read_to_string(dir.join("metadata.json")).unwrap();
+		assert!(metadata.contains(r#""tileSize":64"#));
+		assert!(metadata.contains(r#""maxZoom":2"#));
+
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn test_crossed_tiles() {
+		let crossed = |p1, p2| {
+			let mut cells = Vec::new();
+			crossed_tiles(p1, p2, |x, y| cells.push((x, y)));
+			cells
+		};
+		assert_eq!(crossed((0.5, 0.5), (0.7, 0.2)), vec![(0, 0)]);
+		// Only crosses the corner of (1, 0) and (1, 1)
+		assert_eq!(
+			crossed((0.9, 0.0), (2.0, 1.1)),
+			vec![(0, 0), (1, 0), (1, 1), (2, 1)]
+		);
+		assert_eq!(
+			crossed((2.0, 1.1), (0.9, 0.0)),
+			vec![(2, 1), (1, 1), (1, 0), (0, 0)]
+		);
+		assert_eq!(
+			crossed((0.5, 2.5), (0.5, -0.5)),
+			vec![(0, 2), (0, 1), (0, 0), (0, -1)]
+		);
+		// Long shallow segment: one cell per column, and one more where it changes row
+		let cells = crossed((0.5, 0.5), (100.5, 1.5));
+		assert_eq!(cells.len(), 102);
+		assert_eq!(cells.last(), Some(&(100, 1)));
+	}
+}
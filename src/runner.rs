@@ -0,0 +1,557 @@
+//! Background layout computation, controlled by commands
+
+use crate::{
+	forces::{Attraction, Repulsion},
+	layout::{Layout, Settings},
+	util::*,
+};
+
+use std::{
+	sync::{
+		mpsc::{self, Receiver, Sender, TryRecvError},
+		Arc, Condvar, Mutex, PoisonError,
+	},
+	thread::{self, JoinHandle},
+};
+
+/// Command sent to a [`LayoutRunner`]'s worker
+///
+/// Invalid commands (node out of range, wrong number of coordinates, inconsistent graph or settings)
+/// are ignored and counted in [`Frame::rejected`].
+pub enum Command<T: Coord> {
+	/// Computes iterations continuously, resuming the layout if an observer stopped it
+	Start,
+	/// Stops computing iterations (pending steps are cancelled)
+	Pause,
+	/// Computes this number of iterations, then pauses
+	Step(u64),
+	/// Changes layout settings (see [`Layout::set_settings`])
+	SetSettings(Settings<T>),
+	/// Adds nodes and edges (see [`Layout::add_nodes`])
+	AddNodes {
+		edges: Vec<Edge>,
+		nodes: Nodes<T>,
+		positions: Vec<T>,
+		weights: Option<Vec<T>>,
+	},
+	/// Removes a node and its incident edges (see [`Layout::remove_node_with_edges`])
+	RemoveNode(usize),
+	/// Pins or unpins a node (see [`Layout::set_pinned`])
+	SetPinned(usize, bool),
+	/// Moves a node, e.g. when dragged by the user
+	MoveNode(usize, Vec<T>),
+	/// Replaces the layout, e.g. to restart it from new positions (the iteration count is reset)
+	Replace(Box<Layout<T>>),
+	/// Stops the worker once the pending steps are computed (sent by [`LayoutRunner::stop`])
+	Exit,
+}
+
+/// State of the layout published by a [`LayoutRunner`]
+pub struct Frame<T: Coord> {
+	/// Incremented at each publication
+	pub epoch: u64,
+	/// Number of iterations computed
+	pub iteration: u64,
+	/// Whether iterations are being computed
+	pub running: bool,
+	/// Number of invalid commands ignored so far
+	pub rejected: u64,
+	pub points: PointList<T>,
+	/// Only cloned when the graph changes
+	pub edges: Arc<Vec<Edge>>,
+	/// Only cloned when the graph changes
+	pub masses: Arc<Vec<T>>,
+}
+
+/// Handle to the latest frame, that can be sent to other threads
+pub struct FrameReader<T: Coord>(Arc<Shared<T>>);
+
+struct Shared<T: Coord> {
+	latest: Mutex<Latest<T>>,
+	/// Notified when a frame is published and when the worker stops
+	published: Condvar,
+}
+
+struct Latest<T: Coord> {
+	frame: Arc<Frame<T>>,
+	stopped: bool,
+}
+
+impl<T: Coord> Clone for FrameReader<T> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+
+impl<T: Coord> FrameReader<T> {
+	/// Latest published frame
+	///
+	/// The lock is only held while cloning the `Arc`, so the worker is never blocked by readers.
+	pub fn get(&self) -> Arc<Frame<T>> {
+		self.0.latest.lock().unwrap().frame.clone()
+	}
+
+	/// Blocks until a frame newer than `epoch` is published, e.g. to draw each new frame without polling
+	///
+	/// Returns `None` if the worker stopped before.
+	pub fn wait(&self, epoch: u64) -> Option<Arc<Frame<T>>> {
+		let latest = self
+			.0
+			.published
+			.wait_while(self.0.latest.lock().unwrap(), |latest| {
+				latest.frame.epoch <= epoch && !latest.stopped
+			})
+			.unwrap();
+		(latest.frame.epoch > epoch).then(|| latest.frame.clone())
+	}
+
+	fn publish(&self, frame: Arc<Frame<T>>) -> Arc<Frame<T>> {
+		let previous = std::mem::replace(&mut self.0.latest.lock().unwrap().frame, frame);
+		self.0.published.notify_all();
+		previous
+	}
+}
+
+/// Wakes the readers when the worker stops, even by panicking
+struct StopGuard<T: Coord>(Arc<Shared<T>>);
+
+impl<T: Coord> Drop for StopGuard<T> {
+	fn drop(&mut self) {
+		self.0
+			.latest
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.stopped = true;
+		self.0.published.notify_all();
+	}
+}
+
+/// Computes a layout in a worker thread
+///
+/// The worker owns the layout and is controlled with [`Command`]s. It publishes a [`Frame`] after each iteration,
+/// and after commands when paused. It blocks on the channel when paused, so an idle runner does not use the CPU.
+///
+/// ```no_run
+/// use forceatlas2::{Command, Layout, LayoutRunner, Nodes, Settings};
+///
+/// let edges = vec![(0, 1), (1, 2)];
+/// let layout = Layout::<f32>::from_graph(edges, Nodes::Degree(3), None, Settings::default());
+/// let runner = LayoutRunner::spawn(layout);
+/// runner.send(Command::Start);
+/// let frame = runner.frame();
+/// println!("{} iterations", frame.iteration);
+/// let layout = runner.stop();
+/// ```
+pub struct LayoutRunner<T: Coord> {
+	frame: FrameReader<T>,
+	sender: Sender<Command<T>>,
+	worker: JoinHandle<Layout<T>>,
+}
+
+impl<T: Coord + Copy + std::fmt::Debug + Send + Sync + 'static> LayoutRunner<T>
+where
+	Layout<T>: Repulsion<T> + Attraction<T>,
+{
+	/// Starts the worker, paused
+	pub fn spawn(layout: Layout<T>) -> Self {
+		let (sender, receiver) = mpsc::channel();
+		let frame = FrameReader(Arc::new(Shared {
+			latest: Mutex::new(Latest {
+				frame: Arc::new(Frame {
+					epoch: 0,
+					iteration: 0,
+					running: false,
+					rejected: 0,
+					points: layout.points.clone(),
+					edges: Arc::new(layout.edges.clone()),
+					masses: Arc::new(layout.masses.clone()),
+				}),
+				stopped: false,
+			}),
+			published: Condvar::new(),
+		}));
+		let worker = thread::spawn({
+			let frame = frame.clone();
+			move || {
+				let _guard = StopGuard(frame.0.clone());
+				Worker::new(layout, frame).run(receiver)
+			}
+		});
+		Self {
+			frame,
+			sender,
+			worker,
+		}
+	}
+}
+
+impl<T: Coord> LayoutRunner<T> {
+	/// Sends a command to the worker
+	///
+	/// Commands are applied in order, between iterations.
+	pub fn send(&self, command: Command<T>) {
+		// The worker only exits on `Command::Exit` or when panicking
+		self.sender.send(command).ok();
+	}
+
+	/// Sender that can be moved to other threads
+	pub fn sender(&self) -> Sender<Command<T>> {
+		self.sender.clone()
+	}
+
+	/// Latest published frame
+	pub fn frame(&self) -> Arc<Frame<T>> {
+		self.frame.get()
+	}
+
+	/// Handle to the latest frame, that can be moved to other threads
+	pub fn frame_reader(&self) -> FrameReader<T> {
+		self.frame.clone()
+	}
+
+	/// Stops the worker after the pending commands and returns the layout
+	///
+	/// # Panics
+	/// Panics if the worker panicked.
+	pub fn stop(self) -> Layout<T> {
+		self.send(Command::Exit);
+		self.worker.join().expect("Layout worker panicked")
+	}
+}
+
+struct Worker<T: Coord> {
+	dirty_graph: bool,
+	epoch: u64,
+	exit: bool,
+	frame: FrameReader<T>,
+	iteration: u64,
+	layout: Layout<T>,
+	rejected: u64,
+	running: bool,
+	spare: SpareFrame<Frame<T>>,
+	steps: u64,
+}
+
+impl<T: Coord + Copy + std::fmt::Debug> Worker<T>
+where
+	Layout<T>: Repulsion<T> + Attraction<T>,
+{
+	fn new(layout: Layout<T>, frame: FrameReader<T>) -> Self {
+		Self {
+			dirty_graph: false,
+			epoch: 0,
+			exit: false,
+			frame,
+			iteration: 0,
+			layout,
+			rejected: 0,
+			running: false,
+			spare: SpareFrame::default(),
+			steps: 0,
+		}
+	}
+
+	fn run(mut self, receiver: Receiver<Command<T>>) -> Layout<T> {
+		let mut dirty = false;
+		loop {
+			// Apply every pending command before the next iteration
+			match receiver.try_recv() {
+				Ok(command) => {
+					self.apply(command);
+					dirty = true;
+					continue;
+				}
+				Err(TryRecvError::Empty) => {}
+				Err(TryRecvError::Disconnected) => self.exit = true,
+			}
+
+			if self.steps != 0 || (self.running && !self.exit) {
+				self.layout.iteration();
+				self.iteration += 1;
+				self.steps = self.steps.saturating_sub(1);
+				if self.layout.is_stopped() {
+					// An observer requested a stop
+					self.running = false;
+					self.steps = 0;
+				}
+				self.publish();
+				dirty = false;
+				continue;
+			}
+
+			if dirty {
+				self.publish();
+				dirty = false;
+			}
+			if self.exit {
+				return self.layout;
+			}
+			match receiver.recv() {
+				Ok(command) => {
+					self.apply(command);
+					dirty = true;
+				}
+				Err(_) => self.exit = true,
+			}
+		}
+	}
+
+	/// Whether the command can be applied without panicking
+	fn is_valid(&self, command: &Command<T>) -> bool {
+		let nb_nodes = self.layout.masses.len();
+		let dimensions = self.layout.settings.dimensions;
+		match command {
+			Command::Start
+			| Command::Pause
+			| Command::Step(_)
+			| Command::Replace(_)
+			| Command::Exit => true,
+			Command::SetSettings(settings) => {
+				#[cfg(feature = "parallel")]
+				if settings.chunk_size == Some(0) {
+					return false;
+				}
+				settings.dimensions == dimensions
+			}
+			Command::AddNodes {
+				edges,
+				nodes,
+				positions,
+				weights,
+			} => {
+				let new_nb_nodes = nb_nodes + nodes.len();
+				positions.len() == nodes.len() * dimensions
+					&& edges.iter().all(|(n1, n2)| n1 < n2 && *n2 < new_nb_nodes)
+					&& match (weights, &self.layout.weights) {
+						(Some(new_weights), Some(_)) => new_weights.len() == edges.len(),
+						(None, None) => true,
+						_ => false,
+					}
+			}
+			Command::RemoveNode(node) | Command::SetPinned(node, _) => *node < nb_nodes,
+			Command::MoveNode(node, position) => *node < nb_nodes && position.len() == dimensions,
+		}
+	}
+
+	fn apply(&mut self, command: Command<T>) {
+		if !self.is_valid(&command) {
+			self.rejected += 1;
+			return;
+		}
+		match command {
+			Command::Start => {
+				self.layout.resume();
+				self.running = true;
+			}
+			Command::Pause => {
+				self.running = false;
+				self.steps = 0;
+			}
+			Command::Step(steps) => self.steps += steps,
+			Command::SetSettings(settings) => self.layout.set_settings(settings),
+			Command::AddNodes {
+				edges,
+				nodes,
+				positions,
+				weights,
+			} => {
+				self.layout
+					.add_nodes(&edges, nodes, &positions, weights.as_deref());
+				self.dirty_graph = true;
+			}
+			Command::RemoveNode(node) => {
+				self.layout.remove_node_with_edges(node);
+				self.dirty_graph = true;
+			}
+			Command::SetPinned(node, pinned) => self.layout.set_pinned(node, pinned),
+			Command::MoveNode(node, position) => {
+				self.layout.points.set(node, &position);
+				// Forget the momentum so the node does not jump back
+				self.layout.speeds.get_mut(node).fill(T::zero());
+				self.layout.old_speeds.get_mut(node).fill(T::zero());
+			}
+			Command::Replace(layout) => {
+				self.layout = *layout;
+				self.iteration = 0;
+				self.dirty_graph = true;
+			}
+			Command::Exit => {
+				self.exit = true;
+				self.running = false;
+			}
+		}
+	}
+
+	fn publish(&mut self) {
+		self.epoch += 1;
+		let (edges, masses) = if self.dirty_graph {
+			self.dirty_graph = false;
+			(
+				Arc::new(self.layout.edges.clone()),
+				Arc::new(self.layout.masses.clone()),
+			)
+		} else {
+			let frame = self.frame.get();
+			(frame.edges.clone(), frame.masses.clone())
+		};
+		let running = self.running || self.steps != 0;
+		let frame = match self.spare.take() {
+			Some(mut frame)
+				if frame.points.dimensions == self.layout.points.dimensions
+					&& frame.points.points.len() == self.layout.points.points.len() =>
+			{
+				frame.epoch = self.epoch;
+				frame.iteration = self.iteration;
+				frame.running = running;
+				frame.rejected = self.rejected;
+				frame
+					.points
+					.points
+					.copy_from_slice(&self.layout.points.points);
+				frame.edges = edges;
+				frame.masses = masses;
+				frame
+			}
+			_ => Frame {
+				epoch: self.epoch,
+				iteration: self.iteration,
+				running,
+				rejected: self.rejected,
+				points: self.layout.points.clone(),
+				edges,
+				masses,
+			},
+		};
+		self.spare.set(self.frame.publish(Arc::new(frame)));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_runner() {
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (0, 2), (1, 2)],
+			Nodes::Degree(3),
+			vec![0.0, 0.0, 2.0, 0.0, 1.0, 2.0],
+			None,
+			Settings::default(),
+		);
+		let runner = LayoutRunner::spawn(layout);
+		assert_eq!(runner.frame().iteration, 0);
+
+		runner.send(Command::SetPinned(0, true));
+		runner.send(Command::MoveNode(0, vec![5.0, 5.0]));
+		runner.send(Command::Step(10));
+		runner.send(Command::AddNodes {
+			edges: vec![(2, 3)],
+			nodes: Nodes::Degree(1),
+			positions: vec![2.0, 2.0],
+			weights: None,
+		});
+		runner.send(Command::Step(5));
+		let layout = runner.stop();
+
+		// The worker exits once all the steps have been computed
+		assert_eq!(layout.masses.len(), 4);
+		assert_eq!(layout.points.get(0), [5.0, 5.0]);
+		assert_ne!(layout.points.get(3), [2.0, 2.0]);
+	}
+
+	#[test]
+	fn test_frames() {
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			Nodes::Degree(2),
+			vec![0.0, 0.0, 1.0, 0.0],
+			None,
+			Settings::default(),
+		);
+		let runner = LayoutRunner::spawn(layout);
+		let reader = runner.frame_reader();
+		runner.send(Command::Step(3));
+		runner.send(Command::RemoveNode(1));
+		runner.send(Command::Step(1));
+		let layout = runner.stop();
+
+		let frame = reader.get();
+		assert_eq!(reader.wait(frame.epoch).map(|frame| frame.epoch), None);
+		assert_eq!(frame.iteration, 4);
+		assert!(!frame.running);
+		assert_eq!(frame.points.points, layout.points.points);
+		assert!(frame.edges.is_empty());
+		assert_eq!(frame.masses.len(), 1);
+
+		// Same number of coordinates, in another dimension: the previous frame cannot be reused
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (1, 2)],
+			Nodes::Degree(3),
+			vec![0.0, 0.0, 1.0, 0.0, 2.0, 0.0],
+			None,
+			Settings::default(),
+		);
+		let runner = LayoutRunner::spawn(layout);
+		let reader = runner.frame_reader();
+		runner.send(Command::Step(1));
+		reader.wait(0);
+		runner.send(Command::Replace(Box::new(Layout::from_position_graph(
+			vec![(0, 1)],
+			Nodes::Degree(2),
+			vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+			None,
+			Settings {
+				dimensions: 3,
+				..Default::default()
+			},
+		))));
+		runner.send(Command::Step(1));
+		let layout = runner.stop();
+		let frame = reader.get();
+		assert_eq!(frame.iteration, 1);
+		assert_eq!(frame.points.dimensions, 3);
+		assert_eq!(frame.points.points, layout.points.points);
+		assert_eq!(*frame.edges, vec![(0, 1)]);
+	}
+
+	#[test]
+	fn test_invalid_commands() {
+		let layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			Nodes::Degree(2),
+			vec![0.0, 0.0, 1.0, 0.0],
+			None,
+			Settings::default(),
+		);
+		let runner = LayoutRunner::spawn(layout);
+		runner.send(Command::RemoveNode(2));
+		runner.send(Command::SetPinned(5, true));
+		runner.send(Command::MoveNode(0, vec![1.0, 2.0, 3.0]));
+		runner.send(Command::SetSettings(Settings {
+			dimensions: 3,
+			..Default::default()
+		}));
+		runner.send(Command::AddNodes {
+			edges: vec![(1, 3)],
+			nodes: Nodes::Degree(1),
+			positions: vec![2.0, 2.0],
+			weights: None,
+		});
+		runner.send(Command::AddNodes {
+			edges: vec![(1, 2)],
+			nodes: Nodes::Degree(1),
+			positions: vec![2.0, 2.0],
+			weights: Some(vec![1.0]),
+		});
+		runner.send(Command::Step(2));
+		let reader = runner.frame_reader();
+		let layout = runner.stop();
+
+		// The worker is still alive and computed the steps
+		let frame = reader.get();
+		assert_eq!(frame.rejected, 6);
+		assert_eq!(frame.iteration, 2);
+		assert_eq!(layout.masses.len(), 2);
+	}
+}
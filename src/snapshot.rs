@@ -0,0 +1,178 @@
+use crate::{
+	forces::{self, Attraction, Repulsion},
+	layout::{Layout, Settings},
+	util::*,
+};
+
+/// Owned copy of a layout's state, that can be stored to resume the layout later
+///
+/// With the `serde` feature, it implements `Serialize` and `Deserialize`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutSnapshot<T: Coord> {
+	/// See [`Layout::set_clusters`]
+	pub clusters: Vec<usize>,
+	pub edges: Vec<Edge>,
+	pub masses: Vec<T>,
+	/// `MassMode::Derived` is stored as `MassMode::Fixed`
+	pub mass_mode: MassMode<T>,
+	pub old_speeds: PointList<T>,
+	/// See [`Layout::set_pinned`]
+	pub pinned: Vec<bool>,
+	pub points: PointList<T>,
+	pub settings: Settings<T>,
+	pub speeds: PointList<T>,
+	pub weights: Option<Vec<T>>,
+}
+
+impl<T: Coord + std::fmt::Debug> Layout<T>
+where
+	Layout<T>: Repulsion<T> + Attraction<T>,
+{
+	/// Copies the layout's state
+	///
+	/// A derived mass mode cannot be stored: it is replaced by `MassMode::Fixed`,
+	/// and can be restored with [`Layout::set_mass_mode`].
+	pub fn snapshot(&self) -> LayoutSnapshot<T> {
+		LayoutSnapshot {
+			clusters: self.clusters.clone(),
+			edges: self.edges.clone(),
+			masses: self.masses.clone(),
+			mass_mode: match self.mass_mode {
+				MassMode::Derived(_) => MassMode::Fixed,
+				mass_mode => mass_mode,
+			},
+			old_speeds: self.old_speeds.clone(),
+			pinned: self.pinned.clone(),
+			points: self.points.clone(),
+			settings: self.settings.clone(),
+			speeds: self.speeds.clone(),
+			weights: self.weights.clone(),
+		}
+	}
+
+	/// Instantiates a layout from a snapshot, continuing where the snapshot was taken
+	///
+	/// # Panics
+	/// Panics if the snapshot is inconsistent (lengths or dimensions mismatch,
+	/// or an edge `(n1, n2)` not respecting `n1 < n2 < nodes`).
+	pub fn from_snapshot(snapshot: LayoutSnapshot<T>) -> Self {
+		let LayoutSnapshot {
+			clusters,
+			edges,
+			masses,
+			mass_mode,
+			old_speeds,
+			pinned,
+			points,
+			settings,
+			speeds,
+			weights,
+		} = snapshot;
+
+		let nb = masses.len() * settings.dimensions;
+		for list in [&points, &speeds, &old_speeds] {
+			assert_eq!(list.dimensions, settings.dimensions);
+			assert_eq!(list.points.len(), nb);
+		}
+		assert!(edges.iter().all(|(n1, n2)| n1 < n2 && *n2 < masses.len()));
+		if let Some(weights) = &weights {
+			assert_eq!(weights.len(), edges.len());
+		}
+		assert!(clusters.len() <= masses.len());
+		assert!(clusters.iter().all(|cluster| *cluster < clusters.len()));
+		assert!(pinned.len() <= masses.len());
+
+		Self {
+			cluster_buffers: Default::default(),
+			clusters,
+			constraints: Vec::new(),
+			degrees: Degrees::from_edges(masses.len(), &edges, weights.as_deref()),
+			edges,
+			masses,
+			mass_mode,
+			old_speeds,
+			points,
+			speeds,
+			forces: Vec::new(),
+			observers: Default::default(),
+			pinned,
+			#[cfg(feature = "publish")]
+			publisher: None,
+			weights,
+			fn_attraction: Self::choose_attraction(&settings),
+			fn_gravity: forces::choose_gravity(&settings),
+			fn_repulsion: Self::choose_repulsion(&settings),
+			settings,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_snapshot_resume() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (0, 2), (1, 3)],
+			Nodes::Degree(4),
+			vec![-1.0, -1.0, 1.0, 1.0, 0.5, -2.0, 1.5, 0.0],
+			Some(vec![1.0, 2.0, 0.5]),
+			Settings::default(),
+		);
+		layout.set_pinned(2, true);
+		for _ in 0..5 {
+			layout.iteration();
+		}
+
+		let mut resumed = Layout::from_snapshot(layout.snapshot());
+		assert!(resumed.is_pinned(2));
+		for _ in 0..5 {
+			layout.iteration();
+			resumed.iteration();
+		}
+		assert_eq!(layout.points.points, resumed.points.points);
+		assert_eq!(layout.speeds.points, resumed.speeds.points);
+		assert_eq!(resumed.points.get(2), &[0.5, -2.0]);
+	}
+
+	#[test]
+	#[should_panic]
+	fn test_snapshot_reversed_edge() {
+		let layout =
+			Layout::<f64>::from_graph(vec![(0, 1)], Nodes::Degree(2), None, Settings::default());
+		let mut snapshot = layout.snapshot();
+		snapshot.edges[0] = (1, 0);
+		Layout::from_snapshot(snapshot);
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn test_snapshot_serde() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (0, 2), (1, 3)],
+			Nodes::Degree(4),
+			vec![-1.0, -1.0, 1.0, 1.0, 0.5, -2.0, 1.5, 0.0],
+			Some(vec![1.0, 2.0, 0.5]),
+			Settings::default(),
+		);
+		layout.set_clusters(vec![0, 0, 1, 1]);
+		layout.set_pinned(1, true);
+		for _ in 0..5 {
+			layout.iteration();
+		}
+
+		let json = serde_json::to_string(&layout.snapshot()).unwrap();
+		let snapshot: LayoutSnapshot<f64> = serde_json::from_str(&json).unwrap();
+		assert_eq!(snapshot.clusters, vec![0, 0, 1, 1]);
+		assert_eq!(snapshot.pinned, vec![false, true]);
+		let mut resumed = Layout::from_snapshot(snapshot);
+		for _ in 0..5 {
+			layout.iteration();
+			resumed.iteration();
+		}
+		assert_eq!(layout.points.points, resumed.points.points);
+		assert_eq!(layout.speeds.points, resumed.speeds.points);
+	}
+}
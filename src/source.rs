@@ -0,0 +1,169 @@
+use crate::util::*;
+
+use std::borrow::Cow;
+
+/// Graph representation a layout can be built from (see [`Layout::from_source`](crate::Layout::from_source))
+///
+/// Only the node count and the edges are required: by default, masses are `Nodes::Degree` and positions are random.
+pub trait GraphSource<T: Coord> {
+	type Edges<'a>: Iterator<Item = (Edge, Option<T>)>
+	where
+		Self: 'a;
+
+	fn nb_nodes(&self) -> usize;
+
+	/// Edges `(n1, n2)`, respecting `n1 < n2`, with their weight
+	///
+	/// The weight is ignored if the graph is not weighted, and is 1 if `None` in a weighted graph.
+	fn edges(&self) -> Self::Edges<'_>;
+
+	/// Whether the layout is weighted
+	fn weighted(&self) -> bool;
+
+	/// Node masses, or how to derive them from the graph
+	fn nodes(&self) -> Nodes<T> {
+		Nodes::Degree(self.nb_nodes())
+	}
+
+	/// Initial positions, e.g. `[x1, y1, x2, y2, ...]`
+	fn positions(&self) -> Option<Cow<'_, [T]>> {
+		None
+	}
+}
+
+/// Iterator over edges stored in slices
+pub struct SliceEdges<'a, T> {
+	edges: std::slice::Iter<'a, Edge>,
+	weights: Option<std::slice::Iter<'a, T>>,
+}
+
+impl<'a, T> SliceEdges<'a, T> {
+	pub fn new(edges: &'a [Edge], weights: Option<&'a [T]>) -> Self {
+		if let Some(weights) = weights {
+			assert_eq!(weights.len(), edges.len());
+		}
+		Self {
+			edges: edges.iter(),
+			weights: weights.map(<[T]>::iter),
+		}
+	}
+}
+
+impl<'a, T: Clone> Iterator for SliceEdges<'a, T> {
+	type Item = (Edge, Option<T>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let edge = *self.edges.next()?;
+		Some((
+			edge,
+			self.weights
+				.as_mut()
+				.and_then(|weights| weights.next().cloned()),
+		))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.edges.size_hint()
+	}
+}
+
+impl<T: Coord> GraphSource<T> for crate::io::Graph<T> {
+	type Edges<'a>
+		= SliceEdges<'a, T>
+	where
+		Self: 'a;
+
+	fn nb_nodes(&self) -> usize {
+		self.nb_nodes
+	}
+
+	fn edges(&self) -> Self::Edges<'_> {
+		SliceEdges::new(&self.edges, self.weights.as_deref())
+	}
+
+	fn weighted(&self) -> bool {
+		self.weights.is_some()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Layout, Settings};
+
+	/// Adjacency lists, with a constant mass
+	struct Adjacency {
+		neighbors: Vec<Vec<usize>>,
+		positions: Vec<f64>,
+	}
+
+	impl GraphSource<f64> for Adjacency {
+		type Edges<'a> = Box<dyn Iterator<Item = (Edge, Option<f64>)> + 'a>;
+
+		fn nb_nodes(&self) -> usize {
+			self.neighbors.len()
+		}
+
+		fn edges(&self) -> Self::Edges<'_> {
+			Box::new(
+				self.neighbors
+					.iter()
+					.enumerate()
+					.flat_map(|(n1, neighbors)| {
+						neighbors
+							.iter()
+							.filter(move |n2| **n2 > n1)
+							.map(move |n2| ((n1, *n2), None))
+					}),
+			)
+		}
+
+		fn weighted(&self) -> bool {
+			false
+		}
+
+		fn nodes(&self) -> Nodes<f64> {
+			Nodes::Mass(vec![2.0; self.nb_nodes()])
+		}
+
+		fn positions(&self) -> Option<Cow<'_, [f64]>> {
+			Some(Cow::Borrowed(&self.positions))
+		}
+	}
+
+	#[test]
+	fn test_from_source() {
+		let source = Adjacency {
+			neighbors: vec![vec![1, 2], vec![0], vec![0]],
+			positions: vec![0.0, 0.0, 1.0, 1.0, -1.0, 1.0],
+		};
+		let layout = Layout::from_positioned_source(&source, Settings::default());
+		assert_eq!(layout.edges, vec![(0, 1), (0, 2)]);
+		assert_eq!(layout.weights, None);
+		assert_eq!(layout.masses, vec![2.0; 3]);
+		assert_eq!(layout.points.points, source.positions);
+
+		let graph = crate::io::Graph {
+			edges: vec![(0, 1), (1, 2)],
+			labels: None,
+			nb_nodes: 3,
+			weights: Some(vec![1.0, 3.0]),
+		};
+		assert_eq!(
+			graph.edges().collect::<Vec<_>>(),
+			vec![((0, 1), Some(1.0)), ((1, 2), Some(3.0))]
+		);
+	}
+
+	#[test]
+	#[should_panic]
+	fn test_from_source_reversed_edge() {
+		let graph = crate::io::Graph {
+			edges: vec![(1, 0)],
+			labels: None,
+			nb_nodes: 2,
+			weights: None,
+		};
+		Layout::<f64>::from_source_with_positions(&graph, vec![0.0; 4], Settings::default());
+	}
+}
@@ -8,6 +8,7 @@ use maths_traits::{
 use num_traits::cast::{FromPrimitive, NumCast};
 #[cfg(feature = "rand")]
 use rand::Rng;
+use std::sync::Arc;
 
 pub trait Coord = Clone
 	+ Div<Self, Output = Self>
@@ -35,9 +36,162 @@ pub fn clone_slice_mut<T: Clone>(s: &[T]) -> Vec<T> {
 
 pub type Edge = (usize, usize);
 
+/// Node masses, or the number of nodes and how to derive their masses from the graph
+///
+/// Derived masses are computed at construction and kept up to date when edges are added or removed.
+#[derive(Clone)]
 pub enum Nodes<T> {
 	Mass(Vec<T>),
+	/// Mass is the number of incident edges
 	Degree(usize),
+	/// Mass is the sum of the weights of incident edges (strength)
+	///
+	/// Equivalent to `Degree` for unweighted graphs.
+	WeightedDegree(usize),
+	/// Mass is `ln(1 + degree)`
+	LogDegree(usize),
+	/// Mass is computed by a function of `(degree, strength)`
+	Derived(usize, fn(usize, T) -> T),
+}
+
+impl<T> Nodes<T> {
+	/// Number of nodes
+	pub fn len(&self) -> usize {
+		match self {
+			Nodes::Mass(masses) => masses.len(),
+			Nodes::Degree(nb_nodes)
+			| Nodes::WeightedDegree(nb_nodes)
+			| Nodes::LogDegree(nb_nodes)
+			| Nodes::Derived(nb_nodes, _) => *nb_nodes,
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn mass_mode(&self) -> MassMode<T> {
+		match self {
+			Nodes::Mass(_) => MassMode::Fixed,
+			Nodes::Degree(_) => MassMode::Degree,
+			Nodes::WeightedDegree(_) => MassMode::WeightedDegree,
+			Nodes::LogDegree(_) => MassMode::LogDegree,
+			Nodes::Derived(_, f) => MassMode::Derived(*f),
+		}
+	}
+}
+
+/// How a layout updates node masses when its graph changes
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MassMode<T> {
+	/// Masses are given by the user and never updated
+	Fixed,
+	Degree,
+	WeightedDegree,
+	LogDegree,
+	/// Cannot be serialized
+	#[cfg_attr(feature = "serde", serde(skip))]
+	Derived(fn(usize, T) -> T),
+}
+
+impl<T> Clone for MassMode<T> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+impl<T> Copy for MassMode<T> {}
+
+impl<T> PartialEq for MassMode<T> {
+	fn eq(&self, other: &Self) -> bool {
+		match (self, other) {
+			(MassMode::Derived(f1), MassMode::Derived(f2)) => *f1 as usize == *f2 as usize,
+			_ => std::mem::discriminant(self) == std::mem::discriminant(other),
+		}
+	}
+}
+
+impl<T: Coord> MassMode<T> {
+	/// Mass of a node having the given degree and strength, or `None` if masses are fixed
+	pub fn mass(&self, degree: usize, strength: T) -> Option<T> {
+		match self {
+			MassMode::Fixed => None,
+			MassMode::Degree => Some(<T as NumCast>::from(degree).unwrap()),
+			MassMode::WeightedDegree => Some(strength),
+			MassMode::LogDegree => Some(<T as NumCast>::from(degree).unwrap().ln_1p()),
+			MassMode::Derived(f) => Some(f(degree, strength)),
+		}
+	}
+}
+
+/// Degree and strength (sum of incident edge weights) of every node
+#[derive(Clone)]
+pub struct Degrees<T> {
+	pub degrees: Vec<usize>,
+	pub strengths: Vec<T>,
+}
+
+impl<T: Coord> Degrees<T> {
+	pub fn new(nb_nodes: usize) -> Self {
+		Self {
+			degrees: vec![0; nb_nodes],
+			strengths: (0..nb_nodes).map(|_| T::zero()).collect(),
+		}
+	}
+
+	pub fn from_edges(nb_nodes: usize, edges: &[Edge], weights: Option<&[T]>) -> Self {
+		let mut degrees = Self::new(nb_nodes);
+		for (edge, (n1, n2)) in edges.iter().enumerate() {
+			degrees.add_edge(*n1, *n2, weights.map(|weights| &weights[edge]));
+		}
+		degrees
+	}
+
+	pub fn extend(&mut self, nb_nodes: usize) {
+		self.degrees.extend((0..nb_nodes).map(|_| 0));
+		self.strengths.extend((0..nb_nodes).map(|_| T::zero()));
+	}
+
+	pub fn add_edge(&mut self, n1: usize, n2: usize, weight: Option<&T>) {
+		let weight = weight.map_or_else(T::one, T::clone);
+		self.degrees[n1] += 1;
+		self.degrees[n2] += 1;
+		self.strengths[n1] += weight.clone();
+		self.strengths[n2] += weight;
+	}
+
+	pub fn remove_edge(&mut self, n1: usize, n2: usize, weight: Option<&T>) {
+		let weight = weight.map_or_else(T::one, T::clone);
+		self.degrees[n1] -= 1;
+		self.degrees[n2] -= 1;
+		self.strengths[n1] -= weight.clone();
+		self.strengths[n2] -= weight;
+	}
+
+	pub fn remove(&mut self, node: usize) {
+		self.degrees.remove(node);
+		self.strengths.remove(node);
+	}
+
+	pub fn mass(&self, mode: &MassMode<T>, node: usize) -> Option<T> {
+		mode.mass(self.degrees[node], self.strengths[node].clone())
+	}
+}
+
+/// Returns masses, mass mode and degrees
+pub(crate) fn init_masses<T: Coord>(
+	nodes: Nodes<T>,
+	edges: &[Edge],
+	weights: Option<&[T]>,
+) -> (Vec<T>, MassMode<T>, Degrees<T>) {
+	let mode = nodes.mass_mode();
+	let degrees = Degrees::from_edges(nodes.len(), edges, weights);
+	let masses = match nodes {
+		Nodes::Mass(masses) => masses,
+		_ => (0..degrees.degrees.len())
+			.map(|node| degrees.mass(&mode, node).unwrap())
+			.collect(),
+	};
+	(masses, mode, degrees)
 }
 
 pub fn norm<T: Coord>(n: &Position<T>) -> T {
@@ -121,6 +275,7 @@ impl<'a, T> Iterator for PointIterMut<'a, T> {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointList<T: Coord> {
 	/// Number of coordinates in a vector
 	pub dimensions: usize,
@@ -262,6 +417,40 @@ where
 	v
 }
 
+/// Random positions of `nb_nodes` nodes, e.g. `[x1, y1, x2, y2, ...]` (see [`sample_unit_ncube`])
+#[cfg(feature = "rand")]
+pub(crate) fn random_positions<T>(nb_nodes: usize, dimensions: usize) -> Vec<T>
+where
+	rand::distributions::Standard: rand::distributions::Distribution<T>,
+	T: Coord + rand::distributions::uniform::SampleUniform,
+{
+	let mut rng = rand::thread_rng();
+	(0..nb_nodes)
+		.flat_map(|_| sample_unit_ncube(&mut rng, dimensions))
+		.collect()
+}
+
+/// Previously published frame, whose buffers are reused when no reader holds it anymore
+pub(crate) struct SpareFrame<F>(Option<Arc<F>>);
+
+impl<F> Default for SpareFrame<F> {
+	fn default() -> Self {
+		Self(None)
+	}
+}
+
+impl<F> SpareFrame<F> {
+	/// Takes the frame back, if no reader holds it anymore
+	pub(crate) fn take(&mut self) -> Option<F> {
+		self.0.take().and_then(|frame| Arc::try_unwrap(frame).ok())
+	}
+
+	/// Keeps the frame replaced by a newer one
+	pub(crate) fn set(&mut self, frame: Arc<F>) {
+		self.0 = Some(frame);
+	}
+}
+
 pub(crate) struct SendPtr<T>(pub std::ptr::NonNull<T>);
 
 impl<T> Copy for SendPtr<T> {}
@@ -0,0 +1,60 @@
+//! Builds the shared library and the C API test (`tests/capi/test.c`) against it, and runs it
+//!
+//! The C compiler is `$CC`, or `cc` by default.
+
+#![cfg(all(feature = "capi", unix))]
+
+use std::{env, path::Path, process::Command};
+
+#[test]
+fn test_capi_c() {
+	let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+	// The test binary is in `target/<profile>/deps`
+	let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+	let exe = deps.join("capi_test");
+
+	// In a separate target directory, as Cargo locks this one while testing
+	let target = deps.parent().unwrap().parent().unwrap().join("capi");
+	let status = Command::new(env!("CARGO"))
+		.args([
+			"rustc",
+			"--lib",
+			"--features",
+			"capi",
+			"--crate-type",
+			"cdylib",
+		])
+		.arg("--manifest-path")
+		.arg(root.join("Cargo.toml"))
+		.arg("--target-dir")
+		.arg(&target)
+		.status()
+		.expect("Cannot run Cargo");
+	assert!(status.success(), "Cannot build the shared library");
+	let lib = target.join("debug");
+
+	let cc = env::var("CC").unwrap_or_else(|_| "cc".into());
+	let status = Command::new(cc)
+		.args(["-Wall", "-Werror", "-DNDEBUG", "-I"])
+		.arg(root.join("include"))
+		.arg(root.join("tests/capi/test.c"))
+		.arg("-L")
+		.arg(&lib)
+		.args(["-lforceatlas2", "-o"])
+		.arg(&exe)
+		.status()
+		.expect("Cannot run the C compiler");
+	assert!(status.success(), "Cannot compile the C test");
+
+	let output = Command::new(&exe)
+		.env("LD_LIBRARY_PATH", &lib)
+		.env("DYLD_LIBRARY_PATH", &lib)
+		.output()
+		.unwrap();
+	assert!(
+		output.status.success(),
+		"{}",
+		String::from_utf8_lossy(&output.stderr)
+	);
+	assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 4);
+}
@@ -0,0 +1,87 @@
+/*
+ * C API test, built and run by `cargo test --features capi` (see tests/capi.rs)
+ *
+ * Manually:
+ * cargo rustc --release --lib --features capi --crate-type cdylib
+ * cc -Wall -Werror -Iinclude tests/capi/test.c -Ltarget/release -lforceatlas2 -o target/capi_test
+ * LD_LIBRARY_PATH=target/release target/capi_test
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "forceatlas2.h"
+
+/* Unlike assert, not disabled by NDEBUG */
+#define CHECK(cond) \
+	do { \
+		if (!(cond)) { \
+			fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+			exit(1); \
+		} \
+	} while (0)
+
+int main(void) {
+	const size_t edges[] = {0, 1, 1, 2, 2, 0, 2, 3};
+	const double weights[] = {1.0, 1.0, 2.0, 0.5};
+	const double positions[] = {0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0};
+	Fa2Layout *layout = NULL;
+	Fa2Status status;
+
+	/* Errors */
+	status = fa2_layout_new(edges, 4, weights, 3, 2, NULL, &layout);
+	CHECK(status == FA2_STATUS_INVALID_ARGUMENT);
+	CHECK(layout == NULL);
+	status = fa2_layout_new(edges, 4, NULL, 4, 2, NULL, NULL);
+	CHECK(status == FA2_STATUS_NULL_POINTER);
+	status = fa2_layout_iteration(NULL);
+	CHECK(status == FA2_STATUS_NULL_POINTER);
+
+	status = fa2_layout_new(edges, 4, weights, 4, 2, positions, &layout);
+	CHECK(status == FA2_STATUS_OK);
+	CHECK(layout != NULL);
+
+	size_t nb_nodes = 0, dimensions = 0;
+	status = fa2_layout_size(layout, &nb_nodes, &dimensions);
+	CHECK(status == FA2_STATUS_OK);
+	CHECK(nb_nodes == 4 && dimensions == 2);
+
+	status = fa2_layout_set_kr(layout, 0.5);
+	CHECK(status == FA2_STATUS_OK);
+	status = fa2_layout_set_kg(layout, 0.1);
+	CHECK(status == FA2_STATUS_OK);
+	status = fa2_layout_set_lin_log(layout, true);
+	CHECK(status == FA2_STATUS_OK);
+	status = fa2_layout_set_prevent_overlapping(layout, 0.1, 100.0);
+	CHECK(status == FA2_STATUS_OK);
+
+	bool pinned = false;
+	status = fa2_layout_set_pinned(layout, 0, true);
+	CHECK(status == FA2_STATUS_OK);
+	status = fa2_layout_set_pinned(layout, 4, true);
+	CHECK(status == FA2_STATUS_INVALID_ARGUMENT);
+	status = fa2_layout_is_pinned(layout, 0, &pinned);
+	CHECK(status == FA2_STATUS_OK && pinned);
+
+	double *buffer = NULL;
+	size_t len = 0;
+	status = fa2_layout_positions(layout, &buffer, &len);
+	CHECK(status == FA2_STATUS_OK);
+	CHECK(len == 8);
+
+	for (int i = 0; i < 100; i++) {
+		status = fa2_layout_iteration(layout);
+		CHECK(status == FA2_STATUS_OK);
+	}
+
+	/* The buffer is updated in place, and the pinned node did not move */
+	CHECK(buffer[0] == 0.0 && buffer[1] == 0.0);
+	CHECK(buffer[2] != 1.0 || buffer[3] != 0.0);
+	for (size_t node = 0; node < nb_nodes; node++) {
+		printf("%zu: %f %f\n", node, buffer[node * 2], buffer[node * 2 + 1]);
+	}
+
+	fa2_layout_free(layout);
+	fa2_layout_free(NULL);
+	return 0;
+}